@@ -0,0 +1,36 @@
+//! A reusable buffer for building spreads.
+//!
+//! Pairing strikes fills one vector per underlying and expiry, sized up
+//! front for every possible pair. Allocating and freeing that on each scan
+//! churns the allocator, and WASM linear memory never shrinks, so the buffer
+//! is kept between scans and handed back out instead. `reset` frees it.
+
+use crate::CreditSpread;
+use std::cell::RefCell;
+
+thread_local! {
+    static SPREADS: RefCell<Vec<CreditSpread>> = const { RefCell::new(Vec::new()) };
+}
+
+/// An empty buffer with room for at least `capacity` spreads.
+pub fn take(capacity: usize) -> Vec<CreditSpread> {
+    let mut spreads = SPREADS.with_borrow_mut(std::mem::take);
+    spreads.reserve(capacity);
+    spreads
+}
+
+/// Returns a buffer for reuse, keeping whichever of it and the pooled one
+/// is larger.
+pub fn recycle(mut spreads: Vec<CreditSpread>) {
+    spreads.clear();
+    SPREADS.with_borrow_mut(|pooled| {
+        if spreads.capacity() > pooled.capacity() {
+            *pooled = spreads;
+        }
+    });
+}
+
+/// Frees the pooled buffer.
+pub fn release() {
+    SPREADS.with_borrow_mut(|pooled| *pooled = Vec::new());
+}
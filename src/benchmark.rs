@@ -0,0 +1,57 @@
+//! Comparison of a directional option structure against the index future.
+//!
+//! The structure's net delta says how many lots of the future would give the
+//! same directional exposure. Both sides are then charged for the same adverse
+//! move (spot to the long strike, where the spread's loss is capped) so the
+//! capital figures are comparable.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+/// Futures margin as a percentage of notional when the caller doesn't supply one.
+pub const DEFAULT_FUTURES_MARGIN_PERCENTAGE: f64 = 12.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct FuturesBenchmark {
+    /// Signed lots of the future with the same delta as the structure.
+    delta_equivalent_lots: f64,
+    futures_notional: f64,
+    futures_margin: f64,
+    /// Futures loss on a move from spot to the structure's long strike.
+    futures_capital_at_risk: f64,
+    /// Structure max loss divided by `futures_capital_at_risk`.
+    capital_at_risk_ratio: Option<f64>,
+    /// Futures margin divided by the structure's max loss; above 1 the option
+    /// structure ties up less capital than the future.
+    capital_efficiency: Option<f64>,
+}
+
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    (denominator > 0.0).then(|| numerator / denominator)
+}
+
+/// `net_delta` is per unit of the underlying for one lot of the structure.
+pub fn against_futures(
+    net_delta: f64,
+    spot: f64,
+    long_strike: f64,
+    lot_size: f64,
+    max_loss: f64,
+    futures_margin_percentage: Option<f64>,
+) -> FuturesBenchmark {
+    let margin_percentage = futures_margin_percentage.unwrap_or(DEFAULT_FUTURES_MARGIN_PERCENTAGE);
+    let futures_notional = net_delta.abs() * spot * lot_size;
+    let futures_margin = futures_notional * margin_percentage / 100.0;
+    let futures_capital_at_risk = net_delta.abs() * (long_strike - spot).abs() * lot_size;
+
+    FuturesBenchmark {
+        delta_equivalent_lots: net_delta,
+        futures_notional,
+        futures_margin,
+        futures_capital_at_risk,
+        capital_at_risk_ratio: ratio(max_loss, futures_capital_at_risk),
+        capital_efficiency: ratio(futures_margin, max_loss),
+    }
+}
@@ -0,0 +1,1189 @@
+//! The `wasm_bindgen` exports, built with the `wasm` feature.
+//!
+//! Exports take plain JS objects typed by the `Tsify` declarations and
+//! mostly return JSON strings, with errors as the message string. The
+//! engine underneath is plain Rust; see the crate root for the native API.
+
+use crate::spreads::{self, Strategy};
+use crate::*;
+use js_sys::Float64Array;
+use serde::Serialize;
+use serde_wasm_bindgen::from_value;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+fn group_spreads(
+    credit_spreads: Vec<CreditSpread>,
+    key: impl Fn(&CreditSpread) -> String,
+) -> BTreeMap<String, Vec<CreditSpread>> {
+    let mut grouped: BTreeMap<String, Vec<CreditSpread>> = BTreeMap::new();
+    for spread in credit_spreads {
+        grouped.entry(key(&spread)).or_default().push(spread);
+    }
+    grouped
+}
+
+/// Scan results, grouped as `group_by_underlying` and `group_by_expiry` ask,
+/// and wrapped with their stats under `with_stats`.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum ScanOutput {
+    Flat(Vec<CreditSpread>),
+    Grouped(BTreeMap<String, Vec<CreditSpread>>),
+    Nested(BTreeMap<String, BTreeMap<String, Vec<CreditSpread>>>),
+    WithStats {
+        results: Box<ScanOutput>,
+        stats: trace::RejectionStats,
+    },
+}
+
+/// Ends the rejection tally begun with the scan.
+fn scan_output(params: &BearCallSpreadParams, credit_spreads: Vec<CreditSpread>) -> ScanOutput {
+    let stats = trace::finish();
+    let results = grouped_output(params, credit_spreads);
+    if params.with_stats {
+        ScanOutput::WithStats {
+            results: Box::new(results),
+            stats,
+        }
+    } else {
+        results
+    }
+}
+
+fn grouped_output(params: &BearCallSpreadParams, credit_spreads: Vec<CreditSpread>) -> ScanOutput {
+    match (params.group_by_underlying, params.group_by_expiry) {
+        (false, false) => ScanOutput::Flat(credit_spreads),
+        (true, false) => {
+            ScanOutput::Grouped(group_spreads(credit_spreads, |s| s.underlying_key.clone()))
+        }
+        (false, true) => ScanOutput::Grouped(group_spreads(credit_spreads, |s| s.expiry.clone())),
+        (true, true) => ScanOutput::Nested(
+            group_spreads(credit_spreads, |s| s.underlying_key.clone())
+                .into_iter()
+                .map(|(underlying, spreads)| {
+                    (underlying, group_spreads(spreads, |s| s.expiry.clone()))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Parses spread scan params over the `set_defaults` defaults.
+fn spread_params(params: JsValue) -> Result<BearCallSpreadParams, String> {
+    let parsed = if defaults::is_empty() {
+        from_value(params).ok()
+    } else {
+        from_value::<serde_json::Value>(params)
+            .ok()
+            .and_then(|params| defaults::parse(params).ok())
+    };
+    parsed.ok_or_else(|| String::from("Failed to parse parameters"))
+}
+
+fn scan_json(strategy: Strategy, params: JsValue) -> JsValue {
+    match spread_params(params) {
+        Ok(params) => scan_params_json(strategy, &params),
+        Err(message) => JsValue::from_str(&message),
+    }
+}
+
+/// Runs the scan and returns its JSON, or the error message, as a JS string.
+/// The JSON is written into `json_buffer`'s kept buffer and decoded into the
+/// JS string from there, without a Rust `String` of its own.
+pub fn scan_params_json(strategy: Strategy, params: &BearCallSpreadParams) -> JsValue {
+    let _record = perf::start(strategy.export_name());
+    let _tally = trace::start();
+    let credit_spreads = match spreads::scan(strategy, params) {
+        Ok(credit_spreads) => credit_spreads,
+        Err(message) => return JsValue::from_str(&message),
+    };
+
+    let results = credit_spreads.len();
+    let json = perf::time(perf::Phase::Serialize, || {
+        json_buffer::with_json(
+            &scan_output(params, credit_spreads),
+            results,
+            JsValue::from_str,
+        )
+        .unwrap_or_else(|_| JsValue::from_str("Failed to serialize credit spreads"))
+    });
+    perf::finish(results);
+    json
+}
+
+fn scan_msgpack(strategy: Strategy, params: JsValue, chain: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let params = spread_params(params).map_err(|m| JsValue::from_str(&m))?;
+    let _record = perf::start(&format!("{}_msgpack", strategy.export_name()));
+    let _tally = trace::start();
+    let instruments = perf::time(perf::Phase::Parse, || msgpack::decode_chain(chain))
+        .map_err(|m| JsValue::from_str(&m))?;
+    let credit_spreads =
+        spreads::scan_chain(strategy, &params, instruments).map_err(|m| JsValue::from_str(&m))?;
+    let results = credit_spreads.len();
+    let encoded = perf::time(perf::Phase::Serialize, || {
+        msgpack::encode(&scan_output(&params, credit_spreads))
+    });
+    perf::finish(results);
+    encoded.map_err(|m| JsValue::from_str(&m))
+}
+
+fn scan_packed(strategy: Strategy, params: JsValue) -> Result<Float64Array, JsValue> {
+    let params = spread_params(params).map_err(|m| JsValue::from_str(&m))?;
+    let _record = perf::start(&format!("{}_packed", strategy.export_name()));
+    let credit_spreads = spreads::scan(strategy, &params).map_err(|m| JsValue::from_str(&m))?;
+    let packed = perf::time(perf::Phase::Serialize, || {
+        Float64Array::from(packed::pack(&credit_spreads).as_slice())
+    });
+    perf::finish(credit_spreads.len());
+    Ok(packed)
+}
+
+fn scan_columns(strategy: Strategy, params: JsValue) -> Result<grids::SpreadColumns, JsValue> {
+    let params = spread_params(params).map_err(|m| JsValue::from_str(&m))?;
+    let _record = perf::start(&format!("{}_columns", strategy.export_name()));
+    let credit_spreads = spreads::scan(strategy, &params).map_err(|m| JsValue::from_str(&m))?;
+    let columns = perf::time(perf::Phase::Serialize, || {
+        packed::pack_columns(&credit_spreads)
+    });
+    perf::finish(credit_spreads.len());
+    Ok(grids::SpreadColumns::from(columns))
+}
+
+#[wasm_bindgen(unchecked_return_type = "string")]
+pub fn bear_call_spread(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> JsValue {
+    scan_json(Strategy::BearCall, params)
+}
+
+#[wasm_bindgen(unchecked_return_type = "string")]
+pub fn bull_put_spread(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> JsValue {
+    scan_json(Strategy::BullPut, params)
+}
+
+/// Same scan as `bear_call_spread`, encoded as fixed-size rows in one `Float64Array`.
+/// See `packed_columns` for the row layout.
+#[wasm_bindgen]
+pub fn bear_call_spread_packed(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> Result<Float64Array, JsValue> {
+    scan_packed(Strategy::BearCall, params)
+}
+
+/// Same scan as `bull_put_spread`, encoded as fixed-size rows in one `Float64Array`.
+/// See `packed_columns` for the row layout.
+#[wasm_bindgen]
+pub fn bull_put_spread_packed(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> Result<Float64Array, JsValue> {
+    scan_packed(Strategy::BullPut, params)
+}
+
+/// Writes packed rows into the start of `buffer` and returns how many spreads
+/// the scan found. Rows past the buffer's capacity are left out, so a count
+/// above `byteLength / (8 * stride)` means the buffer was too small.
+#[cfg(feature = "threads")]
+fn scan_shared(
+    strategy: Strategy,
+    params: JsValue,
+    buffer: &js_sys::SharedArrayBuffer,
+) -> Result<u32, JsValue> {
+    let params = spread_params(params).map_err(|m| JsValue::from_str(&m))?;
+    let _record = perf::start(&format!("{}_shared", strategy.export_name()));
+    let credit_spreads = spreads::scan(strategy, &params).map_err(|m| JsValue::from_str(&m))?;
+    perf::time(perf::Phase::Serialize, || {
+        let view = Float64Array::new(buffer);
+        let stride = packed::PACKED_COLUMNS.len();
+        let rows = credit_spreads.len().min(view.length() as usize / stride);
+        let packed = packed::pack(&credit_spreads[..rows]);
+        view.subarray(0, packed.len() as u32).copy_from(&packed);
+    });
+    perf::finish(credit_spreads.len());
+    Ok(credit_spreads.len() as u32)
+}
+
+/// Same scan as `bear_call_spread_packed`, writing the rows straight into a
+/// caller-owned `SharedArrayBuffer` that other workers can read without a
+/// copy. Returns the number of spreads found; see `scan_shared` and
+/// `packed_schema`. Threaded builds only.
+#[cfg(feature = "threads")]
+#[wasm_bindgen]
+pub fn bear_call_spread_shared(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    buffer: &js_sys::SharedArrayBuffer,
+) -> Result<u32, JsValue> {
+    scan_shared(Strategy::BearCall, params, buffer)
+}
+
+/// Same scan as `bull_put_spread_packed`, written into a `SharedArrayBuffer`
+/// as `bear_call_spread_shared` does. Threaded builds only.
+#[cfg(feature = "threads")]
+#[wasm_bindgen]
+pub fn bull_put_spread_shared(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    buffer: &js_sys::SharedArrayBuffer,
+) -> Result<u32, JsValue> {
+    scan_shared(Strategy::BullPut, params, buffer)
+}
+
+/// Same scan as `bear_call_spread`, as one `Float64Array` per packed column.
+#[wasm_bindgen]
+pub fn bear_call_spread_columns(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> Result<grids::SpreadColumns, JsValue> {
+    scan_columns(Strategy::BearCall, params)
+}
+
+/// Same scan as `bull_put_spread`, as one `Float64Array` per packed column.
+#[wasm_bindgen]
+pub fn bull_put_spread_columns(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> Result<grids::SpreadColumns, JsValue> {
+    scan_columns(Strategy::BullPut, params)
+}
+
+/// Same scan as `bear_call_spread`, taking the chain as a MessagePack array
+/// of instruments and returning the results as MessagePack.
+#[wasm_bindgen]
+pub fn bear_call_spread_msgpack(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    chain: &[u8],
+) -> Result<Vec<u8>, JsValue> {
+    scan_msgpack(Strategy::BearCall, params, chain)
+}
+
+/// Same scan as `bull_put_spread`, taking the chain as a MessagePack array
+/// of instruments and returning the results as MessagePack.
+#[wasm_bindgen]
+pub fn bull_put_spread_msgpack(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    chain: &[u8],
+) -> Result<Vec<u8>, JsValue> {
+    scan_msgpack(Strategy::BullPut, params, chain)
+}
+
+const DEFAULT_CHUNK_SIZE: usize = 100;
+
+/// Calls `on_progress`, when given, with the percent of pairs processed.
+fn report_progress(on_progress: Option<&js_sys::Function>, percent: f64) -> Result<(), String> {
+    match on_progress {
+        Some(on_progress) => on_progress
+            .call1(&JsValue::NULL, &JsValue::from(percent))
+            .map(|_| ())
+            .map_err(|_| String::from("Progress callback threw an error")),
+        None => Ok(()),
+    }
+}
+
+fn scan_stream(
+    strategy: Strategy,
+    params: JsValue,
+    on_chunk: &js_sys::Function,
+    chunk_size: Option<u32>,
+    on_progress: Option<js_sys::Function>,
+) -> String {
+    let params = match spread_params(params) {
+        Ok(p) => p,
+        Err(message) => return message,
+    };
+    let _tally = trace::start();
+
+    let chunk_size = chunk_size.map_or(DEFAULT_CHUNK_SIZE, |size| size as usize);
+    let emit = |chunk: &[CreditSpread]| {
+        let json = serde_json::to_string(chunk)
+            .map_err(|_| String::from("Failed to serialize credit spreads"))?;
+        on_chunk
+            .call1(&JsValue::NULL, &JsValue::from_str(&json))
+            .map(|_| ())
+            .map_err(|_| String::from("Chunk callback threw an error"))
+    };
+    let progress = |percent: f64| report_progress(on_progress.as_ref(), percent);
+    let credit_spreads =
+        match spreads::scan_streaming(strategy, &params, chunk_size, emit, progress) {
+            Ok(credit_spreads) => credit_spreads,
+            Err(message) => return message,
+        };
+
+    serde_json::to_string(&scan_output(&params, credit_spreads))
+        .unwrap_or_else(|_| String::from("Failed to serialize credit spreads"))
+}
+
+/// Same scan as `bear_call_spread`, also calling `on_chunk` with a JSON array
+/// of spreads for each underlying and expiry as soon as it is built, at most
+/// `chunk_size` (default 100) at a time. Chunks are sorted and filtered within
+/// themselves only; the return value is the complete, ranked and paged result.
+/// `on_progress` gets the percent of pairs processed after each underlying
+/// and expiry, weighted by the pairs its strikes can make.
+#[wasm_bindgen]
+pub fn bear_call_spread_stream(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "(chunk: string) => void")] on_chunk: &js_sys::Function,
+    chunk_size: Option<u32>,
+    #[wasm_bindgen(unchecked_param_type = "(percent: number) => void")] on_progress: Option<
+        js_sys::Function,
+    >,
+) -> String {
+    scan_stream(
+        Strategy::BearCall,
+        params,
+        on_chunk,
+        chunk_size,
+        on_progress,
+    )
+}
+
+/// Same scan as `bull_put_spread`, streaming chunks as `bear_call_spread_stream` does.
+#[wasm_bindgen]
+pub fn bull_put_spread_stream(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "(chunk: string) => void")] on_chunk: &js_sys::Function,
+    chunk_size: Option<u32>,
+    #[wasm_bindgen(unchecked_param_type = "(percent: number) => void")] on_progress: Option<
+        js_sys::Function,
+    >,
+) -> String {
+    scan_stream(Strategy::BullPut, params, on_chunk, chunk_size, on_progress)
+}
+
+async fn scan_async(
+    strategy: Strategy,
+    params: JsValue,
+    on_progress: Option<js_sys::Function>,
+) -> String {
+    let params = match spread_params(params) {
+        Ok(p) => p,
+        Err(message) => return message,
+    };
+    let instruments = match store::resolve_sorted(strategy, params.chain_id, &params.optionchain) {
+        Ok(instruments) => instruments,
+        Err(message) => return message,
+    };
+    let _tally = trace::start();
+
+    let mut credit_spreads = Vec::new();
+    let (total, slices) =
+        match spreads::weighted_slice_candidates(strategy, &params, instruments, None) {
+            Ok(weighted) => weighted,
+            Err(message) => return message,
+        };
+    let mut done = 0;
+    for (work, slice) in slices {
+        spreads::gather(&mut credit_spreads, slice);
+        done += work;
+        if let Err(message) =
+            report_progress(on_progress.as_ref(), spreads::percent_done(done, total))
+        {
+            return message;
+        }
+        event_loop::yield_now().await;
+    }
+    let credit_spreads = spreads::finish(&params, credit_spreads);
+
+    serde_json::to_string(&scan_output(&params, credit_spreads))
+        .unwrap_or_else(|_| String::from("Failed to serialize credit spreads"))
+}
+
+/// Same scan as `bear_call_spread`, resolving with the same JSON. It yields to
+/// the event loop after each underlying and expiry, so a long scan on the main
+/// thread doesn't freeze the page, and reports progress to `on_progress` as
+/// `bear_call_spread_stream` does.
+#[wasm_bindgen]
+pub async fn bear_call_spread_async(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "(percent: number) => void")] on_progress: Option<
+        js_sys::Function,
+    >,
+) -> String {
+    scan_async(Strategy::BearCall, params, on_progress).await
+}
+
+/// Same scan as `bull_put_spread`, yielding as `bear_call_spread_async` does.
+#[wasm_bindgen]
+pub async fn bull_put_spread_async(
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "(percent: number) => void")] on_progress: Option<
+        js_sys::Function,
+    >,
+) -> String {
+    scan_async(Strategy::BullPut, params, on_progress).await
+}
+
+fn short_json(kind: greeks::OptionKind, params: JsValue) -> String {
+    let params: single_leg::SingleLegParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match single_leg::short(kind, &params) {
+        Ok(options) => serde_json::to_string(&options)
+            .unwrap_or_else(|_| String::from("Failed to serialize options")),
+        Err(message) => message,
+    }
+}
+
+/// Out-of-the-money calls to sell naked, by premium yield on margin.
+#[wasm_bindgen]
+pub fn short_call(
+    #[wasm_bindgen(unchecked_param_type = "SingleLegParams")] params: JsValue,
+) -> String {
+    short_json(greeks::OptionKind::Call, params)
+}
+
+/// Out-of-the-money puts to sell naked, by premium yield on margin.
+#[wasm_bindgen]
+pub fn short_put(
+    #[wasm_bindgen(unchecked_param_type = "SingleLegParams")] params: JsValue,
+) -> String {
+    short_json(greeks::OptionKind::Put, params)
+}
+
+fn long_json(kind: greeks::OptionKind, params: JsValue) -> String {
+    let params: single_leg::SingleLegParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match single_leg::long(kind, &params) {
+        Ok(options) => serde_json::to_string(&options)
+            .unwrap_or_else(|_| String::from("Failed to serialize options")),
+        Err(message) => message,
+    }
+}
+
+/// Calls to buy, ranked by `sort_by` (delta per rupee by default).
+#[wasm_bindgen]
+pub fn long_call(
+    #[wasm_bindgen(unchecked_param_type = "SingleLegParams")] params: JsValue,
+) -> String {
+    long_json(greeks::OptionKind::Call, params)
+}
+
+/// Puts to buy, ranked by `sort_by` (delta per rupee by default).
+#[wasm_bindgen]
+pub fn long_put(
+    #[wasm_bindgen(unchecked_param_type = "SingleLegParams")] params: JsValue,
+) -> String {
+    long_json(greeks::OptionKind::Put, params)
+}
+
+fn covered_json(kind: greeks::OptionKind, params: JsValue) -> String {
+    let params: covered::CoveredParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match covered::scan(kind, &params) {
+        Ok(positions) => serde_json::to_string(&positions)
+            .unwrap_or_else(|_| String::from("Failed to serialize positions")),
+        Err(message) => message,
+    }
+}
+
+/// Calls to sell against a long future, for every expiry in `futures`, by
+/// return on the pair's combined margin.
+#[wasm_bindgen]
+pub fn covered_call(
+    #[wasm_bindgen(unchecked_param_type = "CoveredParams")] params: JsValue,
+) -> String {
+    covered_json(greeks::OptionKind::Call, params)
+}
+
+/// Puts to buy against a long future, for every expiry in `futures`, by the
+/// most the pair can lose.
+#[wasm_bindgen]
+pub fn protective_put(
+    #[wasm_bindgen(unchecked_param_type = "CoveredParams")] params: JsValue,
+) -> String {
+    covered_json(greeks::OptionKind::Put, params)
+}
+
+/// Short strangles or iron condors on the next `weeks` expiries at one
+/// target delta, with their combined greeks and the margin locked as each
+/// week expires.
+#[wasm_bindgen]
+pub fn weekly_ladder(
+    #[wasm_bindgen(unchecked_param_type = "LadderParams")] params: JsValue,
+) -> String {
+    let params: ladder::LadderParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match ladder::build(&params) {
+        Ok(ladder) => serde_json::to_string(&ladder)
+            .unwrap_or_else(|_| String::from("Failed to serialize ladder")),
+        Err(message) => message,
+    }
+}
+
+/// Column names of a packed row, in order; its length is the row stride.
+#[wasm_bindgen]
+pub fn packed_columns() -> Vec<String> {
+    packed::PACKED_COLUMNS
+        .iter()
+        .map(|c| c.to_string())
+        .collect()
+}
+
+/// Layout of a packed row as JSON (`PackedSchema`): column names, stride,
+/// bytes per value and the `type_` codes.
+#[wasm_bindgen]
+pub fn packed_schema() -> String {
+    serde_json::to_string(&packed::schema())
+        .unwrap_or_else(|_| String::from("Failed to serialize schema"))
+}
+
+/// Resamples the chain onto a uniform strike grid (`step`, default 50) and
+/// returns it in the same JSON format as the input chain.
+#[wasm_bindgen]
+pub fn resample_chain(
+    #[wasm_bindgen(unchecked_param_type = "ResampleParams")] params: JsValue,
+) -> String {
+    let params: resample::ResampleParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match resample::resample(&params) {
+        Ok(instruments) => serde_json::to_string(&instruments)
+            .unwrap_or_else(|_| String::from("Failed to serialize option chain")),
+        Err(message) => message,
+    }
+}
+
+/// Reports malformed rows, duplicate strikes, missing market data, stale
+/// quotes and negative prices, and returns the chain with them cleaned up.
+#[wasm_bindgen]
+pub fn validate_chain(chain: &str) -> String {
+    match validation::validate(chain) {
+        Ok(report) => serde_json::to_string(&report)
+            .unwrap_or_else(|_| String::from("Failed to serialize chain validation")),
+        Err(message) => message,
+    }
+}
+
+/// Parses a chain once and keeps it in module memory. Pass the returned id
+/// as `chain_id` to the scanners instead of resending `optionchain`.
+#[wasm_bindgen]
+pub fn load_chain(chain: &str) -> Result<u32, JsValue> {
+    let instruments = chain::parse(chain).map_err(|m| JsValue::from_str(&m))?;
+    Ok(store::insert(instruments))
+}
+
+/// Applies tick updates (`instrument_key` plus any of `ltp`, `bid_price`,
+/// `ask_price`, `bid_qty`, `ask_qty`, `oi`, `volume`) to a loaded chain.
+#[wasm_bindgen]
+pub fn update_quotes(
+    chain_id: u32,
+    #[wasm_bindgen(unchecked_param_type = "QuoteUpdate[]")] updates: JsValue,
+) -> String {
+    let updates: Vec<quotes::QuoteUpdate> = match from_value(updates) {
+        Ok(u) => u,
+        Err(_) => return String::from("Failed to parse quote updates"),
+    };
+
+    match store::with_chain_mut(chain_id, |stored| {
+        stored.update(|instruments| quotes::apply(instruments, &updates))
+    }) {
+        Ok(report) => serde_json::to_string(&report)
+            .unwrap_or_else(|_| String::from("Failed to serialize update report")),
+        Err(message) => message,
+    }
+}
+
+/// Rescans a loaded chain for `strategy` (`bear_call` or `bull_put`),
+/// rebuilding only spreads on strikes changed by `update_quotes` since the
+/// last rescan, and returns the added, changed and removed results.
+#[wasm_bindgen]
+pub fn rescan(
+    chain_id: u32,
+    strategy: &str,
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> String {
+    let Some(strategy) = Strategy::from_name(strategy) else {
+        return String::from("Unknown strategy");
+    };
+    let params = match spread_params(params) {
+        Ok(p) => p,
+        Err(message) => return message,
+    };
+
+    match rescan::rescan(chain_id, strategy, &params) {
+        Ok(delta) => serde_json::to_string(&delta)
+            .unwrap_or_else(|_| String::from("Failed to serialize scan delta")),
+        Err(message) => message,
+    }
+}
+
+/// Scans a loaded chain for `strategy` (`bear_call` or `bull_put`) and
+/// returns a JSON recording of the chain, the params and the results that
+/// `replay_scan` can rerun anywhere, e.g. for a bug report. The clock is
+/// pinned to `params.now`, or to the time of the export.
+#[wasm_bindgen]
+pub fn export_scan(
+    chain_id: u32,
+    strategy: &str,
+    #[wasm_bindgen(unchecked_param_type = "BearCallSpreadParams")] params: JsValue,
+) -> String {
+    let Some(strategy) = Strategy::from_name(strategy) else {
+        return String::from("Unknown strategy");
+    };
+    let params = match spread_params(params) {
+        Ok(p) => p,
+        Err(message) => return message,
+    };
+
+    match replay::record(chain_id, strategy, params) {
+        Ok(recording) => serde_json::to_string(&recording)
+            .unwrap_or_else(|_| String::from("Failed to serialize recording")),
+        Err(message) => message,
+    }
+}
+
+/// Reruns a recording from `export_scan` and returns the results, with
+/// whether they match the recorded ones.
+#[wasm_bindgen]
+pub fn replay_scan(recording: &str) -> String {
+    let recording: replay::ScanRecording = match serde_json::from_str(recording) {
+        Ok(r) => r,
+        Err(_) => return String::from("Failed to parse recording"),
+    };
+
+    match replay::replay(recording) {
+        Ok(replay) => serde_json::to_string(&replay)
+            .unwrap_or_else(|_| String::from("Failed to serialize replay")),
+        Err(message) => message,
+    }
+}
+
+/// Frees a chain loaded with `load_chain`; returns false for an unknown id.
+#[wasm_bindgen]
+pub fn drop_chain(chain_id: u32) -> bool {
+    store::remove(chain_id)
+}
+
+/// Microsecond phase timings and counts from the last completed JSON, packed
+/// or MessagePack spread scan; `null` before the first one. Times cover
+/// parsing the chain, filtering strikes, pairing, sorting and serializing.
+#[wasm_bindgen]
+pub fn last_scan_stats() -> String {
+    serde_json::to_string(&perf::last())
+        .unwrap_or_else(|_| String::from("Failed to serialize scan stats"))
+}
+
+#[wasm_bindgen(start)]
+fn start() {
+    logger::install();
+}
+
+/// Sends log messages, such as chain parse errors, to `callback` as
+/// `(level, message)` instead of the console. Only messages at `level`
+/// ("error", "warn", "info", "debug", "trace" or "off"; default "info") and
+/// above are logged. Call with no callback to log to the console again.
+#[wasm_bindgen]
+pub fn set_logger(
+    #[wasm_bindgen(unchecked_param_type = "(level: string, message: string) => void")]
+    callback: Option<js_sys::Function>,
+    level: Option<String>,
+) -> Result<(), JsValue> {
+    logger::set(callback, level.as_deref()).map_err(|m| JsValue::from_str(&m))
+}
+
+/// Stores params, e.g. `{ lot_size: 65, brokerage_profile, min_pop: 0.7 }`,
+/// that every spread scan starts from: fields a scan's own params leave out
+/// or set to null take these values. Pass null to clear them. Throws if a
+/// field doesn't fit the scan params.
+#[wasm_bindgen]
+pub fn set_defaults(
+    #[wasm_bindgen(unchecked_param_type = "Partial<BearCallSpreadParams> | null")]
+    defaults: JsValue,
+) -> Result<(), JsValue> {
+    let defaults: serde_json::Value =
+        from_value(defaults).map_err(|_| JsValue::from_str("Failed to parse defaults"))?;
+    defaults::set(defaults).map_err(|m| JsValue::from_str(&m))
+}
+
+/// The params stored with `set_defaults`; an empty object when none are.
+#[wasm_bindgen(unchecked_return_type = "Partial<BearCallSpreadParams>")]
+pub fn get_defaults() -> Result<JsValue, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    serde::Serialize::serialize(&defaults::get(), &serializer)
+        .map_err(|_| JsValue::from_str("Failed to serialize defaults"))
+}
+
+/// Frees the buffer kept between scans and every chain's cached `rescan`
+/// results, so the memory can be reused. Loaded chains stay; the next rescan
+/// of each starts over with a full scan.
+#[wasm_bindgen]
+pub fn reset() {
+    arena::release();
+    store::clear_scans();
+}
+
+/// Converts an nseindia.com option chain response into the chain format the
+/// other exports take.
+#[wasm_bindgen]
+pub fn parse_nse_chain(data: &str) -> String {
+    match nse::parse(data) {
+        Ok(instruments) => serde_json::to_string(&instruments)
+            .unwrap_or_else(|_| String::from("Failed to serialize option chain")),
+        Err(message) => message,
+    }
+}
+
+/// Builds a chain from a Kite Connect instruments dump and `/quote` response.
+#[wasm_bindgen]
+pub fn parse_kite_chain(
+    #[wasm_bindgen(unchecked_param_type = "KiteParams")] params: JsValue,
+) -> String {
+    let params: kite::KiteParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match kite::parse(&params) {
+        Ok(instruments) => serde_json::to_string(&instruments)
+            .unwrap_or_else(|_| String::from("Failed to serialize option chain")),
+        Err(message) => message,
+    }
+}
+
+/// Parses a chain in any registered format (`internal`, `upstox`, `nse`,
+/// `kite` or `csv`) and returns it in the chain format the other exports take.
+#[wasm_bindgen]
+pub fn parse_chain(format: &str, data: JsValue) -> String {
+    let data: serde_json::Value = match from_value(data) {
+        Ok(d) => d,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match source::parse(format, &data) {
+        Ok(instruments) => serde_json::to_string(&instruments)
+            .unwrap_or_else(|_| String::from("Failed to serialize option chain")),
+        Err(message) => message,
+    }
+}
+
+/// Limit orders for each leg of a scanned spread, `qty_lots` lots each, in
+/// the body format of `broker` (`"upstox"` or `"kite"`), buy legs first.
+#[wasm_bindgen]
+pub fn to_order_payload(
+    #[wasm_bindgen(unchecked_param_type = "CreditSpread")] spread: JsValue,
+    broker: &str,
+    qty_lots: u32,
+) -> String {
+    let spread: CreditSpread = match from_value(spread) {
+        Ok(s) => s,
+        Err(_) => return String::from("Failed to parse spread"),
+    };
+    let broker = match orders::Broker::from_name(broker) {
+        Ok(broker) => broker,
+        Err(message) => return message,
+    };
+
+    match orders::payload(&spread, broker, qty_lots) {
+        Ok(payload) => serde_json::to_string(&payload)
+            .unwrap_or_else(|_| String::from("Failed to serialize order payload")),
+        Err(message) => message,
+    }
+}
+
+/// Converts fired alert events into `showNotification(title, options)` payloads.
+#[wasm_bindgen]
+pub fn notification_payloads(
+    #[wasm_bindgen(unchecked_param_type = "AlertEvent[]")] events: JsValue,
+) -> String {
+    let events: Vec<notifications::AlertEvent> = match from_value(events) {
+        Ok(e) => e,
+        Err(_) => return String::from("Failed to parse alert events"),
+    };
+
+    let payloads: Vec<notifications::NotificationPayload> =
+        events.iter().map(notifications::payload).collect();
+    serde_json::to_string(&payloads)
+        .unwrap_or_else(|_| String::from("Failed to serialize notifications"))
+}
+
+/// Suggests index put and put-spread hedges for a long portfolio, sized by
+/// its beta-adjusted value, cheapest annualized cost first.
+#[wasm_bindgen]
+pub fn portfolio_hedge(
+    #[wasm_bindgen(unchecked_param_type = "HedgeParams")] params: JsValue,
+) -> String {
+    let params: hedging::HedgeParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match hedging::suggest(&params) {
+        Ok(hedges) => serde_json::to_string(&hedges)
+            .unwrap_or_else(|_| String::from("Failed to serialize hedges")),
+        Err(message) => message,
+    }
+}
+
+/// Solves the near/far ratio of a calendar spread for vega neutrality or for
+/// maximum theta under a vega cap.
+#[wasm_bindgen]
+pub fn calendar_ratio(
+    #[wasm_bindgen(unchecked_param_type = "CalendarParams")] params: JsValue,
+) -> String {
+    let params: calendar::CalendarParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match calendar::solve(&params) {
+        Ok(ratio) => serde_json::to_string(&ratio)
+            .unwrap_or_else(|_| String::from("Failed to serialize calendar ratio")),
+        Err(message) => message,
+    }
+}
+
+/// End-of-day summary of tracked positions marked against the closing chain.
+#[wasm_bindgen]
+pub fn daily_report(
+    #[wasm_bindgen(unchecked_param_type = "Portfolio")] portfolio: JsValue,
+    closing_chain: &str,
+) -> String {
+    let portfolio: positions::Portfolio = match from_value(portfolio) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse portfolio"),
+    };
+
+    match report::build(&portfolio, closing_chain) {
+        Ok(report) => serde_json::to_string(&report)
+            .unwrap_or_else(|_| String::from("Failed to serialize report")),
+        Err(message) => message,
+    }
+}
+
+/// The listed strike nearest spot per underlying and expiry, with its call,
+/// put and straddle LTP, e.g. to center an option chain view. Expiries with
+/// a quote in `futures` also get the future's price and basis.
+#[wasm_bindgen]
+pub fn atm_strike(
+    chain: &str,
+    #[wasm_bindgen(unchecked_param_type = "FuturesQuote[] | null | undefined")] futures: JsValue,
+) -> String {
+    let futures: Option<Vec<futures::FuturesQuote>> = match from_value(futures) {
+        Ok(f) => f,
+        Err(_) => return String::from("Failed to parse futures quotes"),
+    };
+
+    match volatility::atm_strikes(chain, &futures.unwrap_or_default()) {
+        Ok(strikes) => serde_json::to_string(&strikes)
+            .unwrap_or_else(|_| String::from("Failed to serialize ATM strikes")),
+        Err(message) => message,
+    }
+}
+
+/// Straddle-implied expected move per underlying and expiry. With `dte`, the
+/// move is also scaled to that many days.
+#[wasm_bindgen]
+pub fn expected_move(chain: &str, dte: Option<f64>) -> String {
+    match volatility::expected_move(chain, dte, dates::now_ms()) {
+        Ok(moves) => serde_json::to_string(&moves)
+            .unwrap_or_else(|_| String::from("Failed to serialize expected move")),
+        Err(message) => message,
+    }
+}
+
+/// ATM straddle premium and implied move across a time series of chain
+/// snapshots, with decay statistics per underlying and expiry.
+#[wasm_bindgen]
+pub fn straddle_history(
+    #[wasm_bindgen(unchecked_param_type = "StraddleHistoryParams")] params: JsValue,
+) -> String {
+    let params: volatility::StraddleHistoryParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match volatility::straddle_history(&params) {
+        Ok(history) => serde_json::to_string(&history)
+            .unwrap_or_else(|_| String::from("Failed to serialize straddle history")),
+        Err(message) => message,
+    }
+}
+
+/// IV rank and percentile of `current_iv` against `historical_ivs`, an array
+/// of past IVs in the same units.
+#[wasm_bindgen]
+pub fn iv_stats(
+    #[wasm_bindgen(unchecked_param_type = "number[]")] historical_ivs: JsValue,
+    current_iv: f64,
+) -> String {
+    let historical_ivs: Vec<f64> = match from_value(historical_ivs) {
+        Ok(h) => h,
+        Err(_) => return String::from("Failed to parse historical IVs"),
+    };
+
+    match volatility::iv_stats(&historical_ivs, current_iv) {
+        Ok(stats) => serde_json::to_string(&stats)
+            .unwrap_or_else(|_| String::from("Failed to serialize IV stats")),
+        Err(message) => message,
+    }
+}
+
+/// IV by strike for calls and puts per underlying and expiry, with the skew
+/// slope and 25-delta risk reversal.
+#[wasm_bindgen]
+pub fn iv_skew(chain: &str) -> String {
+    match volatility::iv_skew(chain, dates::now_ms()) {
+        Ok(skews) => serde_json::to_string(&skews)
+            .unwrap_or_else(|_| String::from("Failed to serialize IV skew")),
+        Err(message) => message,
+    }
+}
+
+/// ATM IV per expiry for each underlying with the term-structure slope, for
+/// picking calendar spreads.
+#[wasm_bindgen]
+pub fn term_structure(chain: &str) -> String {
+    match volatility::term_structure(chain, dates::now_ms()) {
+        Ok(structures) => serde_json::to_string(&structures)
+            .unwrap_or_else(|_| String::from("Failed to serialize term structure")),
+        Err(message) => message,
+    }
+}
+
+/// P&L at expiry of `legs` (spread `legs` or custom ones) at `steps + 1`
+/// underlying prices across `price_range`, given as `[low, high]`.
+#[wasm_bindgen]
+pub fn payoff_curve(
+    #[wasm_bindgen(unchecked_param_type = "Leg[]")] legs: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "[number, number]")] price_range: JsValue,
+    steps: u32,
+) -> String {
+    let legs: Vec<positions::Leg> = match from_value(legs) {
+        Ok(l) => l,
+        Err(_) => return String::from("Failed to parse legs"),
+    };
+    let (low, high): (f64, f64) = match from_value(price_range) {
+        Ok(r) => r,
+        Err(_) => return String::from("Failed to parse price range"),
+    };
+
+    match payoff::curve(&legs, low, high, steps) {
+        Ok(points) => serde_json::to_string(&points)
+            .unwrap_or_else(|_| String::from("Failed to serialize payoff curve")),
+        Err(message) => message,
+    }
+}
+
+/// Expiry P&L alongside the Black-Scholes P&L at a date before expiry (`at`,
+/// default now), valuing each leg at its own `iv`.
+#[wasm_bindgen]
+pub fn payoff_curves(
+    #[wasm_bindgen(unchecked_param_type = "PayoffParams")] params: JsValue,
+) -> String {
+    let params: payoff::PayoffParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match payoff::curves(&params) {
+        Ok(curves) => serde_json::to_string(&curves)
+            .unwrap_or_else(|_| String::from("Failed to serialize payoff curves")),
+        Err(message) => message,
+    }
+}
+
+/// Position delta, gamma, theta and vega sampled over a grid of underlying
+/// prices and days ahead, for heatmaps.
+#[wasm_bindgen]
+pub fn greeks_surface(
+    #[wasm_bindgen(unchecked_param_type = "SurfaceParams")] params: JsValue,
+) -> String {
+    let params: payoff::SurfaceParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match payoff::surface(&params) {
+        Ok(surface) => serde_json::to_string(&surface)
+            .unwrap_or_else(|_| String::from("Failed to serialize greeks surface")),
+        Err(message) => message,
+    }
+}
+
+/// P&L matrix of `legs` under each IV shift and spot move, e.g. a 20% IV
+/// crush the day after a budget or RBI policy announcement.
+#[wasm_bindgen]
+pub fn scenario(
+    #[wasm_bindgen(unchecked_param_type = "ScenarioParams")] params: JsValue,
+) -> String {
+    let params: scenario::ScenarioParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match scenario::scenario(&params) {
+        Ok(matrix) => serde_json::to_string(&matrix)
+            .unwrap_or_else(|_| String::from("Failed to serialize scenario")),
+        Err(message) => message,
+    }
+}
+
+/// Rupee P&L of `position` under standard spot shocks (±1%, ±2%, ±5% by
+/// default), each combined with IV shocks, with the worst case picked out.
+/// There's no chain to look the legs up in, so the position needs its
+/// `lot_size`, or an `underlying_key` the lot size table knows.
+#[wasm_bindgen]
+pub fn stress_test(
+    #[wasm_bindgen(unchecked_param_type = "Position")] position: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "StressShocks")] shocks: JsValue,
+) -> String {
+    let position: positions::Position = match from_value(position) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse position"),
+    };
+    let shocks: scenario::StressShocks = match from_value(shocks) {
+        Ok(s) => s,
+        Err(_) => return String::from("Failed to parse shocks"),
+    };
+
+    match scenario::stress_test(&position, &shocks) {
+        Ok(test) => serde_json::to_string(&test)
+            .unwrap_or_else(|_| String::from("Failed to serialize stress test")),
+        Err(message) => message,
+    }
+}
+
+/// Same curves as `payoff_curves`, as `Float64Array`s over the price grid.
+#[wasm_bindgen]
+pub fn payoff_grid(
+    #[wasm_bindgen(unchecked_param_type = "PayoffParams")] params: JsValue,
+) -> Result<grids::PayoffGrid, JsValue> {
+    let params: payoff::PayoffParams =
+        from_value(params).map_err(|_| JsValue::from_str("Failed to parse parameters"))?;
+    payoff::curve_buffers(&params)
+        .map(grids::PayoffGrid::from)
+        .map_err(|m| JsValue::from_str(&m))
+}
+
+/// Same surface as `greeks_surface`, with each greek as one `Float64Array`
+/// of `stride`-long rows, one per day.
+#[wasm_bindgen]
+pub fn greeks_grid(
+    #[wasm_bindgen(unchecked_param_type = "SurfaceParams")] params: JsValue,
+) -> Result<grids::GreeksGrid, JsValue> {
+    let params: payoff::SurfaceParams =
+        from_value(params).map_err(|_| JsValue::from_str("Failed to parse parameters"))?;
+    payoff::surface_buffers(&params)
+        .map(grids::GreeksGrid::from)
+        .map_err(|m| JsValue::from_str(&m))
+}
+
+/// Classifies each strike's call and put as long buildup, short buildup, long
+/// unwinding or short covering from the change in OI and price.
+#[wasm_bindgen]
+pub fn oi_buildup(chain: &str) -> String {
+    match oi::buildup(chain) {
+        Ok(report) => serde_json::to_string(&report)
+            .unwrap_or_else(|_| String::from("Failed to serialize OI buildup")),
+        Err(message) => message,
+    }
+}
+
+/// Highest call-OI (resistance) and put-OI (support) strikes per underlying
+/// and expiry, plus the largest OI additions; `top_n` defaults to 3.
+#[wasm_bindgen]
+pub fn oi_levels(chain: &str, top_n: Option<u32>) -> String {
+    let top_n = top_n.map_or(oi::DEFAULT_TOP_LEVELS, |n| n as usize);
+    match oi::levels(chain, top_n) {
+        Ok(levels) => serde_json::to_string(&levels)
+            .unwrap_or_else(|_| String::from("Failed to serialize OI levels")),
+        Err(message) => message,
+    }
+}
+
+/// Suggested lots for a trade from its max loss and POP, using
+/// fixed-fractional and Kelly sizing.
+#[wasm_bindgen]
+pub fn position_size(
+    #[wasm_bindgen(unchecked_param_type = "SizingParams")] params: JsValue,
+) -> String {
+    let params: sizing::SizingParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match sizing::size(&params) {
+        Ok(size) => serde_json::to_string(&size)
+            .unwrap_or_else(|_| String::from("Failed to serialize position size")),
+        Err(message) => message,
+    }
+}
+
+/// Aggregate greeks, margin, net P&L and combined payoff curves for a set of
+/// open positions marked against `chain`.
+#[wasm_bindgen]
+pub fn portfolio_summary(
+    #[wasm_bindgen(unchecked_param_type = "PortfolioParams")] params: JsValue,
+    chain: &str,
+) -> String {
+    let params: portfolio::PortfolioParams = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match portfolio::summarize(&params, chain) {
+        Ok(summary) => serde_json::to_string(&summary)
+            .unwrap_or_else(|_| String::from("Failed to serialize portfolio")),
+        Err(message) => message,
+    }
+}
+
+/// Revalues open positions against `chain` and returns unrealized P&L per leg
+/// and per position. `price_mode` is `ltp` (default), `mid` or
+/// `bid_ask_conservative`, which closes shorts at the ask and longs at the bid.
+#[wasm_bindgen]
+pub fn mark_positions(
+    #[wasm_bindgen(unchecked_param_type = "Position[]")] positions: JsValue,
+    chain: &str,
+    #[wasm_bindgen(unchecked_param_type = "PriceMode | null | undefined")] price_mode: JsValue,
+) -> String {
+    let positions: Vec<positions::Position> = match from_value(positions) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse positions"),
+    };
+    let price_mode: Option<positions::PriceMode> = match from_value(price_mode) {
+        Ok(m) => m,
+        Err(_) => return String::from("Failed to parse price mode"),
+    };
+
+    match positions::mark_all(&positions, chain, price_mode.unwrap_or_default()) {
+        Ok(marks) => serde_json::to_string(&marks)
+            .unwrap_or_else(|_| String::from("Failed to serialize marks")),
+        Err(message) => message,
+    }
+}
+
+/// Roll candidates for a threatened short leg of `position`: out in time, up
+/// or down in strike, with the roll credit and new breakevens of each.
+#[wasm_bindgen]
+pub fn suggest_roll(
+    #[wasm_bindgen(unchecked_param_type = "Position")] position: JsValue,
+    chain: &str,
+    #[wasm_bindgen(unchecked_param_type = "RollParams | null | undefined")] params: JsValue,
+) -> String {
+    let position: positions::Position = match from_value(position) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse position"),
+    };
+    let params: Option<roll::RollParams> = match from_value(params) {
+        Ok(p) => p,
+        Err(_) => return String::from("Failed to parse parameters"),
+    };
+
+    match roll::suggest(&position, chain, &params.unwrap_or_default()) {
+        Ok(suggestions) => serde_json::to_string(&suggestions)
+            .unwrap_or_else(|_| String::from("Failed to serialize roll suggestions")),
+        Err(message) => message,
+    }
+}
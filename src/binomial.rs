@@ -0,0 +1,107 @@
+//! Cox-Ross-Rubinstein binomial pricing for American-style options.
+//!
+//! Stock options that can be exercised early are worth more than
+//! Black-Scholes says when early exercise pays, as for deep in-the-money
+//! puts. The tree checks exercise at every node. Delta, gamma and theta are
+//! read off the first steps of the tree and vega comes from bumping
+//! volatility, in the same units as `greeks::greeks`.
+
+use crate::greeks::{Greeks, OptionKind};
+
+const STEPS: usize = 200;
+// Volatility bump for vega, one vol point
+const VEGA_BUMP: f64 = 0.01;
+
+fn intrinsic(kind: OptionKind, spot: f64, strike: f64) -> f64 {
+    match kind {
+        OptionKind::Call => (spot - strike).max(0.0),
+        OptionKind::Put => (strike - spot).max(0.0),
+    }
+}
+
+/// Option values at the root and the first two steps of the tree, with the
+/// up factor and the step length in years.
+struct Tree {
+    root: f64,
+    step1: [f64; 2],
+    step2: [f64; 3],
+    up: f64,
+    dt: f64,
+}
+
+fn tree(kind: OptionKind, spot: f64, strike: f64, t: f64, r: f64, q: f64, sigma: f64) -> Tree {
+    let dt = t / STEPS as f64;
+    let up = (sigma * dt.sqrt()).exp();
+    let down = 1.0 / up;
+    let p = (((r - q) * dt).exp() - down) / (up - down);
+    let discount = (-r * dt).exp();
+    let node_spot = |step: usize, ups: usize| spot * up.powi(2 * ups as i32 - step as i32);
+
+    let mut values: Vec<f64> = (0..=STEPS)
+        .map(|ups| intrinsic(kind, node_spot(STEPS, ups), strike))
+        .collect();
+    let (mut step1, mut step2) = ([0.0; 2], [0.0; 3]);
+    for step in (0..STEPS).rev() {
+        for ups in 0..=step {
+            let hold = discount * (p * values[ups + 1] + (1.0 - p) * values[ups]);
+            values[ups] = hold.max(intrinsic(kind, node_spot(step, ups), strike));
+        }
+        match step {
+            2 => step2.copy_from_slice(&values[..3]),
+            1 => step1.copy_from_slice(&values[..2]),
+            _ => {}
+        }
+    }
+
+    Tree {
+        root: values[0],
+        step1,
+        step2,
+        up,
+        dt,
+    }
+}
+
+/// American price per unit of the underlying; intrinsic value at or past
+/// expiry.
+pub fn price(kind: OptionKind, spot: f64, strike: f64, t: f64, r: f64, q: f64, sigma: f64) -> f64 {
+    if t <= 0.0 || sigma <= 0.0 {
+        return intrinsic(kind, spot, strike);
+    }
+    tree(kind, spot, strike, t, r, q, sigma).root
+}
+
+pub fn greeks(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    r: f64,
+    q: f64,
+    sigma: f64,
+) -> Greeks {
+    let tree = tree(kind, spot, strike, t, r, q, sigma);
+    let up = tree.up;
+    let down = 1.0 / up;
+
+    let delta = (tree.step1[1] - tree.step1[0]) / (spot * (up - down));
+    let (high, low) = (spot * up * up, spot * down * down);
+    let delta_high = (tree.step2[2] - tree.step2[1]) / (high - spot);
+    let delta_low = (tree.step2[1] - tree.step2[0]) / (spot - low);
+    let gamma = (delta_high - delta_low) / (0.5 * (high - low));
+    // The middle node two steps in is back at spot
+    let theta = (tree.step2[1] - tree.root) / (2.0 * tree.dt);
+
+    let bumped_down = (sigma - VEGA_BUMP).max(sigma / 2.0);
+    let vega = (price(kind, spot, strike, t, r, q, sigma + VEGA_BUMP)
+        - price(kind, spot, strike, t, r, q, bumped_down))
+        / (sigma + VEGA_BUMP - bumped_down)
+        / 100.0;
+
+    Greeks {
+        delta,
+        gamma,
+        theta: theta / 365.0,
+        vega,
+    }
+}
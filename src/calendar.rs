@@ -0,0 +1,203 @@
+//! Near/far quantity ratio for calendar spreads.
+//!
+//! The structure sells `ratio` units of the near-expiry option for every unit
+//! of the far-expiry option bought at the same strike. Net vega falls and net
+//! theta rises as the ratio grows, so the solver either picks the ratio that
+//! zeroes vega or pushes the ratio up until the vega cap binds.
+
+use crate::greeks::OptionKind;
+use crate::{chain, dates, greeks, lots, store, Instrument};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_MAX_RATIO: f64 = 5.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarObjective {
+    VegaNeutral,
+    MaxTheta,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct CalendarParams {
+    #[serde(default)]
+    optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
+    objective: CalendarObjective,
+    #[serde(default)]
+    underlying_key: Option<String>,
+    #[serde(default)]
+    option_type: Option<OptionKind>,
+    /// Defaults to the strike nearest spot listed in both expiries.
+    #[serde(default)]
+    strike: Option<f64>,
+    /// Defaults to the nearest unexpired expiry.
+    #[serde(default)]
+    near_expiry: Option<String>,
+    /// Defaults to the expiry after `near_expiry`.
+    #[serde(default)]
+    far_expiry: Option<String>,
+    /// Largest absolute net vega, in rupees, allowed for `max_theta`.
+    #[serde(default)]
+    vega_cap: Option<f64>,
+    #[serde(default)]
+    max_ratio: Option<f64>,
+    #[serde(default)]
+    far_lots: Option<f64>,
+    #[serde(default)]
+    lot_size: Option<f64>,
+    #[serde(default)]
+    now: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct CalendarRatio {
+    underlying_key: String,
+    option_type: OptionKind,
+    strike: f64,
+    near_expiry: String,
+    far_expiry: String,
+    /// Near units sold per far unit bought, before rounding to lots.
+    ratio: f64,
+    near_lots: f64,
+    far_lots: f64,
+    // Residual greeks in rupees for the rounded lots
+    net_delta: f64,
+    net_gamma: f64,
+    net_theta: f64,
+    net_vega: f64,
+    /// Net debit paid to open the rounded position; negative for a credit.
+    cost: f64,
+}
+
+struct LegQuote {
+    price: f64,
+    delta: f64,
+    gamma: f64,
+    theta: f64,
+    vega: f64,
+}
+
+fn leg_quote(instrument: &Instrument, kind: OptionKind) -> Option<LegQuote> {
+    let option = chain::option(instrument, kind)?;
+    let greeks = option.option_greeks.as_ref()?;
+    Some(LegQuote {
+        price: option.market_data.as_ref()?.ltp?,
+        delta: greeks.delta?,
+        gamma: greeks.gamma?,
+        theta: greeks.theta?,
+        vega: greeks.vega?,
+    })
+}
+
+pub fn solve(params: &CalendarParams) -> Result<CalendarRatio, String> {
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let kind = params.option_type.unwrap_or(OptionKind::Call);
+
+    let (mut instruments, index) = store::resolve_indexed(params.chain_id, &params.optionchain)?;
+    let underlying_key = match &params.underlying_key {
+        Some(key) => key.clone(),
+        None => instruments
+            .first()
+            .map(|i| i.underlying_key.clone())
+            .ok_or_else(|| String::from("Option chain is empty"))?,
+    };
+    greeks::fill_missing(&mut instruments, now_ms);
+    let find = |expiry: &str, strike: f64| {
+        index
+            .row(&underlying_key, expiry, strike)
+            .map(|row| &instruments[row])
+    };
+
+    let expiries: Vec<&str> = index
+        .expiries(&underlying_key)
+        .filter(|expiry| dates::days_to_expiry(expiry, now_ms).is_some_and(|days| days >= 0))
+        .collect();
+
+    let near_expiry = match &params.near_expiry {
+        Some(expiry) => dates::normalize_expiry(expiry).unwrap_or_else(|| expiry.clone()),
+        None => expiries
+            .first()
+            .map(|expiry| expiry.to_string())
+            .ok_or_else(|| String::from("No unexpired expiry in the chain"))?,
+    };
+    let far_expiry = match &params.far_expiry {
+        Some(expiry) => dates::normalize_expiry(expiry).unwrap_or_else(|| expiry.clone()),
+        None => expiries
+            .iter()
+            .find(|expiry| **expiry > near_expiry.as_str())
+            .map(|expiry| expiry.to_string())
+            .ok_or_else(|| String::from("No expiry after the near expiry"))?,
+    };
+
+    let strike = match params.strike {
+        Some(strike) => strike,
+        None => chain::atm(instruments.iter().filter(|i| {
+            i.underlying_key == underlying_key
+                && i.expiry == near_expiry
+                && find(&far_expiry, i.strike_price).is_some()
+        }))
+        .map(|i| i.strike_price)
+        .ok_or_else(|| String::from("No strike listed in both expiries"))?,
+    };
+
+    let near = find(&near_expiry, strike)
+        .and_then(|i| leg_quote(i, kind))
+        .ok_or_else(|| String::from("Near leg has no price or greeks"))?;
+    let far = find(&far_expiry, strike)
+        .and_then(|i| leg_quote(i, kind))
+        .ok_or_else(|| String::from("Far leg has no price or greeks"))?;
+    if near.vega <= 0.0 {
+        return Err(String::from("Near leg vega must be positive"));
+    }
+
+    let lot_size = lots::resolve(params.lot_size, &underlying_key)?;
+    let far_lots = params.far_lots.unwrap_or(1.0).max(1.0);
+    let max_ratio = params.max_ratio.unwrap_or(DEFAULT_MAX_RATIO);
+
+    let ratio = match params.objective {
+        CalendarObjective::VegaNeutral => far.vega / near.vega,
+        CalendarObjective::MaxTheta => {
+            // Net vega per far unit is far.vega - ratio * near.vega; keep it above -cap
+            let cap_per_unit = params.vega_cap.unwrap_or(0.0).abs() / (far_lots * lot_size);
+            if near.theta < 0.0 {
+                (far.vega + cap_per_unit) / near.vega
+            } else {
+                0.0
+            }
+        }
+    }
+    .clamp(0.0, max_ratio);
+
+    // Rounding down keeps a max_theta position inside its vega cap
+    let near_lots = match params.objective {
+        CalendarObjective::VegaNeutral => (ratio * far_lots).round(),
+        CalendarObjective::MaxTheta => (ratio * far_lots).floor(),
+    };
+    let net = |near_value: f64, far_value: f64| {
+        (far_value * far_lots - near_value * near_lots) * lot_size
+    };
+
+    Ok(CalendarRatio {
+        underlying_key,
+        option_type: kind,
+        strike,
+        near_expiry,
+        far_expiry,
+        ratio,
+        near_lots,
+        far_lots,
+        net_delta: net(near.delta, far.delta),
+        net_gamma: net(near.gamma, far.gamma),
+        net_theta: net(near.theta, far.theta),
+        net_vega: net(near.vega, far.vega),
+        cost: net(near.price, far.price),
+    })
+}
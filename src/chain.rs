@@ -0,0 +1,183 @@
+use crate::greeks::OptionKind;
+use crate::{dates, upstox, Instrument, OptionData};
+use std::collections::{BTreeMap, HashMap};
+
+/// Logs a JSON parser error and returns the message handed back to callers.
+pub fn json_error(err: serde_json::Error) -> String {
+    log::error!("Failed to parse JSON: {:?}", err);
+    String::from("Failed to parse JSON")
+}
+
+/// Parses an option chain JSON array, logging the parser error.
+/// A raw Upstox option chain response, with its `data` wrapper, is accepted
+/// as well.
+///
+/// Expiries in any supported vendor format are rewritten to ISO `YYYY-MM-DD`;
+/// unparseable ones are left as given (see `has_valid_expiry`).
+pub fn parse(optionchain: &str) -> Result<Vec<Instrument>, String> {
+    let mut instruments = if optionchain.trim_start().starts_with('{') {
+        upstox::parse(optionchain)?
+    } else {
+        serde_json::from_str::<Vec<Instrument>>(optionchain).map_err(json_error)?
+    };
+    normalize_expiries(&mut instruments);
+    Ok(instruments)
+}
+
+/// Rewrites expiries in any supported vendor format to ISO `YYYY-MM-DD`.
+pub fn normalize_expiries(instruments: &mut [Instrument]) {
+    for instrument in instruments {
+        if let Some(iso) = dates::normalize_expiry(&instrument.expiry) {
+            instrument.expiry = iso;
+        }
+    }
+}
+
+/// Whether the instrument's expiry could be parsed into a date.
+pub fn has_valid_expiry(instrument: &Instrument) -> bool {
+    dates::parse_expiry(&instrument.expiry).is_some()
+}
+
+/// Identifies a strike across chains: underlying, expiry and strike bits.
+pub type StrikeKey = (String, String, u64);
+
+pub fn strike_key(instrument: &Instrument) -> StrikeKey {
+    (
+        instrument.underlying_key.clone(),
+        instrument.expiry.clone(),
+        instrument.strike_price.to_bits(),
+    )
+}
+
+/// Row of every strike by underlying, then expiry, so builders can look a
+/// strike up without scanning the chain. Rows keep their index as long as the
+/// chain isn't reordered; quote updates don't move them.
+#[derive(Debug, Clone, Default)]
+pub struct StrikeIndex {
+    rows: HashMap<String, BTreeMap<String, HashMap<u64, usize>>>,
+}
+
+impl StrikeIndex {
+    /// Indexes `instruments` in one pass; the first row wins for a strike
+    /// listed twice.
+    pub fn build(instruments: &[Instrument]) -> Self {
+        let mut rows: HashMap<String, BTreeMap<String, HashMap<u64, usize>>> = HashMap::new();
+        for (row, instrument) in instruments.iter().enumerate() {
+            rows.entry(instrument.underlying_key.clone())
+                .or_default()
+                .entry(instrument.expiry.clone())
+                .or_default()
+                .entry(instrument.strike_price.to_bits())
+                .or_insert(row);
+        }
+        StrikeIndex { rows }
+    }
+
+    /// Row of `strike` on `underlying_key`'s `expiry`.
+    pub fn row(&self, underlying_key: &str, expiry: &str, strike: f64) -> Option<usize> {
+        self.rows
+            .get(underlying_key)?
+            .get(expiry)?
+            .get(&strike.to_bits())
+            .copied()
+    }
+
+    /// Expiries listed for `underlying_key`, ascending.
+    pub fn expiries<'a>(&'a self, underlying_key: &str) -> impl Iterator<Item = &'a str> {
+        self.rows
+            .get(underlying_key)
+            .into_iter()
+            .flat_map(|expiries| expiries.keys().map(String::as_str))
+    }
+}
+
+/// Buckets instruments by `key`, keeping each bucket in input order.
+pub fn group_by<K: Ord>(
+    instruments: Vec<Instrument>,
+    key: impl Fn(&Instrument) -> K,
+) -> BTreeMap<K, Vec<Instrument>> {
+    let mut groups: BTreeMap<K, Vec<Instrument>> = BTreeMap::new();
+    for instrument in instruments {
+        groups.entry(key(&instrument)).or_default().push(instrument);
+    }
+    groups
+}
+
+/// The call or put side of a strike.
+pub fn option(instrument: &Instrument, kind: OptionKind) -> Option<&OptionData> {
+    match kind {
+        OptionKind::Call => instrument.call_options.as_ref(),
+        OptionKind::Put => instrument.put_options.as_ref(),
+    }
+}
+
+/// The strike closest to spot among `instruments`, which should share one
+/// underlying and expiry. `atm_strike`, the straddle views and the
+/// calendar's default strike all pick their ATM row here.
+pub fn atm<'a>(instruments: impl IntoIterator<Item = &'a Instrument>) -> Option<&'a Instrument> {
+    instruments.into_iter().min_by(|a, b| {
+        let distance = |i: &Instrument| (i.strike_price - i.underlying_spot_price).abs();
+        distance(a)
+            .partial_cmp(&distance(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })
+}
+
+// Strikes closer than this, in index points, are the same strike
+const STRIKE_EPSILON: f64 = 1e-6;
+
+fn sorted_strikes(instruments: &[Instrument]) -> Vec<f64> {
+    let mut strikes: Vec<f64> = instruments.iter().map(|i| i.strike_price).collect();
+    strikes.sort_by(f64::total_cmp);
+    strikes.dedup_by(|a, b| (*a - *b).abs() < STRIKE_EPSILON);
+    strikes
+}
+
+/// The listed strike interval, e.g. 50 for NIFTY or 100 for BANKNIFTY: the
+/// most common gap between neighbouring strikes, the smaller on a tie.
+/// `instruments` should share one underlying and expiry.
+pub fn strike_step(instruments: &[Instrument]) -> Option<f64> {
+    let strikes = sorted_strikes(instruments);
+    // Gaps are counted in paise so equal steps compare exactly
+    let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+    for pair in strikes.windows(2) {
+        *counts
+            .entry(((pair[1] - pair[0]) * 100.0).round() as i64)
+            .or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(gap, _)| *gap > 0)
+        .max_by(|(a_gap, a_count), (b_gap, b_count)| a_count.cmp(b_count).then(b_gap.cmp(a_gap)))
+        .map(|(gap, _)| gap as f64 / 100.0)
+}
+
+/// Whether `strike` is a whole multiple of `step`.
+pub fn on_step(strike: f64, step: f64) -> bool {
+    let steps = strike / step;
+    (steps - steps.round()).abs() * step < STRIKE_EPSILON
+}
+
+/// Strikes `step` apart between the lowest and highest listed strike that
+/// the chain doesn't list, ascending.
+pub fn missing_strikes(instruments: &[Instrument], step: f64) -> Vec<f64> {
+    let strikes = sorted_strikes(instruments);
+    let (Some(&low), Some(&high)) = (strikes.first(), strikes.last()) else {
+        return Vec::new();
+    };
+    (1..)
+        .map(|k| low + k as f64 * step)
+        .take_while(|strike| *strike < high - STRIKE_EPSILON)
+        .filter(|strike| {
+            strikes
+                .binary_search_by(|listed| {
+                    if (listed - strike).abs() < STRIKE_EPSILON {
+                        std::cmp::Ordering::Equal
+                    } else {
+                        listed.total_cmp(strike)
+                    }
+                })
+                .is_err()
+        })
+        .collect()
+}
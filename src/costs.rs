@@ -0,0 +1,58 @@
+//! Brokerage and statutory charges on Indian index option orders.
+//!
+//! Percentage charges apply to premium turnover (price times quantity):
+//! STT on the sell side, stamp duty on the buy side, and exchange and SEBI
+//! fees on both. GST is levied on brokerage plus exchange and SEBI fees.
+//! Defaults are NSE rates with a flat-fee discount broker.
+
+use crate::positions::Side;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(default)]
+pub struct BrokerageProfile {
+    /// Flat brokerage per executed order, in rupees.
+    pub brokerage_per_order: f64,
+    // Rates below are fractions of premium turnover
+    pub stt_rate: f64,
+    pub exchange_rate: f64,
+    pub sebi_rate: f64,
+    pub stamp_duty_rate: f64,
+    pub gst_rate: f64,
+}
+
+impl Default for BrokerageProfile {
+    fn default() -> Self {
+        BrokerageProfile {
+            brokerage_per_order: 20.0,
+            stt_rate: 0.001,
+            exchange_rate: 0.000_350_3,
+            sebi_rate: 0.000_001,
+            stamp_duty_rate: 0.000_03,
+            gst_rate: 0.18,
+        }
+    }
+}
+
+/// Total charges, in rupees, for executing one order per `(side, turnover)`.
+pub fn total_charges(orders: &[(Side, f64)], profile: &BrokerageProfile) -> f64 {
+    let turnover: f64 = orders.iter().map(|(_, turnover)| turnover).sum();
+    let side_turnover = |wanted: Side| -> f64 {
+        orders
+            .iter()
+            .filter(|(side, _)| *side == wanted)
+            .map(|(_, turnover)| turnover)
+            .sum()
+    };
+
+    let brokerage = profile.brokerage_per_order * orders.len() as f64;
+    let stt = side_turnover(Side::Sell) * profile.stt_rate;
+    let exchange = turnover * profile.exchange_rate;
+    let sebi = turnover * profile.sebi_rate;
+    let stamp_duty = side_turnover(Side::Buy) * profile.stamp_duty_rate;
+    let gst = (brokerage + exchange + sebi) * profile.gst_rate;
+    brokerage + stt + exchange + sebi + stamp_duty + gst
+}
@@ -0,0 +1,249 @@
+//! Options paired with a long index future.
+//!
+//! A covered call is a long future with a call sold above it. It has the
+//! payoff of a naked short put at the same strike (long future minus call is
+//! a short put), so that put's margin stands in for the combined margin of
+//! the pair, in place of charging the future and the option separately.
+//!
+//! A protective put is a long future with a put bought below it, which caps
+//! the loss at the strike. A put bought against a short future would add to
+//! the short rather than protect it, so the protective put here hedges a
+//! long future, as the textbook structure does. The pair can't lose more
+//! than its max loss, so that caps the future's margin.
+
+use crate::benchmark::DEFAULT_FUTURES_MARGIN_PERCENTAGE;
+use crate::futures::{self, FuturesQuote};
+use crate::greeks::OptionKind;
+use crate::payoff::{self, PayoffPoint};
+use crate::positions::{self, Leg, PriceMode, Side};
+use crate::spreads::{Direction, StrategyType};
+use crate::{chain, dates, lots, margin, store};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+// Strikes past the future considered per expiry when `max_strikes` isn't given
+const DEFAULT_MAX_STRIKES: usize = 10;
+// Half-width of the payoff curve's price range, as a percent of the future
+const PAYOFF_RANGE_PCT: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct CoveredParams {
+    #[serde(default)]
+    optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
+    /// One quote per expiry to hedge with; expiries without one are skipped.
+    futures: Vec<FuturesQuote>,
+    #[serde(default)]
+    expiry: Option<String>,
+    #[serde(default)]
+    lot_size: Option<f64>,
+    /// Quote the option is traded at; defaults to LTP.
+    #[serde(default)]
+    price_mode: Option<PriceMode>,
+    #[serde(default)]
+    margin_model: Option<margin::MarginModel>,
+    /// Margin on the future alone, as a percent of notional; defaults to 12.
+    #[serde(default)]
+    futures_margin_percentage: Option<f64>,
+    /// Out-of-the-money strikes per expiry, nearest the future first;
+    /// defaults to 10.
+    #[serde(default)]
+    max_strikes: Option<usize>,
+    /// Adds an expiry payoff curve of this many steps over the future's
+    /// price plus or minus 10%.
+    #[serde(default)]
+    payoff_steps: Option<u32>,
+    /// Epoch milliseconds to measure days to expiry from; defaults to the current time.
+    #[serde(default)]
+    now: Option<f64>,
+}
+
+/// One lot of the long future and one of the option. Rupee figures are per
+/// lot.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct CoveredPosition {
+    underlying_key: String,
+    expiry: String,
+    dte: Option<i64>,
+    strategy_type: StrategyType,
+    direction: Direction,
+    futures_price: f64,
+    basis: f64,
+    lot_size: f64,
+    /// The call sold, or the put bought.
+    leg: Leg,
+    premium_per_lot: f64,
+    /// `None` for a protective put, whose profit has no bound.
+    max_profit: Option<f64>,
+    max_loss: f64,
+    breakeven: f64,
+    /// Margin for the pair: the equivalent naked short put for a covered
+    /// call, the future's margin capped at `max_loss` for a protective put.
+    combined_margin: f64,
+    futures_margin: f64,
+    /// Margin the pair saves over the future and the option apart.
+    margin_benefit: f64,
+    /// Max profit as a percent of `combined_margin`; `None` for a
+    /// protective put.
+    return_on_margin: Option<f64>,
+    /// Expiry P&L per lot; present when `payoff_steps` is set.
+    payoff: Option<Vec<PayoffPoint>>,
+}
+
+/// Covered calls (`kind` call) by return on margin, or protective puts (`kind`
+/// put) cheapest protection first, for every expiry with a futures quote.
+pub fn scan(kind: OptionKind, params: &CoveredParams) -> Result<Vec<CoveredPosition>, String> {
+    let mut instruments = store::resolve(params.chain_id, &params.optionchain)?;
+    if let Some(expiry) = &params.expiry {
+        instruments.retain(|instrument| dates::same_expiry(&instrument.expiry, expiry));
+    }
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let mode = params.price_mode.unwrap_or_default();
+    let model = params.margin_model.unwrap_or_default();
+    let margin_percentage = params
+        .futures_margin_percentage
+        .unwrap_or(DEFAULT_FUTURES_MARGIN_PERCENTAGE);
+    let option_side = match kind {
+        OptionKind::Call => Side::Sell,
+        OptionKind::Put => Side::Buy,
+    };
+
+    let mut positions = Vec::new();
+    let slices = chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    });
+    for ((underlying_key, expiry), instruments) in slices {
+        let Some(future) = futures::find(&params.futures, &underlying_key, &expiry) else {
+            continue;
+        };
+        let forward = future.price;
+        let lot_size = lots::resolve(params.lot_size, &underlying_key)?;
+        let futures_margin = forward * lot_size * margin_percentage / 100.0;
+
+        let mut rows: Vec<_> = instruments
+            .iter()
+            .filter(|instrument| match kind {
+                OptionKind::Call => instrument.strike_price > forward,
+                OptionKind::Put => instrument.strike_price < forward,
+            })
+            .filter_map(|instrument| {
+                let option = chain::option(instrument, kind)?;
+                let market_data = option.market_data.as_ref()?;
+                let premium = positions::fill_price(market_data, option_side, mode)?;
+                Some((instrument, option, premium))
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            (a.0.strike_price - forward)
+                .abs()
+                .total_cmp(&(b.0.strike_price - forward).abs())
+        });
+        rows.truncate(params.max_strikes.unwrap_or(DEFAULT_MAX_STRIKES));
+
+        for (instrument, option, premium) in rows {
+            let strike = instrument.strike_price;
+            let leg = Leg {
+                instrument_key: Some(option.instrument_key.clone()).filter(|k| !k.is_empty()),
+                option_type: kind,
+                strike,
+                expiry: Some(expiry.clone()),
+                side: option_side,
+                lots: 1.0,
+                price: premium,
+                iv: option.option_greeks.as_ref().and_then(|g| g.iv),
+            };
+            // Per unit: the long future's P&L from `forward` plus the option's
+            let pnl = |price: f64| {
+                price - forward + payoff::expiry_pnl(std::slice::from_ref(&leg), price)
+            };
+            let (max_profit, max_loss, breakeven) = match kind {
+                OptionKind::Call => (
+                    Some((strike - forward + premium) * lot_size),
+                    (forward - premium) * lot_size,
+                    forward - premium,
+                ),
+                OptionKind::Put => (
+                    None,
+                    (forward - strike + premium) * lot_size,
+                    forward + premium,
+                ),
+            };
+
+            let as_short = |kind: OptionKind| margin::ShortLeg {
+                kind,
+                strike,
+                spot: forward,
+                lot_size,
+                hedge_strike: None,
+            };
+            // A bought option needs no margin of its own
+            let (combined_margin, option_margin) = match kind {
+                OptionKind::Call => (
+                    margin::estimate(&as_short(OptionKind::Put), &model),
+                    margin::naked(&as_short(OptionKind::Call), &model),
+                ),
+                OptionKind::Put => (futures_margin.min(max_loss.max(0.0)), 0.0),
+            };
+
+            let payoff = match params.payoff_steps {
+                Some(steps) => {
+                    let half_width = forward * PAYOFF_RANGE_PCT / 100.0;
+                    let prices =
+                        payoff::price_grid(forward - half_width, forward + half_width, steps)?;
+                    Some(
+                        prices
+                            .into_iter()
+                            .map(|price| PayoffPoint::new(price, pnl(price) * lot_size))
+                            .collect(),
+                    )
+                }
+                None => None,
+            };
+
+            positions.push(CoveredPosition {
+                underlying_key: underlying_key.clone(),
+                expiry: expiry.clone(),
+                dte: dates::days_to_expiry(&expiry, now_ms),
+                strategy_type: match kind {
+                    OptionKind::Call => StrategyType::CoveredCall,
+                    OptionKind::Put => StrategyType::ProtectivePut,
+                },
+                // The long future sets the direction, as a call bought would
+                direction: Direction::of(OptionKind::Call, Side::Buy),
+                futures_price: forward,
+                basis: future.basis(instrument.underlying_spot_price),
+                lot_size,
+                leg,
+                premium_per_lot: premium * lot_size,
+                max_profit,
+                max_loss,
+                breakeven,
+                combined_margin,
+                futures_margin,
+                margin_benefit: futures_margin + option_margin - combined_margin,
+                return_on_margin: max_profit
+                    .filter(|_| combined_margin > 0.0)
+                    .map(|max_profit| max_profit / combined_margin * 100.0),
+                payoff,
+            });
+        }
+    }
+
+    match kind {
+        OptionKind::Call => {
+            positions.sort_by(|a, b| match (a.return_on_margin, b.return_on_margin) {
+                (Some(a), Some(b)) => b.total_cmp(&a),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            })
+        }
+        OptionKind::Put => positions.sort_by(|a, b| a.max_loss.total_cmp(&b.max_loss)),
+    }
+    Ok(positions)
+}
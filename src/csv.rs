@@ -0,0 +1,168 @@
+//! Option chains as CSV, one row per strike.
+//!
+//! Columns are matched by header name, in any order. `expiry`,
+//! `strike_price`, `underlying_key` and `underlying_spot_price` are
+//! required. Each option side takes the `MarketData` and `OptionGreeks`
+//! field names prefixed with `ce_` or `pe_`, e.g. `ce_ltp` or `pe_iv`, plus
+//! `ce_instrument_key` and `pe_instrument_key`. A side with no values in the
+//! row is left out. Empty cells read as missing.
+
+use crate::source::ChainSource;
+use crate::{dates, Instrument, MarketData, OptionData, OptionGreeks};
+use std::collections::HashMap;
+
+/// Splits one CSV record, honouring double-quoted fields.
+pub fn fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+struct Record<'a> {
+    columns: &'a HashMap<String, usize>,
+    values: Vec<String>,
+}
+
+impl Record<'_> {
+    fn text(&self, column: &str) -> Option<&str> {
+        let value = self.values.get(*self.columns.get(column)?)?.trim();
+        (!value.is_empty()).then_some(value)
+    }
+
+    fn number(&self, column: &str) -> Option<f64> {
+        self.text(column)?.parse().ok()
+    }
+
+    fn count(&self, column: &str) -> Option<u64> {
+        self.number(column)
+            .filter(|value| *value >= 0.0)
+            .map(|value| value.round() as u64)
+    }
+
+    fn option(&self, prefix: &str) -> Option<OptionData> {
+        let has_values = self
+            .columns
+            .keys()
+            .any(|column| column.starts_with(prefix) && self.text(column).is_some());
+        if !has_values {
+            return None;
+        }
+
+        let column = |field: &str| format!("{}{}", prefix, field);
+        let market_data = MarketData {
+            ltp: self.number(&column("ltp")),
+            volume: self.count(&column("volume")),
+            oi: self.count(&column("oi")),
+            close_price: self.number(&column("close_price")),
+            bid_price: self.number(&column("bid_price")),
+            bid_qty: self.count(&column("bid_qty")),
+            ask_price: self.number(&column("ask_price")),
+            ask_qty: self.count(&column("ask_qty")),
+            prev_oi: self.count(&column("prev_oi")),
+        };
+        let greeks = OptionGreeks {
+            vega: self.number(&column("vega")),
+            theta: self.number(&column("theta")),
+            gamma: self.number(&column("gamma")),
+            delta: self.number(&column("delta")),
+            iv: self.number(&column("iv")),
+            computed: false,
+            mismatch: false,
+        };
+        let has_greeks = [
+            greeks.vega,
+            greeks.theta,
+            greeks.gamma,
+            greeks.delta,
+            greeks.iv,
+        ]
+        .iter()
+        .any(Option::is_some);
+
+        Some(OptionData {
+            instrument_key: self
+                .text(&column("instrument_key"))
+                .unwrap_or_default()
+                .to_string(),
+            market_data: Some(market_data),
+            option_greeks: has_greeks.then_some(greeks),
+        })
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Instrument>, String> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let columns: HashMap<String, usize> = fields(lines.next().unwrap_or_default())
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_ascii_lowercase(), index))
+        .collect();
+    for required in [
+        "expiry",
+        "strike_price",
+        "underlying_key",
+        "underlying_spot_price",
+    ] {
+        if !columns.contains_key(required) {
+            return Err(format!("CSV chain is missing the {} column", required));
+        }
+    }
+
+    lines
+        .enumerate()
+        .map(|(index, line)| {
+            let record = Record {
+                columns: &columns,
+                values: fields(line),
+            };
+            // Line numbers count the header
+            let invalid = |column: &str| format!("Invalid {} on CSV line {}", column, index + 2);
+            let expiry = record.text("expiry").ok_or_else(|| invalid("expiry"))?;
+            Ok(Instrument {
+                expiry: dates::normalize_expiry(expiry).unwrap_or_else(|| expiry.to_string()),
+                strike_price: record
+                    .number("strike_price")
+                    .ok_or_else(|| invalid("strike_price"))?,
+                underlying_key: record
+                    .text("underlying_key")
+                    .unwrap_or_default()
+                    .to_string(),
+                underlying_spot_price: record
+                    .number("underlying_spot_price")
+                    .ok_or_else(|| invalid("underlying_spot_price"))?,
+                call_options: record.option("ce_"),
+                put_options: record.option("pe_"),
+                exercise_style: None,
+            })
+        })
+        .collect()
+}
+
+pub struct CsvSource;
+
+impl ChainSource for CsvSource {
+    fn format(&self) -> &'static str {
+        "csv"
+    }
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+        let text = data
+            .as_str()
+            .ok_or_else(|| String::from("CSV chain must be a string"))?;
+        parse(text)
+    }
+}
@@ -0,0 +1,269 @@
+//! Calendar helpers for option expiries.
+//!
+//! Dates are handled as day numbers counted from 1970-01-01 so differences
+//! are plain integer subtraction. Vendor expiry strings are normalized to ISO
+//! `YYYY-MM-DD` when the chain is parsed.
+
+use serde::{Deserialize, Deserializer, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+pub const MS_PER_DAY: f64 = 86_400_000.0;
+// Exchange dates are Indian dates; IST is UTC+05:30 with no daylight saving.
+const IST_OFFSET_MS: f64 = 19_800_000.0;
+// Options expire at the 15:30 IST market close
+const EXPIRY_CLOSE_IST_MS: f64 = 55_800_000.0;
+pub const HOURS_PER_YEAR: f64 = 365.0 * 24.0;
+// Day 0, 1970-01-01, was a Thursday
+const THURSDAY: i64 = 0;
+
+/// Day number of a proleptic Gregorian date (Howard Hinnant's `days_from_civil`).
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of `days_from_civil`: `(year, month, day)` for a day number.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats a day number as `YYYY-MM-DD`.
+pub fn to_iso(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+fn valid_date(year: i64, month: u32, day: u32) -> Option<i64> {
+    if (1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month) {
+        Some(days_from_civil(year, month, day))
+    } else {
+        None
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+fn month_from_abbreviation(abbreviation: &str) -> Option<u32> {
+    MONTHS
+        .iter()
+        .position(|month| month.eq_ignore_ascii_case(abbreviation))
+        .map(|index| index as u32 + 1)
+}
+
+// YYYY-MM-DD
+fn parse_iso(expiry: &str) -> Option<i64> {
+    let mut parts = expiry.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    valid_date(year, month, day)
+}
+
+fn parse_year(year: &str) -> Option<i64> {
+    let value: i64 = year.parse().ok()?;
+    match year.len() {
+        2 => Some(2000 + value),
+        4 => Some(value),
+        _ => None,
+    }
+}
+
+// 26-Dec-2024 or 26-Dec-24
+fn parse_dashed(expiry: &str) -> Option<i64> {
+    let mut parts = expiry.splitn(3, '-');
+    let day = parts.next()?.parse().ok()?;
+    let month = month_from_abbreviation(parts.next()?)?;
+    let year = parse_year(parts.next()?)?;
+    valid_date(year, month, day)
+}
+
+// NSE style DDMMMYYYY or DDMMMYY, e.g. 26DEC2024 or 26DEC24
+fn parse_nse(expiry: &str) -> Option<i64> {
+    if !matches!(expiry.len(), 7 | 9) || !expiry.is_ascii() {
+        return None;
+    }
+    let day = expiry[0..2].parse().ok()?;
+    let month = month_from_abbreviation(&expiry[2..5])?;
+    let year = parse_year(&expiry[5..])?;
+    valid_date(year, month, day)
+}
+
+// Epoch milliseconds, read as the IST calendar date of that instant
+fn parse_epoch_millis(expiry: &str) -> Option<i64> {
+    if expiry.len() < 11 || !expiry.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    expiry.parse::<f64>().ok().map(ist_day)
+}
+
+/// Parses an expiry in any supported vendor format into a day number:
+/// `2024-12-26`, `26-Dec-2024`, `26DEC2024`, `26DEC24` or epoch milliseconds.
+pub fn parse_expiry(expiry: &str) -> Option<i64> {
+    let expiry = expiry.trim();
+    parse_iso(expiry)
+        .or_else(|| parse_dashed(expiry))
+        .or_else(|| parse_nse(expiry))
+        .or_else(|| parse_epoch_millis(expiry))
+}
+
+/// The ISO form of a vendor expiry string, or `None` if it can't be parsed.
+pub fn normalize_expiry(expiry: &str) -> Option<String> {
+    parse_expiry(expiry).map(to_iso)
+}
+
+/// Accepts an expiry given either as a string or as a number of epoch milliseconds.
+pub fn deserialize_expiry<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawExpiry {
+        Text(String),
+        Millis(f64),
+    }
+
+    Ok(match RawExpiry::deserialize(deserializer)? {
+        RawExpiry::Text(text) => text,
+        RawExpiry::Millis(millis) => format!("{:.0}", millis),
+    })
+}
+
+/// Current time as epoch milliseconds.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Current time as epoch milliseconds.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+pub fn now_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_millis() as f64)
+}
+
+/// The Indian calendar day containing the epoch-millisecond instant `ms`.
+pub fn ist_day(ms: f64) -> i64 {
+    ((ms + IST_OFFSET_MS) / MS_PER_DAY).floor() as i64
+}
+
+/// Epoch milliseconds of the 15:30 IST close on day number `day`.
+pub fn expiry_close_ms(day: i64) -> f64 {
+    day as f64 * MS_PER_DAY + EXPIRY_CLOSE_IST_MS - IST_OFFSET_MS
+}
+
+/// Time from `now_ms` until the expiry-day close, in years of 365 days;
+/// `None` if the expiry is unparseable or already past.
+pub fn years_to_expiry(expiry: &str, now_ms: f64) -> Option<f64> {
+    let remaining_ms = expiry_close_ms(parse_expiry(expiry)?) - now_ms;
+    (remaining_ms > 0.0).then(|| remaining_ms / (365.0 * MS_PER_DAY))
+}
+
+/// Time from `now_ms` until the expiry-day close, in hours; `None` if the
+/// expiry is unparseable or already past.
+pub fn hours_to_expiry(expiry: &str, now_ms: f64) -> Option<f64> {
+    years_to_expiry(expiry, now_ms).map(|years| years * HOURS_PER_YEAR)
+}
+
+/// `years_to_expiry`, unless `override_hours` gives the time left directly,
+/// as on expiry day when the clock the chain was captured at is unreliable.
+pub fn time_to_expiry(expiry: &str, now_ms: f64, override_hours: Option<f64>) -> Option<f64> {
+    match override_hours {
+        Some(hours) => (hours > 0.0).then(|| hours / HOURS_PER_YEAR),
+        None => years_to_expiry(expiry, now_ms),
+    }
+}
+
+/// Calendar days from the IST date of `now_ms` until `expiry`; `None` if unparseable.
+pub fn days_to_expiry(expiry: &str, now_ms: f64) -> Option<i64> {
+    parse_expiry(expiry).map(|expiry_day| expiry_day - ist_day(now_ms))
+}
+
+/// Whether two expiry strings name the same day, falling back to text comparison.
+pub fn same_expiry(a: &str, b: &str) -> bool {
+    match (parse_expiry(a), parse_expiry(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.trim() == b.trim(),
+    }
+}
+
+/// Whether an expiry is the month's last, the one futures share, or one of
+/// the weeklies before it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryCycle {
+    Weekly,
+    Monthly,
+}
+
+/// Day numbers of the parseable dates in `holidays`.
+pub fn holiday_days(holidays: &[String]) -> Vec<i64> {
+    holidays
+        .iter()
+        .filter_map(|holiday| parse_expiry(holiday))
+        .collect()
+}
+
+/// Neither a weekend nor one of `holidays`.
+pub fn is_trading_day(day: i64, holidays: &[i64]) -> bool {
+    // Day 0 was a Thursday, so Saturday and Sunday are 2 and 3
+    !matches!((day - THURSDAY).rem_euclid(7), 2 | 3) && !holidays.contains(&day)
+}
+
+/// The month's last Thursday, brought forward to the trading day before it
+/// when it falls on one of `holidays`.
+pub fn last_thursday(year: i64, month: u32, holidays: &[i64]) -> i64 {
+    let last = days_from_civil(year, month, days_in_month(year, month));
+    let mut day = last - (last - THURSDAY).rem_euclid(7);
+    while !is_trading_day(day, holidays) {
+        day -= 1;
+    }
+    day
+}
+
+/// Monthly for the holiday-adjusted last Thursday, or for the last expiry
+/// on its weekday in the month, which covers exchanges that moved expiry
+/// day; weekly otherwise. `None` if the expiry is unparseable.
+pub fn expiry_cycle(expiry: &str, holidays: &[i64]) -> Option<ExpiryCycle> {
+    let day = parse_expiry(expiry)?;
+    let (year, month, _) = civil_from_days(day);
+    let (_, next_month, _) = civil_from_days(day + 7);
+    Some(
+        if next_month != month || day == last_thursday(year, month, holidays) {
+            ExpiryCycle::Monthly
+        } else {
+            ExpiryCycle::Weekly
+        },
+    )
+}
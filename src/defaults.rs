@@ -0,0 +1,72 @@
+//! Spread scan parameters set once and inherited by every scan.
+//!
+//! The UI stores the user's preferences, such as lot size, brokerage profile
+//! or filters, with `set_defaults`; each spread scan then parses its params
+//! over them. Fields a scan's own params give win; fields they leave out, or
+//! set to null, take the default. The module is single-threaded, so the
+//! defaults live in a thread local.
+
+use crate::BearCallSpreadParams;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+
+thread_local! {
+    static DEFAULTS: RefCell<Map<String, Value>> = RefCell::new(Map::new());
+}
+
+/// `params` with every default it leaves out or null filled in.
+fn overlay(defaults: &Map<String, Value>, params: Value) -> Value {
+    let Value::Object(params) = params else {
+        return params;
+    };
+    let mut merged = defaults.clone();
+    for (key, value) in params {
+        if !value.is_null() {
+            merged.insert(key, value);
+        }
+    }
+    Value::Object(merged)
+}
+
+/// Replaces the defaults with `defaults`, an object of spread scan params,
+/// or clears them for null.
+pub fn set(defaults: Value) -> Result<(), String> {
+    let defaults = match defaults {
+        Value::Null => Map::new(),
+        Value::Object(defaults) => defaults,
+        _ => return Err(String::from("Defaults must be an object")),
+    };
+    // Every default has to fit the params it will be merged into
+    let base = serde_json::to_value(BearCallSpreadParams::default())
+        .map_err(|_| String::from("Failed to serialize parameters"))?;
+    serde_json::from_value::<BearCallSpreadParams>(overlay(&defaults, base))
+        .map_err(|err| format!("Invalid defaults: {}", err))?;
+    DEFAULTS.set(defaults);
+    Ok(())
+}
+
+pub fn get() -> Value {
+    DEFAULTS.with_borrow(|defaults| Value::Object(defaults.clone()))
+}
+
+pub fn is_empty() -> bool {
+    DEFAULTS.with_borrow(Map::is_empty)
+}
+
+/// Parses `params` with the defaults filled in.
+pub fn parse<T: DeserializeOwned>(params: Value) -> serde_json::Result<T> {
+    let merged = DEFAULTS.with_borrow(|defaults| overlay(defaults, params));
+    serde_json::from_value(merged)
+}
+
+/// The plain-object defaults, before any filters are added, with the stored
+/// defaults on top.
+pub fn params() -> BearCallSpreadParams {
+    if is_empty() {
+        return BearCallSpreadParams::default();
+    }
+    serde_json::to_value(BearCallSpreadParams::default())
+        .and_then(parse)
+        .unwrap_or_default()
+}
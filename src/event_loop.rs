@@ -0,0 +1,25 @@
+//! Yielding to the JS event loop from the async exports.
+//!
+//! A resolved promise only defers to the microtask queue, which runs before
+//! the browser paints or handles input. Waiting on a zero-delay `setTimeout`
+//! ends the current task, so the page stays responsive between chunks of
+//! work. `setTimeout` is looked up on the global object, which works on the
+//! main thread and in Web Workers alike.
+
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Resolves on a later task; immediately if the host has no `setTimeout`.
+pub async fn yield_now() {
+    let Some(set_timeout) = Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+        .ok()
+        .and_then(|value| value.dyn_into::<Function>().ok())
+    else {
+        return;
+    };
+    let promise = Promise::new(&mut |resolve, _reject| {
+        let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+    });
+    let _ = JsFuture::from(promise).await;
+}
@@ -0,0 +1,47 @@
+//! Futures quotes supplied alongside the chain.
+//!
+//! Index options settle on spot but are hedged and priced off the future, so
+//! with a quote for an expiry's future, moneyness is measured against the
+//! future's price instead of spot. `basis` is the future's premium over spot.
+
+use crate::dates;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct FuturesQuote {
+    /// Expiry of the future, in any supported format.
+    pub expiry: String,
+    /// Underlying the future is on; applies to every underlying when absent.
+    #[serde(default)]
+    pub underlying_key: Option<String>,
+    pub price: f64,
+    #[serde(default)]
+    pub oi: Option<u64>,
+    /// Future minus spot; worked out from the chain's spot when absent.
+    #[serde(default)]
+    pub basis: Option<f64>,
+}
+
+impl FuturesQuote {
+    pub fn basis(&self, spot: f64) -> f64 {
+        self.basis.unwrap_or(self.price - spot)
+    }
+}
+
+/// The quote for `underlying_key`'s future expiring on `expiry`.
+pub fn find<'a>(
+    quotes: &'a [FuturesQuote],
+    underlying_key: &str,
+    expiry: &str,
+) -> Option<&'a FuturesQuote> {
+    quotes.iter().find(|quote| {
+        quote
+            .underlying_key
+            .as_deref()
+            .is_none_or(|key| key == underlying_key)
+            && dates::same_expiry(&quote.expiry, expiry)
+    })
+}
@@ -0,0 +1,497 @@
+//! Black-Scholes pricing, greeks and implied volatility.
+//!
+//! Volatility is an annualized decimal (0.15) inside this module and a
+//! percentage (15.0) in `OptionGreeks`, matching the broker feed. Greeks are
+//! reported in feed units too: theta per calendar day, vega per 1 vol point.
+
+use crate::{binomial, dates, Instrument, MarketData, OptionData, OptionGreeks};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+// Roughly the 91-day T-bill yield
+pub const RISK_FREE_RATE: f64 = 0.065;
+// Index premiums track futures, which already price in dividends
+pub const DIVIDEND_YIELD: f64 = 0.0;
+
+const MIN_VOLATILITY: f64 = 1e-4;
+const MAX_VOLATILITY: f64 = 5.0;
+const PRICE_TOLERANCE: f64 = 1e-8;
+const MAX_ITERATIONS: usize = 100;
+// Default cross-check tolerances: delta, and IV in vol points
+const DELTA_TOLERANCE: f64 = 0.1;
+const IV_TOLERANCE: f64 = 5.0;
+
+/// When an option can be exercised. Index options are European; American
+/// ones are priced on a binomial tree.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ExerciseStyle {
+    #[default]
+    European,
+    American,
+}
+
+/// Model inputs beyond the option itself: carry as annualized decimals, and
+/// the exercise style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pricing {
+    pub risk_free_rate: f64,
+    pub dividend_yield: f64,
+    pub exercise_style: ExerciseStyle,
+}
+
+impl Pricing {
+    /// The given inputs, falling back to the defaults for Indian index options.
+    pub fn new(
+        risk_free_rate: Option<f64>,
+        dividend_yield: Option<f64>,
+        exercise_style: Option<ExerciseStyle>,
+    ) -> Pricing {
+        Pricing {
+            risk_free_rate: risk_free_rate.unwrap_or(RISK_FREE_RATE),
+            dividend_yield: dividend_yield.unwrap_or(DIVIDEND_YIELD),
+            exercise_style: exercise_style.unwrap_or_default(),
+        }
+    }
+
+    pub fn price(&self, kind: OptionKind, spot: f64, strike: f64, t: f64, sigma: f64) -> f64 {
+        let (r, q) = (self.risk_free_rate, self.dividend_yield);
+        match self.exercise_style {
+            ExerciseStyle::European => price(kind, spot, strike, t, r, q, sigma),
+            ExerciseStyle::American => binomial::price(kind, spot, strike, t, r, q, sigma),
+        }
+    }
+
+    pub fn greeks(&self, kind: OptionKind, spot: f64, strike: f64, t: f64, sigma: f64) -> Greeks {
+        let (r, q) = (self.risk_free_rate, self.dividend_yield);
+        match self.exercise_style {
+            ExerciseStyle::European => greeks(kind, spot, strike, t, r, q, sigma),
+            ExerciseStyle::American => binomial::greeks(kind, spot, strike, t, r, q, sigma),
+        }
+    }
+
+    pub fn implied_volatility(
+        &self,
+        kind: OptionKind,
+        option_price: f64,
+        spot: f64,
+        strike: f64,
+        t: f64,
+    ) -> Option<f64> {
+        let (r, q) = (self.risk_free_rate, self.dividend_yield);
+        match self.exercise_style {
+            ExerciseStyle::European => {
+                implied_volatility(kind, option_price, spot, strike, t, r, q)
+            }
+            // Black-Scholes vega only steers the search; the bracket keeps it converging
+            ExerciseStyle::American => solve_volatility(
+                option_price,
+                spot,
+                strike,
+                t,
+                |sigma| binomial::price(kind, spot, strike, t, r, q, sigma),
+                |sigma| greeks(kind, spot, strike, t, r, q, sigma).vega * 100.0,
+            ),
+        }
+    }
+}
+
+impl Default for Pricing {
+    fn default() -> Self {
+        Pricing::new(None, None, None)
+    }
+}
+
+/// Tolerances for checking feed greeks against ones solved from the price.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct GreeksCheck {
+    /// Largest absolute delta difference accepted; defaults to 0.1.
+    #[serde(default)]
+    delta_tolerance: Option<f64>,
+    /// Largest IV difference accepted, in vol points; defaults to 5.
+    #[serde(default)]
+    iv_tolerance: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub enum OptionKind {
+    #[serde(rename = "CE")]
+    Call,
+    #[serde(rename = "PE")]
+    Put,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    /// Per calendar day.
+    pub theta: f64,
+    /// Per 1 point of volatility.
+    pub vega: f64,
+}
+
+fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+// Abramowitz & Stegun 26.2.17, absolute error below 7.5e-8
+fn norm_cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.231_641_9 * x.abs());
+    let poly = t
+        * (0.319_381_530
+            + t * (-0.356_563_782
+                + t * (1.781_477_937 + t * (-1.821_255_978 + t * 1.330_274_429))));
+    let upper_tail = norm_pdf(x) * poly;
+    if x >= 0.0 {
+        1.0 - upper_tail
+    } else {
+        upper_tail
+    }
+}
+
+fn d1_d2(spot: f64, strike: f64, t: f64, r: f64, q: f64, sigma: f64) -> (f64, f64) {
+    let vol_sqrt_t = sigma * t.sqrt();
+    let d1 = ((spot / strike).ln() + (r - q + 0.5 * sigma * sigma) * t) / vol_sqrt_t;
+    (d1, d1 - vol_sqrt_t)
+}
+
+/// Theoretical price per unit of the underlying.
+pub fn price(kind: OptionKind, spot: f64, strike: f64, t: f64, r: f64, q: f64, sigma: f64) -> f64 {
+    let discount = (-r * t).exp();
+    let carry = (-q * t).exp();
+    if t <= 0.0 || sigma <= 0.0 {
+        let forward = spot * carry / discount;
+        return discount
+            * match kind {
+                OptionKind::Call => (forward - strike).max(0.0),
+                OptionKind::Put => (strike - forward).max(0.0),
+            };
+    }
+
+    let (d1, d2) = d1_d2(spot, strike, t, r, q, sigma);
+    match kind {
+        OptionKind::Call => spot * carry * norm_cdf(d1) - strike * discount * norm_cdf(d2),
+        OptionKind::Put => strike * discount * norm_cdf(-d2) - spot * carry * norm_cdf(-d1),
+    }
+}
+
+pub fn greeks(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    r: f64,
+    q: f64,
+    sigma: f64,
+) -> Greeks {
+    let discount = (-r * t).exp();
+    let carry = (-q * t).exp();
+    let (d1, d2) = d1_d2(spot, strike, t, r, q, sigma);
+    let sqrt_t = t.sqrt();
+
+    let gamma = carry * norm_pdf(d1) / (spot * sigma * sqrt_t);
+    let vega = spot * carry * norm_pdf(d1) * sqrt_t / 100.0;
+    let decay = -spot * carry * norm_pdf(d1) * sigma / (2.0 * sqrt_t);
+    let (delta, theta) = match kind {
+        OptionKind::Call => (
+            carry * norm_cdf(d1),
+            decay - r * strike * discount * norm_cdf(d2) + q * spot * carry * norm_cdf(d1),
+        ),
+        OptionKind::Put => (
+            carry * (norm_cdf(d1) - 1.0),
+            decay + r * strike * discount * norm_cdf(-d2) - q * spot * carry * norm_cdf(-d1),
+        ),
+    };
+
+    Greeks {
+        delta,
+        gamma,
+        theta: theta / 365.0,
+        vega,
+    }
+}
+
+/// Risk-neutral probability that the underlying finishes below `level` at
+/// expiry under a lognormal model.
+pub fn probability_below(spot: f64, level: f64, t: f64, r: f64, q: f64, sigma: f64) -> f64 {
+    if t <= 0.0 || sigma <= 0.0 {
+        return if spot < level { 1.0 } else { 0.0 };
+    }
+    let (_, d2) = d1_d2(spot, level, t, r, q, sigma);
+    norm_cdf(-d2)
+}
+
+/// Probability that the underlying trades at `level` at any time before
+/// expiry, for geometric Brownian motion with risk-neutral drift.
+pub fn probability_touch(spot: f64, level: f64, t: f64, r: f64, q: f64, sigma: f64) -> f64 {
+    if spot == level {
+        return 1.0;
+    }
+    if t <= 0.0 || sigma <= 0.0 {
+        return 0.0;
+    }
+    // Reflect a barrier below spot into one above by flipping the drift
+    let (distance, drift) = if level > spot {
+        ((level / spot).ln(), r - q - 0.5 * sigma * sigma)
+    } else {
+        ((spot / level).ln(), -(r - q - 0.5 * sigma * sigma))
+    };
+    let vol_sqrt_t = sigma * t.sqrt();
+    let probability = norm_cdf((-distance + drift * t) / vol_sqrt_t)
+        + (2.0 * drift * distance / (sigma * sigma)).exp()
+            * norm_cdf((-distance - drift * t) / vol_sqrt_t);
+    probability.clamp(0.0, 1.0)
+}
+
+/// Probability that an option of `kind` at `strike` finishes in the money.
+pub fn probability_itm(
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    r: f64,
+    q: f64,
+    sigma: f64,
+) -> f64 {
+    let below = probability_below(spot, strike, t, r, q, sigma);
+    match kind {
+        OptionKind::Call => 1.0 - below,
+        OptionKind::Put => below,
+    }
+}
+
+/// Solves for the volatility that reprices `option_price`.
+///
+/// Newton-Raphson steps are taken while they stay inside the bisection
+/// bracket; otherwise the bracket is halved, so the solver always converges
+/// when a solution exists. Prices outside the no-arbitrage bounds give `None`.
+pub fn implied_volatility(
+    kind: OptionKind,
+    option_price: f64,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    r: f64,
+    q: f64,
+) -> Option<f64> {
+    solve_volatility(
+        option_price,
+        spot,
+        strike,
+        t,
+        |sigma| price(kind, spot, strike, t, r, q, sigma),
+        |sigma| greeks(kind, spot, strike, t, r, q, sigma).vega * 100.0,
+    )
+}
+
+// Safeguarded Newton search for the volatility at which `price` matches
+fn solve_volatility(
+    option_price: f64,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    price: impl Fn(f64) -> f64,
+    vega: impl Fn(f64) -> f64,
+) -> Option<f64> {
+    if t <= 0.0 || option_price <= 0.0 || spot <= 0.0 || strike <= 0.0 {
+        return None;
+    }
+    let objective = |sigma: f64| price(sigma) - option_price;
+
+    let (mut low, mut high) = (MIN_VOLATILITY, MAX_VOLATILITY);
+    if objective(low) > 0.0 || objective(high) < 0.0 {
+        return None;
+    }
+
+    // Brenner-Subrahmanyam at-the-money approximation as the starting point
+    let mut sigma =
+        ((2.0 * std::f64::consts::PI / t).sqrt() * option_price / spot).clamp(low, high);
+    for _ in 0..MAX_ITERATIONS {
+        let diff = objective(sigma);
+        if diff.abs() < PRICE_TOLERANCE {
+            return Some(sigma);
+        }
+        if diff > 0.0 {
+            high = sigma;
+        } else {
+            low = sigma;
+        }
+
+        let vega = vega(sigma);
+        let newton = sigma - diff / vega;
+        sigma = if vega > f64::EPSILON && newton > low && newton < high {
+            newton
+        } else {
+            0.5 * (low + high)
+        };
+    }
+    Some(sigma)
+}
+
+/// LTP, or the bid/ask mid when nothing has traded.
+fn option_price(market_data: &MarketData) -> Option<f64> {
+    market_data
+        .ltp
+        .or(match (market_data.bid_price, market_data.ask_price) {
+            (Some(bid), Some(ask)) if bid > 0.0 && ask > 0.0 => Some(0.5 * (bid + ask)),
+            _ => None,
+        })
+}
+
+fn fill_option(
+    option: &mut OptionData,
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    pricing: Pricing,
+) {
+    let Some(market_data) = &option.market_data else {
+        return;
+    };
+    let option_price = option_price(market_data);
+
+    let existing = option.option_greeks.as_ref();
+    let complete = existing.is_some_and(|g| {
+        g.iv.is_some()
+            && g.delta.is_some()
+            && g.gamma.is_some()
+            && g.theta.is_some()
+            && g.vega.is_some()
+    });
+    if complete {
+        return;
+    }
+
+    let sigma = match existing.and_then(|g| g.iv) {
+        Some(iv) => iv / 100.0,
+        None => {
+            match option_price.and_then(|p| pricing.implied_volatility(kind, p, spot, strike, t)) {
+                Some(sigma) => sigma,
+                None => return,
+            }
+        }
+    };
+    let computed = pricing.greeks(kind, spot, strike, t, sigma);
+
+    let filled = option.option_greeks.get_or_insert(OptionGreeks {
+        vega: None,
+        theta: None,
+        gamma: None,
+        delta: None,
+        iv: None,
+        computed: false,
+        mismatch: false,
+    });
+    filled.iv = filled.iv.or(Some(sigma * 100.0));
+    filled.delta = filled.delta.or(Some(computed.delta));
+    filled.gamma = filled.gamma.or(Some(computed.gamma));
+    filled.theta = filled.theta.or(Some(computed.theta));
+    filled.vega = filled.vega.or(Some(computed.vega));
+    filled.computed = true;
+}
+
+/// Fills in IV and greeks the feed left out, solving IV from the option's
+/// price. Rows with an unparseable or past expiry are left untouched.
+pub fn fill_missing(instruments: &mut [Instrument], now_ms: f64) {
+    fill_missing_at(instruments, now_ms, None, Pricing::default());
+}
+
+/// `fill_missing` with the time to expiry of every row overridden, in hours
+/// (see `dates::time_to_expiry`), and the given pricing inputs. A row's own
+/// `exercise_style` takes precedence over `pricing`'s.
+pub fn fill_missing_at(
+    instruments: &mut [Instrument],
+    now_ms: f64,
+    override_hours: Option<f64>,
+    pricing: Pricing,
+) {
+    for instrument in instruments {
+        let Some(t) = dates::time_to_expiry(&instrument.expiry, now_ms, override_hours) else {
+            continue;
+        };
+        let (spot, strike) = (instrument.underlying_spot_price, instrument.strike_price);
+        let pricing = Pricing {
+            exercise_style: instrument.exercise_style.unwrap_or(pricing.exercise_style),
+            ..pricing
+        };
+        if let Some(call) = instrument.call_options.as_mut() {
+            fill_option(call, OptionKind::Call, spot, strike, t, pricing);
+        }
+        if let Some(put) = instrument.put_options.as_mut() {
+            fill_option(put, OptionKind::Put, spot, strike, t, pricing);
+        }
+    }
+}
+
+// Whether the feed's delta or IV is off from values solved from the price
+fn disagrees(
+    option: &OptionData,
+    kind: OptionKind,
+    spot: f64,
+    strike: f64,
+    t: f64,
+    pricing: Pricing,
+    check: &GreeksCheck,
+) -> bool {
+    let Some(feed) = option.option_greeks.as_ref().filter(|g| !g.computed) else {
+        return false;
+    };
+    let Some(sigma) = option
+        .market_data
+        .as_ref()
+        .and_then(option_price)
+        .and_then(|p| pricing.implied_volatility(kind, p, spot, strike, t))
+    else {
+        return false;
+    };
+    let delta = pricing.greeks(kind, spot, strike, t, sigma).delta;
+
+    let delta_tolerance = check.delta_tolerance.unwrap_or(DELTA_TOLERANCE);
+    let iv_tolerance = check.iv_tolerance.unwrap_or(IV_TOLERANCE);
+    feed.delta
+        .is_some_and(|feed| (feed - delta).abs() > delta_tolerance)
+        || feed
+            .iv
+            .is_some_and(|feed| (feed - sigma * 100.0).abs() > iv_tolerance)
+}
+
+/// Sets `mismatch` on feed-supplied greeks whose delta or IV is further than
+/// `check` allows from the values solved from the option's price. Run before
+/// `fill_missing`, which marks what it fills in as computed.
+pub fn cross_check(
+    instruments: &mut [Instrument],
+    now_ms: f64,
+    override_hours: Option<f64>,
+    pricing: Pricing,
+    check: &GreeksCheck,
+) {
+    for instrument in instruments {
+        let Some(t) = dates::time_to_expiry(&instrument.expiry, now_ms, override_hours) else {
+            continue;
+        };
+        let (spot, strike) = (instrument.underlying_spot_price, instrument.strike_price);
+        let pricing = Pricing {
+            exercise_style: instrument.exercise_style.unwrap_or(pricing.exercise_style),
+            ..pricing
+        };
+        for (kind, option) in [
+            (OptionKind::Call, instrument.call_options.as_mut()),
+            (OptionKind::Put, instrument.put_options.as_mut()),
+        ] {
+            let Some(option) = option else {
+                continue;
+            };
+            if disagrees(option, kind, spot, strike, t, pricing, check) {
+                if let Some(greeks) = option.option_greeks.as_mut() {
+                    greeks.mismatch = true;
+                }
+            }
+        }
+    }
+}
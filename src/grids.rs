@@ -0,0 +1,149 @@
+//! Payoff curves, greeks surfaces and scan results as `Float64Array`s.
+//!
+//! `payoff_grid` and `greeks_grid` compute the same numbers as
+//! `payoff_curves` and `greeks_surface` but hand them over as flat typed
+//! arrays, one copy per buffer, instead of JSON. That keeps large grids,
+//! such as 1000 prices by 30 days, cheap to move into charts. Surface
+//! buffers hold one row per day, laid end to end; see `SurfaceBuffers`.
+//!
+//! `SpreadColumns` holds a scan's packed values column by column, one
+//! `Float64Array` per column with a value per spread, for table
+//! virtualization and charts that read a column at a time.
+
+use crate::packed::PACKED_COLUMNS;
+use crate::payoff::{PayoffBuffers, SurfaceBuffers};
+use js_sys::{Array, Float64Array, Uint32Array};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct PayoffGrid {
+    buffers: PayoffBuffers,
+}
+
+impl From<PayoffBuffers> for PayoffGrid {
+    fn from(buffers: PayoffBuffers) -> Self {
+        PayoffGrid { buffers }
+    }
+}
+
+#[wasm_bindgen]
+impl PayoffGrid {
+    /// IST date the pre-expiry curve is valued at.
+    #[wasm_bindgen(getter, js_name = asOf)]
+    pub fn as_of(&self) -> String {
+        self.buffers.as_of.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = underlyingPrices)]
+    pub fn underlying_prices(&self) -> Float64Array {
+        Float64Array::from(self.buffers.underlying_prices.as_slice())
+    }
+
+    #[wasm_bindgen(getter, js_name = atExpiry)]
+    pub fn at_expiry(&self) -> Float64Array {
+        Float64Array::from(self.buffers.at_expiry.as_slice())
+    }
+
+    #[wasm_bindgen(getter, js_name = atDate)]
+    pub fn at_date(&self) -> Float64Array {
+        Float64Array::from(self.buffers.at_date.as_slice())
+    }
+}
+
+#[wasm_bindgen]
+pub struct GreeksGrid {
+    buffers: SurfaceBuffers,
+}
+
+impl From<SurfaceBuffers> for GreeksGrid {
+    fn from(buffers: SurfaceBuffers) -> Self {
+        GreeksGrid { buffers }
+    }
+}
+
+#[wasm_bindgen]
+impl GreeksGrid {
+    #[wasm_bindgen(getter, js_name = underlyingPrices)]
+    pub fn underlying_prices(&self) -> Float64Array {
+        Float64Array::from(self.buffers.underlying_prices.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn days(&self) -> Uint32Array {
+        Uint32Array::from(self.buffers.days.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dates(&self) -> Vec<String> {
+        self.buffers.dates.clone()
+    }
+
+    /// Row length of each greek buffer, one value per underlying price.
+    #[wasm_bindgen(getter)]
+    pub fn stride(&self) -> usize {
+        self.buffers.underlying_prices.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn delta(&self) -> Float64Array {
+        Float64Array::from(self.buffers.delta.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn gamma(&self) -> Float64Array {
+        Float64Array::from(self.buffers.gamma.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn theta(&self) -> Float64Array {
+        Float64Array::from(self.buffers.theta.as_slice())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn vega(&self) -> Float64Array {
+        Float64Array::from(self.buffers.vega.as_slice())
+    }
+}
+
+#[wasm_bindgen]
+pub struct SpreadColumns {
+    columns: Vec<Vec<f64>>,
+}
+
+impl From<Vec<Vec<f64>>> for SpreadColumns {
+    fn from(columns: Vec<Vec<f64>>) -> Self {
+        SpreadColumns { columns }
+    }
+}
+
+#[wasm_bindgen]
+impl SpreadColumns {
+    /// Column names, in the order `arrays` returns them; same as `packed_columns`.
+    #[wasm_bindgen(getter)]
+    pub fn names(&self) -> Vec<String> {
+        PACKED_COLUMNS.iter().map(|c| c.to_string()).collect()
+    }
+
+    /// Number of spreads, the length of every column.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.columns.first().map_or(0, Vec::len)
+    }
+
+    /// The column called `name`, or `undefined` for an unknown name.
+    pub fn column(&self, name: &str) -> Option<Float64Array> {
+        PACKED_COLUMNS
+            .iter()
+            .position(|c| *c == name)
+            .map(|i| Float64Array::from(self.columns[i].as_slice()))
+    }
+
+    /// Every column, in `names` order.
+    #[wasm_bindgen(unchecked_return_type = "Float64Array[]")]
+    pub fn arrays(&self) -> Array {
+        self.columns
+            .iter()
+            .map(|column| Float64Array::from(column.as_slice()))
+            .collect()
+    }
+}
@@ -0,0 +1,172 @@
+//! Index put hedges for a long equity portfolio.
+//!
+//! The portfolio is hedged through its beta-adjusted exposure to the index:
+//! `portfolio_value * beta` of index notional is protected below a strike
+//! `protection_level` percent under spot. For each expiry a plain long put and
+//! a put spread (selling a further strike to cut cost) are suggested.
+
+use crate::{chain, dates, lots, store, Instrument};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_SPREAD_WIDTH_PERCENTAGE: f64 = 5.0;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct HedgeParams {
+    #[serde(default)]
+    optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
+    portfolio_value: f64,
+    #[serde(default)]
+    beta: Option<f64>,
+    /// Percent drop from spot at which protection should start.
+    protection_level: f64,
+    /// Distance between the long and short put of a put spread, as a percent of spot.
+    #[serde(default)]
+    spread_width_percentage: Option<f64>,
+    #[serde(default)]
+    expiry: Option<String>,
+    #[serde(default)]
+    lot_size: Option<f64>,
+    #[serde(default)]
+    now: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum HedgeKind {
+    Put,
+    PutSpread,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct HedgeSuggestion {
+    kind: HedgeKind,
+    underlying_key: String,
+    expiry: String,
+    dte: Option<i64>,
+    buy_strike: f64,
+    sell_strike: Option<f64>,
+    lots: f64,
+    /// Index level below which the hedge starts paying out.
+    protected_below: f64,
+    /// Largest payout at expiry; `None` for an outright put.
+    max_payout: Option<f64>,
+    cost: f64,
+    cost_percentage: f64,
+    /// Cost as a percent of the portfolio, scaled to a 365-day year.
+    annualized_cost_percentage: Option<f64>,
+}
+
+fn put_ltp(instrument: &Instrument) -> Option<f64> {
+    instrument
+        .put_options
+        .as_ref()
+        .and_then(|data| data.market_data.as_ref())
+        .and_then(|market_data| market_data.ltp)
+        .filter(|ltp| *ltp > 0.0)
+}
+
+fn nearest<'a>(puts: &'a [&'a Instrument], level: f64) -> Option<&'a Instrument> {
+    puts.iter().copied().min_by(|a, b| {
+        (a.strike_price - level)
+            .abs()
+            .partial_cmp(&(b.strike_price - level).abs())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })
+}
+
+fn expiry_hedges(
+    params: &HedgeParams,
+    instruments: &[Instrument],
+    now_ms: f64,
+) -> Result<Vec<HedgeSuggestion>, String> {
+    let puts: Vec<&Instrument> = instruments
+        .iter()
+        .filter(|instrument| put_ltp(instrument).is_some())
+        .collect();
+    let Some(first) = puts.first() else {
+        return Ok(Vec::new());
+    };
+
+    let spot = first.underlying_spot_price;
+    let lot_size = lots::resolve(params.lot_size, &first.underlying_key)?;
+    let exposure = params.portfolio_value * params.beta.unwrap_or(1.0);
+    let lots = (exposure / (spot * lot_size)).ceil().max(1.0);
+    let dte = dates::days_to_expiry(&first.expiry, now_ms);
+    let width = params
+        .spread_width_percentage
+        .unwrap_or(DEFAULT_SPREAD_WIDTH_PERCENTAGE);
+
+    let Some(long_put) = nearest(&puts, spot * (1.0 - params.protection_level / 100.0)) else {
+        return Ok(Vec::new());
+    };
+    let long_premium = put_ltp(long_put).unwrap_or(0.0);
+    let short_put = nearest(&puts, long_put.strike_price - spot * width / 100.0)
+        .filter(|put| put.strike_price < long_put.strike_price);
+
+    let suggestion = |kind, sell: Option<&Instrument>| {
+        let net_premium = long_premium - sell.and_then(put_ltp).unwrap_or(0.0);
+        let cost = net_premium * lots * lot_size;
+        let cost_percentage = cost / params.portfolio_value * 100.0;
+        HedgeSuggestion {
+            kind,
+            underlying_key: long_put.underlying_key.clone(),
+            expiry: long_put.expiry.clone(),
+            dte,
+            buy_strike: long_put.strike_price,
+            sell_strike: sell.map(|put| put.strike_price),
+            lots,
+            protected_below: long_put.strike_price,
+            max_payout: sell
+                .map(|put| (long_put.strike_price - put.strike_price) * lots * lot_size),
+            cost,
+            cost_percentage,
+            annualized_cost_percentage: dte
+                .filter(|days| *days > 0)
+                .map(|days| cost_percentage * 365.0 / days as f64),
+        }
+    };
+
+    let mut hedges = vec![suggestion(HedgeKind::Put, None)];
+    if let Some(short_put) = short_put {
+        hedges.push(suggestion(HedgeKind::PutSpread, Some(short_put)));
+    }
+    Ok(hedges)
+}
+
+/// Suggests put and put-spread hedges per underlying and expiry, cheapest
+/// annualized cost first.
+pub fn suggest(params: &HedgeParams) -> Result<Vec<HedgeSuggestion>, String> {
+    if params.portfolio_value <= 0.0 {
+        return Err(String::from("Portfolio value must be positive"));
+    }
+
+    let mut instruments = store::resolve(params.chain_id, &params.optionchain)?;
+    if let Some(expiry) = &params.expiry {
+        instruments.retain(|instrument| dates::same_expiry(&instrument.expiry, expiry));
+    }
+
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let mut hedges = Vec::new();
+    let slices = chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    });
+    for instruments in slices.into_values() {
+        hedges.extend(expiry_hedges(params, &instruments, now_ms)?);
+    }
+
+    hedges.sort_by(|a, b| {
+        let key = |h: &HedgeSuggestion| h.annualized_cost_percentage.unwrap_or(f64::INFINITY);
+        key(a)
+            .partial_cmp(&key(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(hedges)
+}
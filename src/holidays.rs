@@ -0,0 +1,85 @@
+//! NSE trading holidays and trading-day counts.
+//!
+//! The table holds the weekday equity and derivatives holidays NSE
+//! published for 2024 through 2026. Later years, and special sessions or
+//! closures announced at short notice, come in through a scan's `holidays`,
+//! which add to the table.
+
+use crate::dates;
+
+/// NSE's calendar of about 250 sessions a year.
+pub const TRADING_DAYS_PER_YEAR: f64 = 250.0;
+
+const NSE_HOLIDAYS: [&str; 47] = [
+    "2024-01-22",
+    "2024-01-26",
+    "2024-03-08",
+    "2024-03-25",
+    "2024-03-29",
+    "2024-04-11",
+    "2024-04-17",
+    "2024-05-01",
+    "2024-05-20",
+    "2024-06-17",
+    "2024-07-17",
+    "2024-08-15",
+    "2024-10-02",
+    "2024-11-01",
+    "2024-11-15",
+    "2024-11-20",
+    "2024-12-25",
+    "2025-02-26",
+    "2025-03-14",
+    "2025-03-31",
+    "2025-04-10",
+    "2025-04-14",
+    "2025-04-18",
+    "2025-05-01",
+    "2025-08-15",
+    "2025-08-27",
+    "2025-10-02",
+    "2025-10-21",
+    "2025-10-22",
+    "2025-11-05",
+    "2025-12-25",
+    "2026-01-15",
+    "2026-01-26",
+    "2026-03-03",
+    "2026-03-26",
+    "2026-03-31",
+    "2026-04-03",
+    "2026-04-14",
+    "2026-05-01",
+    "2026-05-28",
+    "2026-06-26",
+    "2026-09-14",
+    "2026-10-02",
+    "2026-10-20",
+    "2026-11-10",
+    "2026-11-24",
+    "2026-12-25",
+];
+
+/// Day numbers of the NSE table plus `extra`, sorted.
+pub fn trading_holidays(extra: Option<&[String]>) -> Vec<i64> {
+    let mut holidays: Vec<i64> = NSE_HOLIDAYS
+        .iter()
+        .filter_map(|holiday| dates::parse_expiry(holiday))
+        .chain(extra.map(dates::holiday_days).unwrap_or_default())
+        .collect();
+    holidays.sort_unstable();
+    holidays.dedup();
+    holidays
+}
+
+/// Trading sessions after the IST date of `now_ms` up to and including
+/// `expiry`, skipping weekends and `holidays`; `None` if unparseable.
+pub fn trading_days_to_expiry(expiry: &str, now_ms: f64, holidays: &[i64]) -> Option<i64> {
+    let expiry_day = dates::parse_expiry(expiry)?;
+    let today = dates::ist_day(now_ms);
+    Some(
+        (today + 1..=expiry_day)
+            .filter(|day| dates::is_trading_day(*day, holidays))
+            .count() as i64,
+    )
+}
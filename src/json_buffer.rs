@@ -0,0 +1,56 @@
+//! A serialization buffer kept between scans.
+//!
+//! Live dashboards rescan every few seconds, and each result is a JSON
+//! string of up to several megabytes. Writing it into one buffer that stays
+//! allocated, reserved up front from the last scan's bytes per spread, saves
+//! growing and freeing a fresh string every time. The module is
+//! single-threaded, so the buffer lives in a thread local.
+
+use serde::Serialize;
+use std::cell::RefCell;
+
+// Room for the envelope around the spreads: stats, errors, grouping keys
+const OVERHEAD: usize = 1024;
+// Capacity kept between scans: at most this many times the last output,
+// and never trimmed below `MIN_RETAINED`
+const MAX_SLACK: usize = 4;
+const MIN_RETAINED: usize = 1 << 20;
+
+#[derive(Default)]
+struct Buffer {
+    bytes: Vec<u8>,
+    bytes_per_spread: usize,
+}
+
+thread_local! {
+    static BUFFER: RefCell<Buffer> = RefCell::new(Buffer::default());
+}
+
+/// Serializes `value`, which holds `spreads` spreads, into the kept buffer
+/// and hands the JSON to `f`.
+pub fn with_json<T: Serialize, R>(
+    value: &T,
+    spreads: usize,
+    f: impl FnOnce(&str) -> R,
+) -> Result<R, serde_json::Error> {
+    BUFFER.with_borrow_mut(|buffer| {
+        buffer.bytes.clear();
+        buffer
+            .bytes
+            .reserve(spreads * buffer.bytes_per_spread + OVERHEAD);
+        serde_json::to_writer(&mut buffer.bytes, value)?;
+        if spreads > 0 {
+            buffer.bytes_per_spread = buffer.bytes.len().div_ceil(spreads);
+        }
+
+        // serde_json only writes UTF-8, so this borrows
+        let result = f(&String::from_utf8_lossy(&buffer.bytes));
+
+        let keep = MIN_RETAINED.max(buffer.bytes.len() * MAX_SLACK);
+        if buffer.bytes.capacity() > keep {
+            buffer.bytes.clear();
+            buffer.bytes.shrink_to(keep);
+        }
+        Ok(result)
+    })
+}
@@ -0,0 +1,231 @@
+//! Zerodha Kite Connect instruments and quotes.
+//!
+//! Kite has no option chain endpoint. A chain is assembled from the NFO
+//! instruments dump, which gives each contract's underlying, expiry, strike
+//! and type, and a `/quote` response keyed by `EXCHANGE:TRADINGSYMBOL`.
+//! Only contracts present in the quotes become chain rows. Best bid and ask
+//! come from the top of the market depth. Kite doesn't publish the previous
+//! day's OI or any greeks, so those are left empty.
+
+use crate::source::ChainSource;
+use crate::{csv, dates, lots, Instrument, MarketData, OptionData};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct KiteParams {
+    /// The instruments dump, as Kite's CSV or a JSON array of the same fields.
+    instruments: String,
+    /// A `/quote` response, with or without the `{ status, data }` envelope.
+    quotes: String,
+    /// Underlying price; defaults to the index quote, e.g. `NSE:NIFTY 50`,
+    /// if it was requested alongside the options.
+    #[serde(default)]
+    spot: Option<f64>,
+    /// Only build rows for this underlying, e.g. `NIFTY`.
+    #[serde(default)]
+    underlying: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct KiteInstrument {
+    tradingsymbol: String,
+    #[serde(default)]
+    exchange: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    expiry: String,
+    #[serde(default)]
+    strike: f64,
+    instrument_type: String,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+struct DepthLevel {
+    #[serde(default)]
+    price: f64,
+    #[serde(default)]
+    quantity: u64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Depth {
+    #[serde(default)]
+    buy: Vec<DepthLevel>,
+    #[serde(default)]
+    sell: Vec<DepthLevel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Ohlc {
+    close: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct KiteQuote {
+    last_price: Option<f64>,
+    volume: Option<u64>,
+    oi: Option<f64>,
+    ohlc: Option<Ohlc>,
+    #[serde(default)]
+    depth: Depth,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum QuotePayload {
+    Envelope { data: HashMap<String, KiteQuote> },
+    Bare(HashMap<String, KiteQuote>),
+}
+
+fn parse_instruments(instruments: &str) -> Result<Vec<KiteInstrument>, String> {
+    if instruments.trim_start().starts_with('[') {
+        return serde_json::from_str(instruments)
+            .map_err(|_| String::from("Failed to parse Kite instruments"));
+    }
+
+    let mut lines = instruments.lines().filter(|line| !line.trim().is_empty());
+    let header = csv::fields(lines.next().unwrap_or_default());
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let (Some(symbol), Some(kind)) = (column("tradingsymbol"), column("instrument_type")) else {
+        return Err(String::from("Kite instruments CSV is missing columns"));
+    };
+    let (exchange, name, expiry, strike) = (
+        column("exchange"),
+        column("name"),
+        column("expiry"),
+        column("strike"),
+    );
+
+    Ok(lines
+        .map(csv::fields)
+        .filter_map(|fields| {
+            let get = |index: Option<usize>| {
+                index
+                    .and_then(|i| fields.get(i))
+                    .map(|field| field.trim().to_string())
+                    .unwrap_or_default()
+            };
+            Some(KiteInstrument {
+                tradingsymbol: fields.get(symbol)?.trim().to_string(),
+                exchange: get(exchange),
+                name: get(name),
+                expiry: get(expiry),
+                strike: get(strike).parse().ok()?,
+                instrument_type: fields.get(kind)?.trim().to_string(),
+            })
+        })
+        .collect())
+}
+
+fn option_data(key: String, quote: &KiteQuote) -> OptionData {
+    let best = |levels: &[DepthLevel]| levels.first().copied();
+    let (bid, ask) = (best(&quote.depth.buy), best(&quote.depth.sell));
+    OptionData {
+        instrument_key: key,
+        market_data: Some(MarketData {
+            ltp: quote.last_price,
+            volume: quote.volume,
+            oi: quote.oi.map(|oi| oi.round() as u64),
+            close_price: quote.ohlc.as_ref().and_then(|ohlc| ohlc.close),
+            bid_price: bid.map(|level| level.price),
+            bid_qty: bid.map(|level| level.quantity),
+            ask_price: ask.map(|level| level.price),
+            ask_qty: ask.map(|level| level.quantity),
+            prev_oi: None,
+        }),
+        option_greeks: None,
+    }
+}
+
+// The index quote's last price, matched on the name after the exchange prefix
+fn index_spot(quotes: &HashMap<String, KiteQuote>, symbol: &str) -> Option<f64> {
+    let index = lots::index_name(symbol).unwrap_or(symbol);
+    quotes
+        .iter()
+        .find(|(key, _)| {
+            key.rsplit_once(':')
+                .is_some_and(|(_, name)| name.eq_ignore_ascii_case(index))
+        })
+        .and_then(|(_, quote)| quote.last_price)
+}
+
+pub fn parse(params: &KiteParams) -> Result<Vec<Instrument>, String> {
+    let instruments = parse_instruments(&params.instruments)?;
+    let quotes = match serde_json::from_str::<QuotePayload>(&params.quotes) {
+        Ok(QuotePayload::Envelope { data }) | Ok(QuotePayload::Bare(data)) => data,
+        Err(_) => return Err(String::from("Failed to parse Kite quotes")),
+    };
+
+    // (underlying, expiry, strike bits) -> (call, put)
+    type Row = (Option<OptionData>, Option<OptionData>);
+    let mut rows: BTreeMap<(String, String, u64), Row> = BTreeMap::new();
+    for instrument in &instruments {
+        if params
+            .underlying
+            .as_ref()
+            .is_some_and(|underlying| !underlying.eq_ignore_ascii_case(&instrument.name))
+        {
+            continue;
+        }
+        let exchange = if instrument.exchange.is_empty() {
+            "NFO"
+        } else {
+            instrument.exchange.as_str()
+        };
+        let key = format!("{}:{}", exchange, instrument.tradingsymbol);
+        let Some(quote) = quotes.get(&key) else {
+            continue;
+        };
+
+        // JSON dumps may carry a time after the date
+        let expiry = instrument.expiry.split('T').next().unwrap_or_default();
+        let expiry = dates::normalize_expiry(expiry).unwrap_or_else(|| expiry.to_string());
+        let row = rows
+            .entry((instrument.name.clone(), expiry, instrument.strike.to_bits()))
+            .or_default();
+        match instrument.instrument_type.as_str() {
+            "CE" => row.0 = Some(option_data(key, quote)),
+            "PE" => row.1 = Some(option_data(key, quote)),
+            _ => {}
+        }
+    }
+
+    rows.into_iter()
+        .filter(|(_, (call, put))| call.is_some() || put.is_some())
+        .map(|((underlying, expiry, strike), (call, put))| {
+            let spot = params
+                .spot
+                .or_else(|| index_spot(&quotes, &underlying))
+                .ok_or_else(|| format!("No spot price for {}", underlying))?;
+            Ok(Instrument {
+                expiry,
+                strike_price: f64::from_bits(strike),
+                underlying_key: underlying,
+                underlying_spot_price: spot,
+                call_options: call,
+                put_options: put,
+                exercise_style: None,
+            })
+        })
+        .collect()
+}
+
+/// Takes the same object as `parse_kite_chain`.
+pub struct KiteSource;
+
+impl ChainSource for KiteSource {
+    fn format(&self) -> &'static str {
+        "kite"
+    }
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+        let params = KiteParams::deserialize(data)
+            .map_err(|_| String::from("Failed to parse Kite parameters"))?;
+        parse(&params)
+    }
+}
@@ -0,0 +1,346 @@
+//! A ladder of short options across consecutive weekly expiries.
+//!
+//! Weekly selling programs open the same structure every week and hold
+//! several at once. Each rung here is a short strangle, or an iron condor
+//! with wings `wing_width` further out, on one of the next `weeks` expiries,
+//! its short strikes picked at the same absolute delta. The ladder adds up
+//! the rungs' greeks and shows the margin still locked as each one expires.
+
+use crate::greeks::OptionKind;
+use crate::positions::{self, Leg, LegMark, PositionGreeks, PriceMode, Side};
+use crate::spreads::StrategyType;
+use crate::{chain, dates, greeks, lots, margin, store, Instrument, OptionData};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_WEEKS: usize = 4;
+// Wing distance, in strike steps, when `wing_width` isn't given
+const DEFAULT_WING_STEPS: f64 = 4.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum LadderStructure {
+    #[default]
+    Strangle,
+    IronCondor,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct LadderParams {
+    #[serde(default)]
+    optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
+    /// Defaults to the chain's first underlying.
+    #[serde(default)]
+    underlying_key: Option<String>,
+    #[serde(default)]
+    structure: Option<LadderStructure>,
+    /// Absolute delta of every short strike, e.g. 0.15.
+    target_delta: f64,
+    /// Consecutive expiries to ladder over; defaults to 4.
+    #[serde(default)]
+    weeks: Option<usize>,
+    /// Lots per rung; defaults to 1.
+    #[serde(default)]
+    lots: Option<f64>,
+    /// Distance from each short strike to its wing, in index points, for
+    /// `iron_condor`; defaults to 4 strike steps.
+    #[serde(default)]
+    wing_width: Option<f64>,
+    #[serde(default)]
+    lot_size: Option<f64>,
+    /// Quote each leg fills at; defaults to LTP.
+    #[serde(default)]
+    price_mode: Option<PriceMode>,
+    #[serde(default)]
+    margin_model: Option<margin::MarginModel>,
+    /// Epoch milliseconds to measure days to expiry from; defaults to the current time.
+    #[serde(default)]
+    now: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct LadderRung {
+    expiry: String,
+    dte: Option<i64>,
+    strategy_type: StrategyType,
+    legs: Vec<Leg>,
+    /// Premium collected for the rung's lots, in rupees.
+    credit: f64,
+    margin: f64,
+    /// `None` when a leg has no greeks.
+    greeks: Option<PositionGreeks>,
+    /// `credit` as a percent of `margin`.
+    return_on_margin: Option<f64>,
+}
+
+/// Capital still tied up once the ladder's rungs up to `expiry` have expired.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct CapitalStep {
+    expiry: String,
+    margin_released: f64,
+    margin_locked: f64,
+    /// Credit of the rungs expired so far.
+    credit_realized: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Ladder {
+    underlying_key: String,
+    spot: f64,
+    lot_size: f64,
+    rungs: Vec<LadderRung>,
+    total_credit: f64,
+    /// Margin for every rung at once, the most the ladder locks up.
+    total_margin: f64,
+    /// Sum of the rungs' greeks; `None` when any rung's are missing.
+    greeks: Option<PositionGreeks>,
+    capital_profile: Vec<CapitalStep>,
+}
+
+/// A priced option of one expiry.
+struct Candidate<'a> {
+    instrument: &'a Instrument,
+    option: &'a OptionData,
+    sell_price: f64,
+    buy_price: f64,
+}
+
+fn candidates(instruments: &[Instrument], kind: OptionKind, mode: PriceMode) -> Vec<Candidate<'_>> {
+    instruments
+        .iter()
+        .filter_map(|instrument| {
+            let option = chain::option(instrument, kind)?;
+            let market_data = option.market_data.as_ref()?;
+            Some(Candidate {
+                instrument,
+                option,
+                sell_price: positions::fill_price(market_data, Side::Sell, mode)?,
+                buy_price: positions::fill_price(market_data, Side::Buy, mode)?,
+            })
+        })
+        .collect()
+}
+
+/// The out-of-the-money option whose absolute delta is nearest `target`.
+fn at_delta<'a, 'b>(
+    candidates: &'b [Candidate<'a>],
+    kind: OptionKind,
+    target: f64,
+) -> Option<&'b Candidate<'a>> {
+    candidates
+        .iter()
+        .filter(|c| match kind {
+            OptionKind::Call => c.instrument.strike_price > c.instrument.underlying_spot_price,
+            OptionKind::Put => c.instrument.strike_price < c.instrument.underlying_spot_price,
+        })
+        .filter_map(|c| {
+            let delta = c.option.option_greeks.as_ref()?.delta?;
+            Some((c, (delta.abs() - target).abs()))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(c, _)| c)
+}
+
+/// The nearest listed wing at least `width` beyond `short`.
+fn wing<'a, 'b>(
+    candidates: &'b [Candidate<'a>],
+    kind: OptionKind,
+    short: f64,
+    width: f64,
+) -> Option<&'b Candidate<'a>> {
+    candidates
+        .iter()
+        .filter(|c| match kind {
+            OptionKind::Call => c.instrument.strike_price >= short + width,
+            OptionKind::Put => c.instrument.strike_price <= short - width,
+        })
+        .min_by(|a, b| {
+            (a.instrument.strike_price - short)
+                .abs()
+                .total_cmp(&(b.instrument.strike_price - short).abs())
+        })
+}
+
+fn leg(candidate: &Candidate, kind: OptionKind, side: Side, lots: f64) -> Leg {
+    Leg {
+        instrument_key: Some(candidate.option.instrument_key.clone()).filter(|k| !k.is_empty()),
+        option_type: kind,
+        strike: candidate.instrument.strike_price,
+        expiry: Some(candidate.instrument.expiry.clone()),
+        side,
+        lots,
+        price: match side {
+            Side::Sell => candidate.sell_price,
+            Side::Buy => candidate.buy_price,
+        },
+        iv: candidate.option.option_greeks.as_ref().and_then(|g| g.iv),
+    }
+}
+
+fn add(a: PositionGreeks, b: PositionGreeks) -> PositionGreeks {
+    PositionGreeks {
+        delta: a.delta + b.delta,
+        gamma: a.gamma + b.gamma,
+        theta: a.theta + b.theta,
+        vega: a.vega + b.vega,
+    }
+}
+
+pub fn build(params: &LadderParams) -> Result<Ladder, String> {
+    if !(params.target_delta > 0.0 && params.target_delta < 1.0) {
+        return Err(String::from("Target delta must be between 0 and 1"));
+    }
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let structure = params.structure.unwrap_or_default();
+    let mode = params.price_mode.unwrap_or_default();
+    let model = params.margin_model.unwrap_or_default();
+    let lots = params.lots.unwrap_or(1.0);
+
+    let mut instruments = store::resolve(params.chain_id, &params.optionchain)?;
+    let underlying_key = match &params.underlying_key {
+        Some(key) => key.clone(),
+        None => instruments
+            .first()
+            .map(|i| i.underlying_key.clone())
+            .ok_or_else(|| String::from("Option chain is empty"))?,
+    };
+    instruments.retain(|i| {
+        i.underlying_key == underlying_key
+            && dates::days_to_expiry(&i.expiry, now_ms).is_some_and(|days| days >= 0)
+    });
+    greeks::fill_missing(&mut instruments, now_ms);
+    let spot = instruments
+        .first()
+        .map(|i| i.underlying_spot_price)
+        .ok_or_else(|| String::from("No unexpired expiry in the chain"))?;
+    let lot_size = lots::resolve(params.lot_size, &underlying_key)?;
+
+    let expiries = chain::group_by(instruments, |i| i.expiry.clone());
+    let mut rungs = Vec::new();
+    for (expiry, instruments) in expiries
+        .into_iter()
+        .take(params.weeks.unwrap_or(DEFAULT_WEEKS))
+    {
+        let calls = candidates(&instruments, OptionKind::Call, mode);
+        let puts = candidates(&instruments, OptionKind::Put, mode);
+        let (Some(short_call), Some(short_put)) = (
+            at_delta(&calls, OptionKind::Call, params.target_delta),
+            at_delta(&puts, OptionKind::Put, params.target_delta),
+        ) else {
+            return Err(format!("No strikes at the target delta for {}", expiry));
+        };
+
+        let mut picked = vec![
+            (short_call, OptionKind::Call, Side::Sell),
+            (short_put, OptionKind::Put, Side::Sell),
+        ];
+        if structure == LadderStructure::IronCondor {
+            let width = match params.wing_width {
+                Some(width) => width,
+                None => {
+                    chain::strike_step(&instruments)
+                        .ok_or_else(|| format!("No strike step for {}", expiry))?
+                        * DEFAULT_WING_STEPS
+                }
+            };
+            let call_wing = wing(
+                &calls,
+                OptionKind::Call,
+                short_call.instrument.strike_price,
+                width,
+            );
+            let put_wing = wing(
+                &puts,
+                OptionKind::Put,
+                short_put.instrument.strike_price,
+                width,
+            );
+            let (Some(call_wing), Some(put_wing)) = (call_wing, put_wing) else {
+                return Err(format!("No wings listed far enough out for {}", expiry));
+            };
+            picked.push((call_wing, OptionKind::Call, Side::Buy));
+            picked.push((put_wing, OptionKind::Put, Side::Buy));
+        }
+
+        let legs: Vec<Leg> = picked
+            .iter()
+            .map(|(candidate, kind, side)| leg(candidate, *kind, *side, lots))
+            .collect();
+        let marks: Vec<LegMark> = picked
+            .iter()
+            .zip(&legs)
+            .map(|((candidate, _, _), leg)| LegMark {
+                leg,
+                option: candidate.option,
+                quantity: leg.side.sign() * leg.lots * lot_size,
+                price: leg.price,
+            })
+            .collect();
+        let greeks = positions::position_greeks(&marks);
+        let credit = -marks
+            .iter()
+            .map(|mark| mark.price * mark.quantity)
+            .sum::<f64>();
+        let margin = margin::for_legs(&legs, spot, lot_size, &model);
+
+        rungs.push(LadderRung {
+            dte: dates::days_to_expiry(&expiry, now_ms),
+            expiry,
+            strategy_type: match structure {
+                LadderStructure::Strangle => StrategyType::Strangle,
+                LadderStructure::IronCondor => StrategyType::IronCondor,
+            },
+            legs,
+            credit,
+            margin,
+            greeks,
+            return_on_margin: (margin > 0.0).then(|| credit / margin * 100.0),
+        });
+    }
+    if rungs.is_empty() {
+        return Err(String::from("No unexpired expiry in the chain"));
+    }
+
+    let total_credit = rungs.iter().map(|rung| rung.credit).sum();
+    let total_margin: f64 = rungs.iter().map(|rung| rung.margin).sum();
+    let greeks = rungs
+        .iter()
+        .try_fold(PositionGreeks::default(), |total, rung| {
+            Some(add(total, rung.greeks?))
+        });
+    let mut released = 0.0;
+    let mut realized = 0.0;
+    let capital_profile = rungs
+        .iter()
+        .map(|rung| {
+            released += rung.margin;
+            realized += rung.credit;
+            CapitalStep {
+                expiry: rung.expiry.clone(),
+                margin_released: rung.margin,
+                margin_locked: total_margin - released,
+                credit_realized: realized,
+            }
+        })
+        .collect();
+
+    Ok(Ladder {
+        underlying_key,
+        spot,
+        lot_size,
+        rungs,
+        total_credit,
+        total_margin,
+        greeks,
+        capital_profile,
+    })
+}
@@ -1,9 +1,78 @@
+// Without the JS exports only the native scan API below reaches most modules
+#![cfg_attr(not(feature = "wasm"), allow(dead_code))]
+
+mod arena;
+mod benchmark;
+#[cfg(feature = "wasm")]
+mod bindings;
+mod binomial;
+mod calendar;
+mod chain;
+mod costs;
+mod covered;
+mod csv;
+mod dates;
+mod defaults;
+#[cfg(feature = "wasm")]
+mod event_loop;
+mod futures;
+mod greeks;
+#[cfg(feature = "wasm")]
+mod grids;
+mod hedging;
+mod holidays;
+mod json_buffer;
+mod kite;
+mod ladder;
+mod liquidity;
+#[cfg(feature = "wasm")]
+mod logger;
+mod lots;
+mod margin;
+mod msgpack;
+mod notifications;
+mod nse;
+mod oi;
+mod orders;
+mod packed;
+mod payoff;
+mod perf;
+mod portfolio;
+mod positions;
+mod quotes;
+mod replay;
+mod report;
+mod resample;
+mod rescan;
+mod roll;
+#[cfg(feature = "wasm")]
+mod scan_params;
+mod scenario;
+mod scoring;
+mod single_leg;
+mod sizing;
+mod source;
+mod spreads;
+mod store;
+mod trace;
+mod upstox;
+mod validation;
+mod volatility;
+
 use serde::{Deserialize, Serialize};
-use serde_wasm_bindgen::from_value;
+use spreads::Strategy;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use web_sys::console;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Starts the worker pool for the `threads` build; await it once before
+/// scanning.
+#[cfg(feature = "threads")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct MarketData {
     ltp: Option<f64>,
     volume: Option<u64>,
@@ -17,15 +86,24 @@ pub struct MarketData {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct OptionGreeks {
     vega: Option<f64>,
     theta: Option<f64>,
     gamma: Option<f64>,
     delta: Option<f64>,
     iv: Option<f64>,
+    /// Set when IV or greeks were solved from prices because the feed omitted them.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    computed: bool,
+    /// Set when a `greeks_check` found the feed's delta or IV out of line
+    /// with values solved from the price.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    mismatch: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct OptionData {
     instrument_key: String,
     market_data: Option<MarketData>,
@@ -33,279 +111,291 @@ pub struct OptionData {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct Instrument {
+    #[serde(deserialize_with = "dates::deserialize_expiry")]
+    #[cfg_attr(feature = "wasm", tsify(type = "string | number"))]
     expiry: String,
     strike_price: f64,
     underlying_key: String,
     underlying_spot_price: f64,
     call_options: Option<OptionData>,
     put_options: Option<OptionData>,
+    /// Overrides the scan's `exercise_style` for this row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exercise_style: Option<greeks::ExerciseStyle>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
 pub struct BearCallSpreadParams {
+    /// JSON chain; the MessagePack scanners take theirs separately.
+    #[serde(default)]
     optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
     bid_ask_spread: bool,
     risk_reward_ratio: bool,
+    /// Largest max loss to max profit ratio kept; `risk_reward_ratio` is the
+    /// same as 3.
+    #[serde(default)]
+    max_risk_reward: Option<f64>,
+    /// Deprecated: same as `sort_by: "breakeven_pct"`.
+    #[serde(default)]
     breakeven_percentage_sort: bool,
+    /// Required for underlyings the built-in table doesn't know, such as
+    /// stocks; the scan fails without it.
+    #[serde(default)]
+    lot_size: Option<f64>,
+    #[serde(default)]
+    group_by_underlying: bool,
+    #[serde(default)]
+    expiry: Option<String>,
+    /// Only weekly or only monthly expiries.
+    #[serde(default)]
+    expiry_cycle: Option<dates::ExpiryCycle>,
+    /// Exchange holidays, as dates, on top of the built-in NSE calendar;
+    /// they bring a monthly expiry forward and are skipped in `trading_dte`.
+    #[serde(default)]
+    holidays: Option<Vec<String>>,
+    /// `"trading"` annualizes returns over trading sessions instead of
+    /// calendar days.
+    #[serde(default)]
+    day_count: Option<spreads::DayCount>,
+    #[serde(default)]
+    group_by_expiry: bool,
+    #[serde(default)]
+    futures_margin_percentage: Option<f64>,
+    /// Futures quotes per expiry; where one matches, strikes are in or out
+    /// of the money against its price rather than spot.
+    #[serde(default)]
+    futures: Option<Vec<futures::FuturesQuote>>,
+    /// Epoch milliseconds to measure days to expiry from; defaults to the current time.
+    #[serde(default)]
+    now: Option<f64>,
+    /// Hours left to expiry, used instead of the clock for every expiry
+    /// scanned; for expiry-day scans, e.g. 2.5 with the close two and a half
+    /// hours away.
+    #[serde(default)]
+    time_to_expiry_hours: Option<f64>,
+    /// Annualized risk-free rate for pricing and computed IVs, as a decimal;
+    /// defaults to 0.065, close to the 91-day T-bill yield.
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    /// Annualized dividend yield of the underlying, as a decimal; defaults
+    /// to 0.
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    /// `"american"` prices computed IVs and greeks on a binomial tree, for
+    /// stock options that can be exercised early; defaults to `"european"`.
+    #[serde(default)]
+    exercise_style: Option<greeks::ExerciseStyle>,
+    /// Drop strikes whose expiry can't be parsed instead of flagging them.
+    #[serde(default)]
+    reject_invalid_expiry: bool,
+    /// Keep strikes that fail soft checks and flag them in `warnings` instead.
+    #[serde(default)]
+    lenient: bool,
+    /// Drop strikes with no bid/ask or no trades today; otherwise they are
+    /// flagged in `warnings`.
+    #[serde(default)]
+    exclude_stale: bool,
+    /// Check feed greeks against ones solved from prices and drop strikes
+    /// where they disagree, or flag them in `warnings` when `lenient`.
+    #[serde(default)]
+    greeks_check: Option<greeks::GreeksCheck>,
+    #[serde(default)]
+    pop_model: Option<spreads::PopModel>,
+    /// Minimum probability of profit, between 0 and 1.
+    #[serde(default)]
+    min_pop: Option<f64>,
+    /// Bounds on the short leg's absolute delta, e.g. 0.10 to 0.30.
+    #[serde(default)]
+    min_short_delta: Option<f64>,
+    #[serde(default)]
+    max_short_delta: Option<f64>,
+    /// Bounds on the short strike's distance from spot, as a percent of spot.
+    #[serde(default)]
+    min_otm_pct: Option<f64>,
+    #[serde(default)]
+    max_otm_pct: Option<f64>,
+    /// Only pair strikes that are multiples of the chain's detected strike step.
+    #[serde(default)]
+    strike_step_only: bool,
+    /// `"otm"` (the default) keeps both legs out of the money; `"atm"` also
+    /// allows the strike nearest spot, and `"itm"` the `itm_strikes`
+    /// in-the-money strikes nearest spot.
+    #[serde(default)]
+    moneyness: Option<spreads::Moneyness>,
+    #[serde(default)]
+    itm_strikes: Option<usize>,
+    /// Bounds on the distance between the two strikes, in index points.
+    #[serde(default)]
+    min_width: Option<f64>,
+    #[serde(default)]
+    max_width: Option<f64>,
+    /// Minimum open interest on the short strike.
+    #[serde(default)]
+    min_short_strike_oi: Option<u64>,
+    /// Minimum day-over-day change in the short strike's open interest.
+    #[serde(default)]
+    min_oi_change: Option<f64>,
+    /// Quote used as each leg's entry price; defaults to LTP.
+    #[serde(default)]
+    price_mode: Option<positions::PriceMode>,
+    /// Per-unit slippage on each leg's fill, in `slippage_unit`.
+    #[serde(default)]
+    slippage: Option<f64>,
+    #[serde(default)]
+    slippage_unit: Option<spreads::SlippageUnit>,
+    /// Brokerage and statutory rates; missing fields use NSE defaults.
+    #[serde(default)]
+    brokerage_profile: Option<costs::BrokerageProfile>,
+    /// SPAN and exposure assumptions, or a broker-quoted `margin_per_lot`.
+    #[serde(default)]
+    margin_model: Option<margin::MarginModel>,
+    /// Exit when the loss reaches this multiple of the entry credit, e.g. 2.
+    #[serde(default)]
+    stop_loss_multiple: Option<f64>,
+    /// Exit once this percent of max profit is captured, e.g. 50.
+    #[serde(default)]
+    profit_target_pct: Option<f64>,
+    /// Rank by a weighted composite score instead of the other sorts.
+    #[serde(default)]
+    score_weights: Option<scoring::ScoreWeights>,
+    #[serde(default)]
+    sort_by: Option<spreads::SortBy>,
+    /// Defaults to descending.
+    #[serde(default)]
+    sort_order: Option<spreads::SortOrder>,
+    /// Deprecated: same as `sort_by: "roi"`.
+    #[serde(default)]
+    return_on_margin_sort: bool,
+    /// Minimum liquidity score, 0 to 100, required on both legs.
+    #[serde(default)]
+    min_liquidity_score: Option<f64>,
+    /// IV rank from `iv_stats`, copied onto every spread.
+    #[serde(default)]
+    iv_rank: Option<f64>,
+    /// Spreads to skip, then the most to return, after sorting and ranking.
+    #[serde(default)]
+    offset: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+    /// Leave max profit, max loss and breakevens unrounded, and breakeven
+    /// percentages untrimmed, for research and backtests.
+    #[serde(default)]
+    raw_numbers: bool,
+    /// Add `breakeven_distance_pct`, the breakeven's signed distance from spot.
+    #[serde(default)]
+    signed_breakeven: bool,
+    /// Return `{ results, stats }`, with counts of what the filters dropped,
+    /// instead of the bare results.
+    #[serde(default)]
+    with_stats: bool,
+    /// Log every strike, pair and spread the filters drop, and why; see
+    /// `trace`.
+    #[serde(default)]
+    debug: bool,
 }
 
-#[wasm_bindgen]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", wasm_bindgen, derive(Tsify))]
 pub struct CreditSpread {
     sell_strike: f64,
     buy_strike: f64,
     spread: f64,
+    /// Contract size the rupee figures are for.
+    lot_size: f64,
     net_credit: f64,
     max_profit: f64,
     max_loss: f64,
     breakeven: f64,
     breakeven_percentage: f64, // New key added
-    type_: String,
+    /// `breakeven_percentage` with a sign, negative when the breakeven is
+    /// below spot; present when `signed_breakeven` is set.
+    breakeven_distance_pct: Option<f64>,
+    slippage_adjusted: Option<spreads::SlippageAdjusted>,
+    /// Composite score from 0 to 100, present when `score_weights` is set.
+    score: Option<f64>,
+    /// Present when `stop_loss_multiple` or `profit_target_pct` is set.
+    exits: Option<spreads::ExitTriggers>,
+    /// Brokerage, taxes and fees for opening both legs.
+    total_charges: f64,
+    net_profit_after_costs: f64,
+    /// Approximate SPAN plus exposure margin for one lot of the spread.
+    estimated_margin: f64,
+    /// Max profit as a percent of `estimated_margin`.
+    return_on_margin: Option<f64>,
+    /// Margin for the short leg alone, and how much the hedge saves.
+    naked_margin: f64,
+    margin_benefit: f64,
+    /// Option type of both legs.
+    type_: greeks::OptionKind,
+    strategy_type: spreads::StrategyType,
+    direction: spreads::Direction,
+    underlying_key: String,
+    expiry: String,
+    /// False when `expiry` couldn't be parsed; date-based fields are then null.
+    expiry_valid: bool,
+    /// `None` when `expiry` couldn't be parsed.
+    expiry_cycle: Option<dates::ExpiryCycle>,
+    dte: Option<i64>,
+    /// Trading sessions to expiry, skipping weekends and holidays.
+    trading_dte: Option<i64>,
+    /// Time left to the expiry-day close, to the fraction of an hour.
+    hours_to_expiry: Option<f64>,
+    annualized_return_on_risk: Option<f64>,
+    /// Probability of profit at expiry, between 0 and 1.
+    pop: Option<f64>,
+    /// `pop * max_profit - (1 - pop) * max_loss`.
+    expected_value: Option<f64>,
+    iv_rank: Option<f64>,
+    // Buy leg minus sell leg, per unit of the underlying
+    net_delta: Option<f64>,
+    net_gamma: Option<f64>,
+    net_theta: Option<f64>,
+    /// `net_theta` spread over the trading sessions left instead of the
+    /// calendar days.
+    net_theta_per_trading_day: Option<f64>,
+    net_vega: Option<f64>,
+    /// A lot's daily decay as a percent of `estimated_margin`.
+    theta_per_day_per_margin: Option<f64>,
+    /// `net_theta` per point of absolute `net_vega`: decay earned for the
+    /// volatility risk carried.
+    theta_to_vega_ratio: Option<f64>,
+    futures_benchmark: Option<benchmark::FuturesBenchmark>,
+    /// Strike with the most open interest on the spread's side of the chain.
+    oi_wall: Option<f64>,
+    /// Whether `oi_wall` sits between spot and the short strike, so price has
+    /// to break through it before the spread loses.
+    oi_wall_protected: bool,
+    warnings: Vec<spreads::SpreadWarning>,
+    legs: Vec<spreads::SpreadLeg>,
 }
 
-#[wasm_bindgen]
-pub fn bear_call_spread(params: JsValue) -> String {
-    const NIFTY_LOTSIZE: f64 = 25.0;
-
-    let params: BearCallSpreadParams = match from_value(params) {
-        Ok(p) => p,
-        Err(_) => return String::from("Failed to parse parameters"),
-    };
-
-    let optionchain = &params.optionchain;
-
-    match serde_json::from_str::<Vec<Instrument>>(optionchain) {
-        Ok(instruments) => {
-            let otm_strikes: Vec<Instrument> = instruments
-                .into_iter()
-                .filter(|instrument| {
-                    let is_otm = instrument.strike_price > instrument.underlying_spot_price;
-
-                    let has_valid_market_data = instrument
-                        .call_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .map_or(false, |market_data| {
-                            let ltp_is_some = market_data.ltp.is_some();
-                            let bid_ask_diff_ok =
-                                match (market_data.bid_price, market_data.ask_price) {
-                                    (Some(bid), Some(ask)) => (ask - bid).abs() <= 2.0,
-                                    _ => false,
-                                };
-                            ltp_is_some && (!params.bid_ask_spread || bid_ask_diff_ok)
-                        });
-
-                    is_otm && has_valid_market_data
-                })
-                .collect();
-
-            let mut sorted_otm_strikes = otm_strikes;
-            sorted_otm_strikes.sort_by(|a, b| {
-                a.strike_price
-                    .partial_cmp(&b.strike_price)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-
-            let call_credit_spread_pairs: Vec<(Instrument, Instrument)> = sorted_otm_strikes
-                .iter()
-                .enumerate()
-                .flat_map(|(i, lower)| {
-                    sorted_otm_strikes[i + 1..]
-                        .iter()
-                        .map(move |higher| (lower.clone(), higher.clone()))
-                })
-                .collect();
-
-            let mut credit_spreads: Vec<CreditSpread> = call_credit_spread_pairs
-                .into_iter()
-                .filter_map(|(lower, higher)| {
-                    let lower_ltp = lower
-                        .call_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .and_then(|market_data| market_data.ltp)
-                        .unwrap_or(0.0);
-
-                    let higher_ltp = higher
-                        .call_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .and_then(|market_data| market_data.ltp)
-                        .unwrap_or(0.0);
-
-                    let spread = (higher.strike_price - lower.strike_price) * NIFTY_LOTSIZE;
-                    let net_credit = (lower_ltp - higher_ltp) * NIFTY_LOTSIZE;
-                    let max_profit = net_credit.ceil();
-                    let max_loss = (spread - net_credit).ceil();
-                    let breakeven = (lower.strike_price + (net_credit / NIFTY_LOTSIZE)).ceil();
-
-                    // Calculate breakeven_percentage and trim it to 2 decimal places without rounding up
-                    let breakeven_percentage = ((breakeven - lower.underlying_spot_price).abs()
-                        / lower.underlying_spot_price)
-                        * 100.0;
-                    let breakeven_percentage_trimmed =
-                        (breakeven_percentage * 100.0).floor() / 100.0;
-
-                    Some(CreditSpread {
-                        sell_strike: lower.strike_price,
-                        buy_strike: higher.strike_price,
-                        spread,
-                        net_credit,
-                        max_profit,
-                        max_loss,
-                        breakeven,
-                        breakeven_percentage: breakeven_percentage_trimmed,
-                        type_: String::from("CE"),
-                    })
-                })
-                .collect();
-
-            // Sort by breakeven_percentage in descending order if breakeven_percentage_sort is true
-            if params.breakeven_percentage_sort {
-                credit_spreads.sort_by(|a, b| {
-                    b.breakeven_percentage
-                        .partial_cmp(&a.breakeven_percentage)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                });
-            }
-
-            if params.risk_reward_ratio {
-                credit_spreads.retain(|spread| spread.max_loss <= 3.0 * spread.max_profit);
-            }
-
-            serde_json::to_string(&credit_spreads)
-                .unwrap_or_else(|_| String::from("Failed to serialize credit spreads"))
-        }
-        Err(err) => {
-            console::log_1(&JsValue::from_str(&format!(
-                "Failed to parse JSON: {:?}",
-                err
-            )));
-            String::from("Failed to parse JSON")
-        }
-    }
+/// Bear call spreads on a parsed chain, filtered, sorted and paged as the
+/// `bear_call_spread` export does. `params.optionchain` and `chain_id` are
+/// ignored.
+pub fn scan_bear_call(
+    instruments: &[Instrument],
+    params: &BearCallSpreadParams,
+) -> Result<Vec<CreditSpread>, String> {
+    let candidates = spreads::candidates(Strategy::BearCall, params, instruments.to_vec(), None)?;
+    Ok(spreads::finish(params, candidates))
 }
 
-#[wasm_bindgen]
-pub fn bull_put_spread(params: JsValue) -> String {
-    const NIFTY_LOTSIZE: f64 = 25.0;
-
-    let params: BearCallSpreadParams = match from_value(params) {
-        Ok(p) => p,
-        Err(_) => return String::from("Failed to parse parameters"),
-    };
-
-    let optionchain = &params.optionchain;
-
-    match serde_json::from_str::<Vec<Instrument>>(optionchain) {
-        Ok(instruments) => {
-            let otm_strikes: Vec<Instrument> = instruments
-                .into_iter()
-                .filter(|instrument| {
-                    let is_otm = instrument.strike_price < instrument.underlying_spot_price;
-
-                    let has_valid_market_data = instrument
-                        .put_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .map_or(false, |market_data| {
-                            let ltp_is_some = market_data.ltp.is_some();
-                            let bid_ask_diff_ok =
-                                match (market_data.bid_price, market_data.ask_price) {
-                                    (Some(bid), Some(ask)) => (ask - bid).abs() <= 2.0,
-                                    _ => false,
-                                };
-                            ltp_is_some && (!params.bid_ask_spread || bid_ask_diff_ok)
-                        });
-
-                    is_otm && has_valid_market_data
-                })
-                .collect();
-
-            let mut sorted_otm_strikes = otm_strikes;
-            sorted_otm_strikes.sort_by(|a, b| {
-                b.strike_price
-                    .partial_cmp(&a.strike_price)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
-
-            let put_credit_spread_pairs: Vec<(Instrument, Instrument)> = sorted_otm_strikes
-                .iter()
-                .enumerate()
-                .flat_map(|(i, higher)| {
-                    sorted_otm_strikes[i + 1..]
-                        .iter()
-                        .map(move |lower| (higher.clone(), lower.clone()))
-                })
-                .collect();
-
-            let mut credit_spreads: Vec<CreditSpread> = put_credit_spread_pairs
-                .into_iter()
-                .filter_map(|(higher, lower)| {
-                    let higher_ltp = higher
-                        .put_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .and_then(|market_data| market_data.ltp)
-                        .unwrap_or(0.0);
-
-                    let lower_ltp = lower
-                        .put_options
-                        .as_ref()
-                        .and_then(|data| data.market_data.as_ref())
-                        .and_then(|market_data| market_data.ltp)
-                        .unwrap_or(0.0);
-
-                    let spread = (higher.strike_price - lower.strike_price) * NIFTY_LOTSIZE;
-                    let net_credit = (higher_ltp - lower_ltp) * NIFTY_LOTSIZE;
-                    let max_profit = net_credit.ceil();
-                    let max_loss = (spread - net_credit).ceil();
-                    let breakeven = (lower.strike_price - (net_credit / NIFTY_LOTSIZE)).ceil();
-
-                    // Calculate breakeven_percentage and trim it to 2 decimal places without rounding up
-                    let breakeven_percentage = ((breakeven - lower.underlying_spot_price).abs()
-                        / lower.underlying_spot_price)
-                        * 100.0;
-                    let breakeven_percentage_trimmed =
-                        (breakeven_percentage * 100.0).floor() / 100.0;
-
-                    Some(CreditSpread {
-                        sell_strike: higher.strike_price,
-                        buy_strike: lower.strike_price,
-                        spread,
-                        net_credit,
-                        max_profit,
-                        max_loss,
-                        breakeven,
-                        breakeven_percentage: breakeven_percentage_trimmed, // Set trimmed value
-                        type_: String::from("PE"),
-                    })
-                })
-                .collect();
-
-            // Sort by breakeven_percentage in descending order if breakeven_percentage_sort is true
-            if params.breakeven_percentage_sort {
-                credit_spreads.sort_by(|a, b| {
-                    b.breakeven_percentage
-                        .partial_cmp(&a.breakeven_percentage)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                });
-            }
-
-            if params.risk_reward_ratio {
-                credit_spreads.retain(|spread| spread.max_loss <= 3.0 * spread.max_profit);
-            }
+/// Bull put spreads on a parsed chain, as `scan_bear_call`.
+pub fn scan_bull_put(
+    instruments: &[Instrument],
+    params: &BearCallSpreadParams,
+) -> Result<Vec<CreditSpread>, String> {
+    let candidates = spreads::candidates(Strategy::BullPut, params, instruments.to_vec(), None)?;
+    Ok(spreads::finish(params, candidates))
+}
 
-            serde_json::to_string(&credit_spreads)
-                .unwrap_or_else(|_| String::from("Failed to serialize credit spreads"))
-        }
-        Err(err) => {
-            console::log_1(&JsValue::from_str(&format!(
-                "Failed to parse JSON: {:?}",
-                err
-            )));
-            String::from("Failed to parse JSON")
-        }
-    }
+/// Parses a chain in the crate's JSON format, or a raw Upstox response.
+pub fn parse_chain(optionchain: &str) -> Result<Vec<Instrument>, String> {
+    chain::parse(optionchain)
 }
@@ -0,0 +1,43 @@
+//! Liquidity score for a single option quote.
+//!
+//! Four components contribute up to 25 points each, for a score from 0 to
+//! 100: traded volume, open interest, bid/ask gap relative to mid, and quote
+//! depth (the smaller of `bid_qty` and `ask_qty`). Volume, OI and depth are
+//! scored on a log scale that saturates at index-option levels, so a strike
+//! with a tenth of the saturation volume still earns most of its points.
+
+use crate::MarketData;
+
+const MAX_COMPONENT: f64 = 25.0;
+const VOLUME_SATURATION: f64 = 1_000_000.0;
+const OI_SATURATION: f64 = 1_000_000.0;
+const DEPTH_SATURATION: f64 = 10_000.0;
+// Bid/ask gap, as a fraction of mid, that scores zero
+const MAX_RELATIVE_SPREAD: f64 = 0.10;
+
+fn log_component(value: Option<u64>, saturation: f64) -> f64 {
+    let value = value.unwrap_or(0) as f64;
+    MAX_COMPONENT * ((1.0 + value).log10() / (1.0 + saturation).log10()).min(1.0)
+}
+
+fn spread_component(market_data: &MarketData) -> f64 {
+    match (market_data.bid_price, market_data.ask_price) {
+        (Some(bid), Some(ask)) if bid > 0.0 && ask >= bid => {
+            let relative = (ask - bid) / (0.5 * (bid + ask));
+            MAX_COMPONENT * (1.0 - relative / MAX_RELATIVE_SPREAD).max(0.0)
+        }
+        _ => 0.0,
+    }
+}
+
+/// Liquidity score from 0 (untradeable) to 100.
+pub fn score(market_data: &MarketData) -> f64 {
+    let depth = market_data
+        .bid_qty
+        .zip(market_data.ask_qty)
+        .map(|(bid, ask)| bid.min(ask));
+    log_component(market_data.volume, VOLUME_SATURATION)
+        + log_component(market_data.oi, OI_SATURATION)
+        + spread_component(market_data)
+        + log_component(depth, DEPTH_SATURATION)
+}
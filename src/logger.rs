@@ -0,0 +1,86 @@
+//! Routes `log` records to JS.
+//!
+//! The crate logs through the `log` facade so it does not assume a browser.
+//! In the wasm build records go to the callback passed to `set_logger`, or
+//! else to `console` looked up on the global object, which browsers, Web
+//! Workers, Node and Deno all provide. Native builds install any `log`
+//! implementation they like.
+
+use js_sys::{Function, Reflect};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::cell::RefCell;
+use wasm_bindgen::{JsCast, JsValue};
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+thread_local! {
+    static CALLBACK: RefCell<Option<Function>> = const { RefCell::new(None) };
+}
+
+struct JsLogger;
+
+static LOGGER: JsLogger = JsLogger;
+
+fn console(level: Level, message: &JsValue) {
+    let method = match level {
+        Level::Error => "error",
+        Level::Warn => "warn",
+        Level::Info => "info",
+        Level::Debug | Level::Trace => "debug",
+    };
+    let Ok(console) = Reflect::get(&js_sys::global(), &JsValue::from_str("console")) else {
+        return;
+    };
+    if let Some(log) = Reflect::get(&console, &JsValue::from_str(method))
+        .ok()
+        .and_then(|value| value.dyn_into::<Function>().ok())
+    {
+        let _ = log.call1(&console, message);
+    }
+}
+
+impl Log for JsLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = JsValue::from_str(&record.args().to_string());
+        let called = CALLBACK.with_borrow(|callback| {
+            callback.as_ref().map(|callback| {
+                let level = JsValue::from_str(&record.level().as_str().to_lowercase());
+                let _ = callback.call2(&JsValue::NULL, &level, &message);
+            })
+        });
+        if called.is_none() {
+            console(record.level(), &message);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Makes this the `log` logger, at `info` and above, unless one is set.
+pub fn install() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+}
+
+/// Sends records to `callback`, or back to the console when `None`, at
+/// `level` and above.
+pub fn set(callback: Option<Function>, level: Option<&str>) -> Result<(), String> {
+    let level = match level {
+        Some(level) => level
+            .parse::<LevelFilter>()
+            .map_err(|_| format!("Unknown log level: {}", level))?,
+        None => DEFAULT_LEVEL,
+    };
+    install();
+    CALLBACK.set(callback);
+    log::set_max_level(level);
+    Ok(())
+}
@@ -0,0 +1,46 @@
+//! Contract lot sizes for the index options the scanners know about.
+//!
+//! Sizes are the exchanges' revision for contracts from January 2026; pass
+//! `lot_size` when a later revision lands before this table is updated.
+
+// (symbol, Upstox instrument name, lot size)
+const INDEX_LOT_SIZES: [(&str, &str, f64); 5] = [
+    ("NIFTY", "Nifty 50", 65.0),
+    ("BANKNIFTY", "Nifty Bank", 30.0),
+    ("FINNIFTY", "Nifty Fin Service", 60.0),
+    ("MIDCPNIFTY", "NIFTY MID SELECT", 120.0),
+    ("SENSEX", "SENSEX", 20.0),
+];
+
+/// Looks up the lot size for an `underlying_key` such as `NSE_INDEX|Nifty Bank`.
+/// Bare symbols like `BANKNIFTY` are accepted too.
+pub fn lookup(underlying_key: &str) -> Option<f64> {
+    let name = underlying_key
+        .rsplit_once('|')
+        .map_or(underlying_key, |(_, name)| name)
+        .trim();
+    INDEX_LOT_SIZES
+        .iter()
+        .find(|(symbol, instrument_name, _)| {
+            name.eq_ignore_ascii_case(symbol) || name.eq_ignore_ascii_case(instrument_name)
+        })
+        .map(|&(_, _, lot_size)| lot_size)
+}
+
+/// Lot size for a scan: the caller's explicit value wins, then the table.
+/// Underlyings the table doesn't know, stocks among them, need `lot_size`.
+pub fn resolve(explicit: Option<f64>, underlying_key: &str) -> Result<f64, String> {
+    explicit
+        .filter(|lot_size| *lot_size > 0.0)
+        .or_else(|| lookup(underlying_key))
+        .ok_or_else(|| format!("lot_size is required for {}", underlying_key))
+}
+
+/// The exchange's index name for a bare symbol, e.g. `Nifty Bank` for
+/// `BANKNIFTY`.
+pub fn index_name(symbol: &str) -> Option<&'static str> {
+    INDEX_LOT_SIZES
+        .iter()
+        .find(|(known, _, _)| symbol.trim().eq_ignore_ascii_case(known))
+        .map(|&(_, instrument_name, _)| instrument_name)
+}
@@ -0,0 +1,123 @@
+//! Approximate SPAN plus exposure margin for short option legs.
+//!
+//! Exchange SPAN runs a grid of price and volatility scenarios; this module
+//! uses the usual broker shortcut instead. A naked short is charged a
+//! percentage of the underlying notional, less the amount the strike is out
+//! of the money, with a floor. A hedged spread can't lose more than its
+//! width, so its SPAN is capped there. Exposure margin is a flat percentage of
+//! notional on the short leg either way. Pass `margin_per_lot` to use the
+//! broker's own figure instead.
+
+use crate::greeks::OptionKind;
+use crate::positions::{Leg, Side};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(default)]
+pub struct MarginModel {
+    /// SPAN charge on a naked short, as a percent of underlying notional.
+    pub span_percentage: f64,
+    /// Smallest SPAN charge on a far out-of-the-money short, as a percent of notional.
+    pub minimum_span_percentage: f64,
+    pub exposure_percentage: f64,
+    /// Broker-quoted margin per lot of the structure; overrides the estimate.
+    pub margin_per_lot: Option<f64>,
+}
+
+impl Default for MarginModel {
+    fn default() -> Self {
+        MarginModel {
+            span_percentage: 9.0,
+            minimum_span_percentage: 3.0,
+            exposure_percentage: 2.0,
+            margin_per_lot: None,
+        }
+    }
+}
+
+/// Inputs for one lot of a short leg, optionally hedged by a long leg.
+pub struct ShortLeg {
+    pub kind: OptionKind,
+    pub strike: f64,
+    pub spot: f64,
+    pub lot_size: f64,
+    /// Strike of the long hedge on the same side, if any.
+    pub hedge_strike: Option<f64>,
+}
+
+fn notional(leg: &ShortLeg) -> f64 {
+    leg.spot * leg.lot_size
+}
+
+fn exposure(leg: &ShortLeg, model: &MarginModel) -> f64 {
+    notional(leg) * model.exposure_percentage / 100.0
+}
+
+fn naked_span(leg: &ShortLeg, model: &MarginModel) -> f64 {
+    let otm = match leg.kind {
+        OptionKind::Call => leg.strike - leg.spot,
+        OptionKind::Put => leg.spot - leg.strike,
+    }
+    .max(0.0)
+        * leg.lot_size;
+    (notional(leg) * model.span_percentage / 100.0 - otm)
+        .max(notional(leg) * model.minimum_span_percentage / 100.0)
+}
+
+/// Estimated margin for one lot of the short leg without its hedge. Always
+/// uses the model percentages, even when `margin_per_lot` is set.
+pub fn naked(leg: &ShortLeg, model: &MarginModel) -> f64 {
+    naked_span(leg, model) + exposure(leg, model)
+}
+
+/// Estimated margin for one lot of the short leg and its hedge.
+pub fn estimate(leg: &ShortLeg, model: &MarginModel) -> f64 {
+    if let Some(per_lot) = model.margin_per_lot {
+        return per_lot;
+    }
+    let span = match leg.hedge_strike {
+        Some(hedge) => naked_span(leg, model).min((hedge - leg.strike).abs() * leg.lot_size),
+        None => naked_span(leg, model),
+    };
+    span + exposure(leg, model)
+}
+
+/// Estimated margin for a set of legs on one underlying. Each short leg is
+/// hedged by the nearest further out-of-the-money long leg of the same type
+/// and expiry, if there is one.
+pub fn for_legs(legs: &[Leg], spot: f64, lot_size: f64, model: &MarginModel) -> f64 {
+    legs.iter()
+        .filter(|leg| leg.side == Side::Sell)
+        .map(|short| {
+            let hedge_strike = legs
+                .iter()
+                .filter(|leg| {
+                    leg.side == Side::Buy
+                        && leg.option_type == short.option_type
+                        && leg.expiry == short.expiry
+                        && match short.option_type {
+                            OptionKind::Call => leg.strike > short.strike,
+                            OptionKind::Put => leg.strike < short.strike,
+                        }
+                })
+                .map(|leg| leg.strike)
+                .min_by(|a, b| {
+                    (a - short.strike)
+                        .abs()
+                        .partial_cmp(&(b - short.strike).abs())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            let leg = ShortLeg {
+                kind: short.option_type,
+                strike: short.strike,
+                spot,
+                lot_size,
+                hedge_strike,
+            };
+            estimate(&leg, model) * short.lots
+        })
+        .sum()
+}
@@ -0,0 +1,21 @@
+//! MessagePack encoding for chains and scan results.
+//!
+//! Large chains spend much of a scan's time in JSON text on either side of
+//! the JS boundary. MessagePack carries the same field names as the JSON
+//! formats, with structs encoded as maps, so any MessagePack library on the
+//! JS side decodes results into the same objects `JSON.parse` would give.
+
+use crate::{chain, Instrument};
+use serde::Serialize;
+
+/// Decodes a chain encoded as an array of `Instrument` maps.
+pub fn decode_chain(bytes: &[u8]) -> Result<Vec<Instrument>, String> {
+    let mut instruments = rmp_serde::from_slice::<Vec<Instrument>>(bytes)
+        .map_err(|_| String::from("Failed to decode MessagePack chain"))?;
+    chain::normalize_expiries(&mut instruments);
+    Ok(instruments)
+}
+
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    rmp_serde::to_vec_named(value).map_err(|_| String::from("Failed to encode MessagePack"))
+}
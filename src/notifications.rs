@@ -0,0 +1,114 @@
+//! Web Notification payloads for fired alerts.
+//!
+//! The caller's alert engine decides when a tracked structure has crossed its
+//! target or stop; this module only turns those events into the
+//! `(title, options)` pair that `ServiceWorkerRegistration.showNotification`
+//! expects, so the wording and tagging stay consistent across clients.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    Target,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct AlertEvent {
+    structure_id: String,
+    kind: AlertKind,
+    /// Threshold that was crossed.
+    level: f64,
+    /// Value observed when the alert fired.
+    value: f64,
+    /// Human readable name such as `NIFTY 24200/24300 CE`; defaults to the id.
+    #[serde(default)]
+    label: Option<String>,
+    /// Epoch milliseconds at which the alert fired.
+    #[serde(default)]
+    triggered_at: Option<f64>,
+}
+
+impl AlertEvent {
+    pub fn new(
+        structure_id: String,
+        kind: AlertKind,
+        level: f64,
+        value: f64,
+        label: Option<String>,
+        triggered_at: Option<f64>,
+    ) -> Self {
+        AlertEvent {
+            structure_id,
+            kind,
+            level,
+            value,
+            label,
+            triggered_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct NotificationData {
+    structure_id: String,
+    kind: AlertKind,
+    level: f64,
+    value: f64,
+    triggered_at: Option<f64>,
+}
+
+// Field names follow the DOM `NotificationOptions` dictionary
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationOptions {
+    body: String,
+    /// One tag per structure and alert kind, so a re-fire replaces the old notification.
+    tag: String,
+    renotify: bool,
+    require_interaction: bool,
+    data: NotificationData,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct NotificationPayload {
+    title: String,
+    options: NotificationOptions,
+}
+
+pub fn payload(event: &AlertEvent) -> NotificationPayload {
+    let label = event.label.as_deref().unwrap_or(&event.structure_id);
+    let (title, tag_suffix) = match event.kind {
+        AlertKind::Target => (format!("Target reached: {}", label), "target"),
+        AlertKind::Stop => (format!("Stop hit: {}", label), "stop"),
+    };
+
+    NotificationPayload {
+        title,
+        options: NotificationOptions {
+            body: format!(
+                "Now at {:.2}, crossed the {} level of {:.2}",
+                event.value, tag_suffix, event.level
+            ),
+            tag: format!("rupeetrader-{}-{}", event.structure_id, tag_suffix),
+            renotify: true,
+            // Stops need action; targets are informational
+            require_interaction: event.kind == AlertKind::Stop,
+            data: NotificationData {
+                structure_id: event.structure_id.clone(),
+                kind: event.kind,
+                level: event.level,
+                value: event.value,
+                triggered_at: event.triggered_at,
+            },
+        },
+    }
+}
@@ -0,0 +1,146 @@
+//! The nseindia.com option chain JSON.
+//!
+//! NSE nests each strike's `CE` and `PE` quotes under `records.data` for
+//! every expiry, and under `filtered.data` for the nearest one only. The
+//! previous close and previous OI aren't given directly, so they are backed
+//! out of `change` and `changeinOpenInterest`. Only IV is published, and NSE
+//! reports zero when it has none; the scanners solve the rest of the greeks.
+
+use crate::source::{self, ChainSource};
+use crate::{dates, Instrument, MarketData, OptionData, OptionGreeks};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NseQuote {
+    #[serde(default)]
+    identifier: String,
+    #[serde(default)]
+    underlying: String,
+    underlying_value: Option<f64>,
+    last_price: Option<f64>,
+    change: Option<f64>,
+    total_traded_volume: Option<f64>,
+    open_interest: Option<f64>,
+    changein_open_interest: Option<f64>,
+    implied_volatility: Option<f64>,
+    #[serde(rename = "bidprice")]
+    bid_price: Option<f64>,
+    bid_qty: Option<f64>,
+    ask_price: Option<f64>,
+    ask_qty: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NseRow {
+    strike_price: f64,
+    expiry_date: String,
+    #[serde(rename = "CE")]
+    call: Option<NseQuote>,
+    #[serde(rename = "PE")]
+    put: Option<NseQuote>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NseRecords {
+    #[serde(default)]
+    data: Vec<NseRow>,
+    underlying_value: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct NseChain {
+    records: Option<NseRecords>,
+    filtered: Option<NseRecords>,
+}
+
+// NSE sends counts as JSON numbers that may carry a fraction
+fn count(value: Option<f64>) -> Option<u64> {
+    value.filter(|v| *v >= 0.0).map(|v| v.round() as u64)
+}
+
+fn option_data(quote: &NseQuote) -> OptionData {
+    let prev_oi = quote
+        .open_interest
+        .zip(quote.changein_open_interest)
+        .map(|(oi, change)| oi - change);
+    OptionData {
+        instrument_key: quote.identifier.clone(),
+        market_data: Some(MarketData {
+            ltp: quote.last_price,
+            volume: count(quote.total_traded_volume),
+            oi: count(quote.open_interest),
+            close_price: quote
+                .last_price
+                .zip(quote.change)
+                .map(|(ltp, change)| ltp - change),
+            bid_price: quote.bid_price,
+            bid_qty: count(quote.bid_qty),
+            ask_price: quote.ask_price,
+            ask_qty: count(quote.ask_qty),
+            prev_oi: count(prev_oi),
+        }),
+        option_greeks: quote
+            .implied_volatility
+            .filter(|iv| *iv > 0.0)
+            .map(|iv| OptionGreeks {
+                vega: None,
+                theta: None,
+                gamma: None,
+                delta: None,
+                iv: Some(iv),
+                computed: false,
+                mismatch: false,
+            }),
+    }
+}
+
+/// Converts an NSE option chain into instruments, using `records` when it
+/// is present and `filtered` otherwise.
+pub fn parse(payload: &str) -> Result<Vec<Instrument>, String> {
+    let chain = serde_json::from_str::<NseChain>(payload)
+        .map_err(|_| String::from("Failed to parse NSE option chain"))?;
+    let records = chain
+        .records
+        .filter(|records| !records.data.is_empty())
+        .or(chain.filtered)
+        .ok_or_else(|| String::from("NSE option chain has no records"))?;
+
+    Ok(records
+        .data
+        .iter()
+        .filter_map(|row| {
+            let quote = row.call.as_ref().or(row.put.as_ref());
+            let spot = quote
+                .and_then(|quote| quote.underlying_value)
+                .or(records.underlying_value)?;
+            let expiry = dates::normalize_expiry(&row.expiry_date)
+                .unwrap_or_else(|| row.expiry_date.clone());
+            Some(Instrument {
+                expiry,
+                strike_price: row.strike_price,
+                underlying_key: quote
+                    .map(|quote| quote.underlying.clone())
+                    .unwrap_or_default(),
+                underlying_spot_price: spot,
+                call_options: row.call.as_ref().map(option_data),
+                put_options: row.put.as_ref().map(option_data),
+                exercise_style: None,
+            })
+        })
+        .collect())
+}
+
+pub struct NseSource;
+
+impl ChainSource for NseSource {
+    fn format(&self) -> &'static str {
+        "nse"
+    }
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+        parse(&source::json_text(data))
+    }
+}
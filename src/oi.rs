@@ -0,0 +1,206 @@
+//! Open interest analysis.
+//!
+//! Day-over-day OI change comes from `oi` against `prev_oi`, and price change
+//! from `ltp` against `close_price` (the previous session's close). Strikes
+//! with the largest call OI are read as resistance and those with the largest
+//! put OI as support, since option writers defend them.
+
+use crate::greeks::OptionKind;
+use crate::{chain, Instrument, MarketData};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+/// Positioning read from the direction of price and OI together.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum OiActivity {
+    /// Price and OI up: fresh longs.
+    LongBuildup,
+    /// Price down, OI up: fresh shorts.
+    ShortBuildup,
+    /// Price and OI down: longs closing.
+    LongUnwinding,
+    /// Price up, OI down: shorts closing.
+    ShortCovering,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct OptionBuildup {
+    oi: Option<u64>,
+    prev_oi: Option<u64>,
+    oi_change: Option<f64>,
+    /// `oi_change` as a percent of `prev_oi`.
+    oi_change_pct: Option<f64>,
+    price_change: Option<f64>,
+    /// `None` when either change is unknown or zero.
+    buildup: Option<OiActivity>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StrikeBuildup {
+    underlying_key: String,
+    expiry: String,
+    strike: f64,
+    call: Option<OptionBuildup>,
+    put: Option<OptionBuildup>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct OiLevel {
+    strike: f64,
+    oi: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct OiChange {
+    strike: f64,
+    oi_change: f64,
+    oi_change_pct: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct OiLevels {
+    underlying_key: String,
+    expiry: String,
+    spot: f64,
+    /// Highest call OI strikes, largest first.
+    resistance: Vec<OiLevel>,
+    /// Highest put OI strikes, largest first.
+    support: Vec<OiLevel>,
+    /// Strikes that added the most call OI since the previous session.
+    call_oi_additions: Vec<OiChange>,
+    put_oi_additions: Vec<OiChange>,
+}
+
+pub const DEFAULT_TOP_LEVELS: usize = 3;
+
+pub fn oi_change(market_data: &MarketData) -> Option<f64> {
+    Some(market_data.oi? as f64 - market_data.prev_oi? as f64)
+}
+
+pub fn oi_change_pct(market_data: &MarketData) -> Option<f64> {
+    let prev_oi = market_data.prev_oi.filter(|oi| *oi > 0)? as f64;
+    Some(oi_change(market_data)? / prev_oi * 100.0)
+}
+
+fn price_change(market_data: &MarketData) -> Option<f64> {
+    Some(market_data.ltp? - market_data.close_price.filter(|close| *close > 0.0)?)
+}
+
+fn classify(price_change: f64, oi_change: f64) -> Option<OiActivity> {
+    match (price_change, oi_change) {
+        (p, o) if p > 0.0 && o > 0.0 => Some(OiActivity::LongBuildup),
+        (p, o) if p < 0.0 && o > 0.0 => Some(OiActivity::ShortBuildup),
+        (p, o) if p < 0.0 && o < 0.0 => Some(OiActivity::LongUnwinding),
+        (p, o) if p > 0.0 && o < 0.0 => Some(OiActivity::ShortCovering),
+        _ => None,
+    }
+}
+
+fn option_buildup(instrument: &Instrument, kind: OptionKind) -> Option<OptionBuildup> {
+    let market_data = chain::option(instrument, kind)?.market_data.as_ref()?;
+    let oi_change = oi_change(market_data);
+    let price_change = price_change(market_data);
+    Some(OptionBuildup {
+        oi: market_data.oi,
+        prev_oi: market_data.prev_oi,
+        oi_change,
+        oi_change_pct: oi_change_pct(market_data),
+        price_change,
+        buildup: price_change
+            .zip(oi_change)
+            .and_then(|(price, oi)| classify(price, oi)),
+    })
+}
+
+/// Buildup of the call and put at every strike, in chain order.
+pub fn buildup(optionchain: &str) -> Result<Vec<StrikeBuildup>, String> {
+    let instruments = chain::parse(optionchain)?;
+    Ok(instruments
+        .iter()
+        .map(|instrument| StrikeBuildup {
+            underlying_key: instrument.underlying_key.clone(),
+            expiry: instrument.expiry.clone(),
+            strike: instrument.strike_price,
+            call: option_buildup(instrument, OptionKind::Call),
+            put: option_buildup(instrument, OptionKind::Put),
+        })
+        .collect())
+}
+
+/// The `n` strikes with the most `kind` open interest, largest first.
+pub fn top_levels(instruments: &[Instrument], kind: OptionKind, n: usize) -> Vec<OiLevel> {
+    let mut levels: Vec<OiLevel> = instruments
+        .iter()
+        .filter_map(|instrument| {
+            let oi = chain::option(instrument, kind)?.market_data.as_ref()?.oi?;
+            (oi > 0).then_some(OiLevel {
+                strike: instrument.strike_price,
+                oi,
+            })
+        })
+        .collect();
+    levels.sort_by_key(|level| std::cmp::Reverse(level.oi));
+    levels.truncate(n);
+    levels
+}
+
+/// The `n` strikes whose `kind` open interest grew the most, largest first.
+pub fn top_additions(instruments: &[Instrument], kind: OptionKind, n: usize) -> Vec<OiChange> {
+    let mut changes: Vec<OiChange> = instruments
+        .iter()
+        .filter_map(|instrument| {
+            let market_data = chain::option(instrument, kind)?.market_data.as_ref()?;
+            let oi_change = oi_change(market_data).filter(|change| *change > 0.0)?;
+            Some(OiChange {
+                strike: instrument.strike_price,
+                oi_change,
+                oi_change_pct: oi_change_pct(market_data),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| {
+        b.oi_change
+            .partial_cmp(&a.oi_change)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    changes.truncate(n);
+    changes
+}
+
+/// Strike with the most `kind` open interest.
+pub fn wall(instruments: &[Instrument], kind: OptionKind) -> Option<f64> {
+    top_levels(instruments, kind, 1)
+        .first()
+        .map(|level| level.strike)
+}
+
+/// Top call-OI (resistance) and put-OI (support) strikes per underlying and
+/// expiry, with the strikes that added the most OI on each side.
+pub fn levels(optionchain: &str, top_n: usize) -> Result<Vec<OiLevels>, String> {
+    let instruments = chain::parse(optionchain)?;
+    Ok(chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    })
+    .into_values()
+    .filter_map(|instruments| {
+        let first = instruments.first()?;
+        Some(OiLevels {
+            underlying_key: first.underlying_key.clone(),
+            expiry: first.expiry.clone(),
+            spot: first.underlying_spot_price,
+            resistance: top_levels(&instruments, OptionKind::Call, top_n),
+            support: top_levels(&instruments, OptionKind::Put, top_n),
+            call_oi_additions: top_additions(&instruments, OptionKind::Call, top_n),
+            put_oi_additions: top_additions(&instruments, OptionKind::Put, top_n),
+        })
+    })
+    .collect())
+}
@@ -0,0 +1,195 @@
+//! Broker order payloads for a scanned spread.
+//!
+//! Each leg becomes one limit order at the leg's entry price, rounded to the
+//! exchange tick, for `qty_lots` lots. Upstox orders use the leg's
+//! `instrument_key` as the instrument token and carry a `correlation_id`, as
+//! its multi-order endpoint expects. Kite orders take the exchange and
+//! trading symbol from an `EXCHANGE:TRADINGSYMBOL` key, as produced by
+//! `parse_kite_chain`. Both use the carry-forward product, since spreads are
+//! normally held overnight.
+//!
+//! Orders come hedge first: every buy leg before any sell leg. Indian
+//! brokers only grant the spread margin benefit once the long leg is on the
+//! books, so selling first blocks naked margin and can be rejected outright.
+//! `execution_order` lists the same sequence against the spread's legs.
+
+use crate::positions::{Leg, Side};
+use crate::CreditSpread;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const TICK_SIZE: f64 = 0.05;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum Broker {
+    Upstox,
+    Kite,
+}
+
+impl Broker {
+    pub fn from_name(name: &str) -> Result<Broker, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "upstox" => Ok(Broker::Upstox),
+            "kite" | "zerodha" => Ok(Broker::Kite),
+            _ => Err(format!("Unknown broker {}; expected upstox or kite", name)),
+        }
+    }
+}
+
+/// Body of one Upstox order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct UpstoxOrder {
+    correlation_id: String,
+    instrument_token: String,
+    quantity: u64,
+    product: String,
+    validity: String,
+    price: f64,
+    order_type: String,
+    transaction_type: String,
+    disclosed_quantity: u64,
+    trigger_price: f64,
+    is_amo: bool,
+}
+
+/// Parameters of one Kite order.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct KiteOrder {
+    variety: String,
+    exchange: String,
+    tradingsymbol: String,
+    transaction_type: String,
+    order_type: String,
+    quantity: u64,
+    product: String,
+    price: f64,
+    validity: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(untagged)]
+pub enum BrokerOrder {
+    Upstox(UpstoxOrder),
+    Kite(KiteOrder),
+}
+
+/// One step of the recommended execution; wait for each fill before the next.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ExecutionStep {
+    step: usize,
+    /// Index into the spread's `legs`.
+    leg: usize,
+    side: Side,
+    strike: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct OrderPayload {
+    broker: Broker,
+    /// In execution order.
+    orders: Vec<BrokerOrder>,
+    execution_order: Vec<ExecutionStep>,
+}
+
+fn round_to_tick(price: f64) -> f64 {
+    (price / TICK_SIZE).round() / (1.0 / TICK_SIZE)
+}
+
+fn transaction_type(side: Side) -> String {
+    String::from(match side {
+        Side::Buy => "BUY",
+        Side::Sell => "SELL",
+    })
+}
+
+fn order(broker: Broker, leg: &Leg, step: usize, quantity: u64) -> Result<BrokerOrder, String> {
+    let key = leg
+        .instrument_key
+        .as_deref()
+        .ok_or_else(|| format!("Leg at strike {} has no instrument_key", leg.strike))?;
+    let price = round_to_tick(leg.price);
+
+    Ok(match broker {
+        Broker::Upstox => BrokerOrder::Upstox(UpstoxOrder {
+            correlation_id: format!("step{}", step),
+            instrument_token: key.to_string(),
+            quantity,
+            product: String::from("D"),
+            validity: String::from("DAY"),
+            price,
+            order_type: String::from("LIMIT"),
+            transaction_type: transaction_type(leg.side),
+            disclosed_quantity: 0,
+            trigger_price: 0.0,
+            is_amo: false,
+        }),
+        Broker::Kite => {
+            let (exchange, tradingsymbol) = key
+                .split_once(':')
+                .ok_or_else(|| format!("{} is not a Kite EXCHANGE:TRADINGSYMBOL key", key))?;
+            BrokerOrder::Kite(KiteOrder {
+                variety: String::from("regular"),
+                exchange: exchange.to_string(),
+                tradingsymbol: tradingsymbol.to_string(),
+                transaction_type: transaction_type(leg.side),
+                order_type: String::from("LIMIT"),
+                quantity,
+                product: String::from("NRML"),
+                price,
+                validity: String::from("DAY"),
+            })
+        }
+    })
+}
+
+pub fn payload(
+    spread: &CreditSpread,
+    broker: Broker,
+    qty_lots: u32,
+) -> Result<OrderPayload, String> {
+    if qty_lots == 0 {
+        return Err(String::from("qty_lots must be at least 1"));
+    }
+    if !(spread.lot_size.is_finite() && spread.lot_size > 0.0) {
+        return Err(String::from("Spread has no lot size"));
+    }
+    let quantity = (spread.lot_size * qty_lots as f64) as u64;
+
+    // Stable, so legs on the same side keep their order in the spread
+    let mut sequence: Vec<(usize, &Leg)> = spread
+        .legs
+        .iter()
+        .map(|leg| leg.leg())
+        .enumerate()
+        .collect();
+    sequence.sort_by_key(|(_, leg)| leg.side != Side::Buy);
+
+    let orders = sequence
+        .iter()
+        .enumerate()
+        .map(|(step, (_, leg))| order(broker, leg, step + 1, quantity))
+        .collect::<Result<_, _>>()?;
+    let execution_order = sequence
+        .iter()
+        .enumerate()
+        .map(|(step, &(index, leg))| ExecutionStep {
+            step: step + 1,
+            leg: index,
+            side: leg.side,
+            strike: leg.strike,
+        })
+        .collect();
+    Ok(OrderPayload {
+        broker,
+        orders,
+        execution_order,
+    })
+}
@@ -0,0 +1,103 @@
+//! Fixed-layout numeric encoding of credit spread results.
+//!
+//! Every spread becomes one row of `PACKED_COLUMNS.len()` consecutive `f64`s
+//! in a single flat buffer, so row `i`, column `j` lives at
+//! `i * PACKED_COLUMNS.len() + j`. Columns, in order:
+//!
+//! | index | column                 | notes                    |
+//! |-------|------------------------|--------------------------|
+//! | 0     | `sell_strike`          |                          |
+//! | 1     | `buy_strike`           |                          |
+//! | 2     | `spread`               | rupees                   |
+//! | 3     | `net_credit`           | rupees                   |
+//! | 4     | `max_profit`           | rupees                   |
+//! | 5     | `max_loss`             | rupees                   |
+//! | 6     | `breakeven`            |                          |
+//! | 7     | `breakeven_percentage` |                          |
+//! | 8     | `type_`                | `0.0` = CE, `1.0` = PE   |
+//!
+//! `schema` describes the same layout as data, for readers of rows written
+//! straight into a shared buffer.
+
+use crate::greeks::OptionKind;
+use crate::CreditSpread;
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+pub const PACKED_COLUMNS: [&str; 9] = [
+    "sell_strike",
+    "buy_strike",
+    "spread",
+    "net_credit",
+    "max_profit",
+    "max_loss",
+    "breakeven",
+    "breakeven_percentage",
+    "type_",
+];
+
+/// Layout of packed rows: `stride` little-endian `f64`s of `bytes_per_value`
+/// bytes each, one per `columns` entry in order.
+#[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PackedSchema {
+    pub columns: Vec<String>,
+    pub stride: usize,
+    pub bytes_per_value: usize,
+    /// Codes of the `type_` column.
+    pub type_codes: Vec<(String, f64)>,
+}
+
+pub fn schema() -> PackedSchema {
+    PackedSchema {
+        columns: PACKED_COLUMNS.iter().map(|c| c.to_string()).collect(),
+        stride: PACKED_COLUMNS.len(),
+        bytes_per_value: std::mem::size_of::<f64>(),
+        type_codes: vec![
+            (String::from("CE"), option_type_code(OptionKind::Call)),
+            (String::from("PE"), option_type_code(OptionKind::Put)),
+        ],
+    }
+}
+
+fn option_type_code(type_: OptionKind) -> f64 {
+    match type_ {
+        OptionKind::Call => 0.0,
+        OptionKind::Put => 1.0,
+    }
+}
+
+fn row(spread: &CreditSpread) -> [f64; PACKED_COLUMNS.len()] {
+    [
+        spread.sell_strike,
+        spread.buy_strike,
+        spread.spread,
+        spread.net_credit,
+        spread.max_profit,
+        spread.max_loss,
+        spread.breakeven,
+        spread.breakeven_percentage,
+        option_type_code(spread.type_),
+    ]
+}
+
+pub fn pack(credit_spreads: &[CreditSpread]) -> Vec<f64> {
+    let mut buffer = Vec::with_capacity(credit_spreads.len() * PACKED_COLUMNS.len());
+    for spread in credit_spreads {
+        buffer.extend_from_slice(&row(spread));
+    }
+    buffer
+}
+
+/// The same values as `pack`, one buffer per column in `PACKED_COLUMNS`
+/// order, each holding a value per spread.
+pub fn pack_columns(credit_spreads: &[CreditSpread]) -> Vec<Vec<f64>> {
+    let mut columns = vec![Vec::with_capacity(credit_spreads.len()); PACKED_COLUMNS.len()];
+    for spread in credit_spreads {
+        for (column, value) in columns.iter_mut().zip(row(spread)) {
+            column.push(value);
+        }
+    }
+    columns
+}
@@ -0,0 +1,368 @@
+//! Payoff curves for a set of option legs.
+//!
+//! Legs use the same shape as spread `legs` and position legs, so a scan
+//! result can be charted as-is. P&L is per unit of the underlying scaled by
+//! each leg's `lots`; multiply by the lot size for rupees.
+//!
+//! Before expiry each leg is valued with Black-Scholes at its own `iv`, or on
+//! a binomial tree for `american` exercise, holding IV constant as spot
+//! moves.
+
+use crate::dates;
+use crate::greeks::{ExerciseStyle, Greeks, OptionKind, Pricing};
+use crate::positions::Leg;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PayoffParams {
+    legs: Vec<Leg>,
+    /// `[low, high]` underlying prices.
+    price_range: (f64, f64),
+    steps: u32,
+    /// Epoch milliseconds to value the legs at; defaults to the current time.
+    #[serde(default)]
+    at: Option<f64>,
+    /// Annualized decimals; see `bear_call_spread`.
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    #[serde(default)]
+    exercise_style: Option<ExerciseStyle>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PayoffCurves {
+    /// IST date of `at`.
+    as_of: String,
+    at_expiry: Vec<PayoffPoint>,
+    at_date: Vec<PayoffPoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SurfaceParams {
+    legs: Vec<Leg>,
+    price_range: (f64, f64),
+    steps: u32,
+    /// Days after `at` to sample, one row per day from 0. Defaults to the
+    /// days until the first leg expires.
+    #[serde(default)]
+    days_ahead: Option<u32>,
+    #[serde(default)]
+    at: Option<f64>,
+    /// Annualized decimals; see `bear_call_spread`.
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    #[serde(default)]
+    exercise_style: Option<ExerciseStyle>,
+}
+
+/// Position greeks sampled on a day by price grid. Each greek is indexed
+/// `[day][price]`; theta is per day and vega per vol point, both scaled by
+/// leg lots like the payoff P&L.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct GreeksSurface {
+    underlying_prices: Vec<f64>,
+    days: Vec<u32>,
+    dates: Vec<String>,
+    delta: Vec<Vec<f64>>,
+    gamma: Vec<Vec<f64>>,
+    theta: Vec<Vec<f64>>,
+    vega: Vec<Vec<f64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PayoffPoint {
+    underlying_price: f64,
+    pnl: f64,
+}
+
+impl PayoffPoint {
+    pub fn new(underlying_price: f64, pnl: f64) -> Self {
+        PayoffPoint {
+            underlying_price,
+            pnl,
+        }
+    }
+}
+
+/// Value of one unit of the option when the underlying settles at `spot`.
+pub fn intrinsic(kind: OptionKind, strike: f64, spot: f64) -> f64 {
+    match kind {
+        OptionKind::Call => (spot - strike).max(0.0),
+        OptionKind::Put => (strike - spot).max(0.0),
+    }
+}
+
+/// P&L of `leg` if it is worth `value` per unit.
+pub fn leg_pnl(leg: &Leg, value: f64) -> f64 {
+    (value - leg.price) * leg.side.sign() * leg.lots
+}
+
+/// `steps + 1` evenly spaced underlying prices from `low` to `high`.
+pub fn price_grid(low: f64, high: f64, steps: u32) -> Result<Vec<f64>, String> {
+    if !(low.is_finite() && high.is_finite()) || low >= high {
+        return Err(String::from(
+            "Price range must be [low, high] with low below high",
+        ));
+    }
+    if steps == 0 {
+        return Err(String::from("Steps must be at least 1"));
+    }
+    let width = (high - low) / steps as f64;
+    Ok((0..=steps).map(|i| low + width * i as f64).collect())
+}
+
+/// P&L of all `legs` if the underlying settles at `underlying_price`.
+pub fn expiry_pnl(legs: &[Leg], underlying_price: f64) -> f64 {
+    legs.iter()
+        .map(|leg| {
+            leg_pnl(
+                leg,
+                intrinsic(leg.option_type, leg.strike, underlying_price),
+            )
+        })
+        .sum()
+}
+
+/// Underlying prices at which expiry P&L plus `realized` is zero. Expiry P&L
+/// is linear between strikes, so roots are found exactly by interpolating
+/// between the kinks.
+pub fn breakevens(legs: &[Leg], realized: f64) -> Vec<f64> {
+    let mut kinks: Vec<f64> = legs.iter().map(|leg| leg.strike).collect();
+    let Some(&highest) = kinks.iter().max_by(|a, b| a.total_cmp(b)) else {
+        return Vec::new();
+    };
+    kinks.push(0.0);
+    kinks.push(highest * 2.0);
+    kinks.sort_by(|a, b| a.total_cmp(b));
+    kinks.dedup();
+
+    let pnl = |price: f64| expiry_pnl(legs, price) + realized;
+    let mut roots = Vec::new();
+    for pair in kinks.windows(2) {
+        let (low, high) = (pair[0], pair[1]);
+        let (pnl_low, pnl_high) = (pnl(low), pnl(high));
+        if pnl_low == 0.0 {
+            roots.push(low);
+        } else if pnl_low.signum() != pnl_high.signum() && pnl_high != 0.0 {
+            roots.push(low + (high - low) * pnl_low / (pnl_low - pnl_high));
+        }
+    }
+    roots
+}
+
+/// P&L at expiry across `[low, high]`.
+pub fn curve(legs: &[Leg], low: f64, high: f64, steps: u32) -> Result<Vec<PayoffPoint>, String> {
+    Ok(price_grid(low, high, steps)?
+        .into_iter()
+        .map(|underlying_price| PayoffPoint {
+            underlying_price,
+            pnl: expiry_pnl(legs, underlying_price),
+        })
+        .collect())
+}
+
+/// Years to expiry and volatility for valuing `leg` at `at_ms`; `None` once
+/// the leg has expired.
+pub fn leg_inputs(leg: &Leg, at_ms: f64) -> Result<Option<(f64, f64)>, String> {
+    let expiry = leg
+        .expiry
+        .as_deref()
+        .ok_or_else(|| format!("Leg at strike {} has no expiry", leg.strike))?;
+    if dates::parse_expiry(expiry).is_none() {
+        return Err(format!(
+            "Leg at strike {} has an invalid expiry",
+            leg.strike
+        ));
+    }
+    let Some(t) = dates::years_to_expiry(expiry, at_ms) else {
+        return Ok(None);
+    };
+    let sigma = leg
+        .iv
+        .filter(|iv| *iv > 0.0)
+        .ok_or_else(|| format!("Leg at strike {} has no IV", leg.strike))?
+        / 100.0;
+    Ok(Some((t, sigma)))
+}
+
+/// Theoretical value of one unit of the leg at `spot`, given its
+/// `leg_inputs`. Legs past expiry are worth intrinsic value.
+pub fn leg_value(leg: &Leg, spot: f64, inputs: Option<(f64, f64)>, pricing: Pricing) -> f64 {
+    match inputs {
+        Some((t, sigma)) => pricing.price(leg.option_type, spot, leg.strike, t, sigma),
+        None => intrinsic(leg.option_type, leg.strike, spot),
+    }
+}
+
+/// Greeks of one unit of the leg at `spot`, given its `leg_inputs`. An
+/// expired leg only has the delta of its intrinsic value.
+fn leg_greeks(leg: &Leg, spot: f64, inputs: Option<(f64, f64)>, pricing: Pricing) -> Greeks {
+    match inputs {
+        Some((t, sigma)) => pricing.greeks(leg.option_type, spot, leg.strike, t, sigma),
+        None => Greeks {
+            delta: match leg.option_type {
+                OptionKind::Call if spot > leg.strike => 1.0,
+                OptionKind::Put if spot < leg.strike => -1.0,
+                _ => 0.0,
+            },
+            gamma: 0.0,
+            theta: 0.0,
+            vega: 0.0,
+        },
+    }
+}
+
+/// `PayoffCurves` as flat buffers, each one value per grid price.
+pub struct PayoffBuffers {
+    pub as_of: String,
+    pub underlying_prices: Vec<f64>,
+    pub at_expiry: Vec<f64>,
+    pub at_date: Vec<f64>,
+}
+
+/// `GreeksSurface` as flat buffers. Each greek holds one row of
+/// `underlying_prices.len()` values per day, end to end, so day `d` and price
+/// `i` sit at `d * underlying_prices.len() + i`.
+pub struct SurfaceBuffers {
+    pub underlying_prices: Vec<f64>,
+    pub days: Vec<u32>,
+    pub dates: Vec<String>,
+    pub delta: Vec<f64>,
+    pub gamma: Vec<f64>,
+    pub theta: Vec<f64>,
+    pub vega: Vec<f64>,
+}
+
+/// Expiry P&L and theoretical P&L at `params.at` over the same price grid,
+/// summed one leg at a time across the whole grid.
+pub fn curve_buffers(params: &PayoffParams) -> Result<PayoffBuffers, String> {
+    let (low, high) = params.price_range;
+    let at_ms = params.at.unwrap_or_else(dates::now_ms);
+    let grid = price_grid(low, high, params.steps)?;
+    let pricing = Pricing::new(
+        params.risk_free_rate,
+        params.dividend_yield,
+        params.exercise_style,
+    );
+
+    let mut at_date = vec![0.0; grid.len()];
+    for leg in &params.legs {
+        let inputs = leg_inputs(leg, at_ms)?;
+        for (pnl, &spot) in at_date.iter_mut().zip(&grid) {
+            *pnl += leg_pnl(leg, leg_value(leg, spot, inputs, pricing));
+        }
+    }
+
+    Ok(PayoffBuffers {
+        as_of: dates::to_iso(dates::ist_day(at_ms)),
+        at_expiry: grid
+            .iter()
+            .map(|&price| expiry_pnl(&params.legs, price))
+            .collect(),
+        underlying_prices: grid,
+        at_date,
+    })
+}
+
+pub fn curves(params: &PayoffParams) -> Result<PayoffCurves, String> {
+    let buffers = curve_buffers(params)?;
+    let points = |pnl: &[f64]| {
+        buffers
+            .underlying_prices
+            .iter()
+            .zip(pnl)
+            .map(|(&underlying_price, &pnl)| PayoffPoint::new(underlying_price, pnl))
+            .collect()
+    };
+    Ok(PayoffCurves {
+        at_expiry: points(&buffers.at_expiry),
+        at_date: points(&buffers.at_date),
+        as_of: buffers.as_of,
+    })
+}
+
+/// Position greeks over the price grid for each day ahead, summed one leg at
+/// a time across a day's row.
+pub fn surface_buffers(params: &SurfaceParams) -> Result<SurfaceBuffers, String> {
+    let (low, high) = params.price_range;
+    let at_ms = params.at.unwrap_or_else(dates::now_ms);
+    let underlying_prices = price_grid(low, high, params.steps)?;
+    let pricing = Pricing::new(
+        params.risk_free_rate,
+        params.dividend_yield,
+        params.exercise_style,
+    );
+
+    let days_ahead = match params.days_ahead {
+        Some(days) => days,
+        None => params
+            .legs
+            .iter()
+            .filter_map(|leg| dates::days_to_expiry(leg.expiry.as_deref()?, at_ms))
+            .min()
+            .ok_or_else(|| String::from("No leg has a valid expiry"))?
+            .max(0) as u32,
+    };
+    let days: Vec<u32> = (0..=days_ahead).collect();
+    let cells = days.len() * underlying_prices.len();
+
+    let mut surface = SurfaceBuffers {
+        dates: Vec::with_capacity(days.len()),
+        delta: vec![0.0; cells],
+        gamma: vec![0.0; cells],
+        theta: vec![0.0; cells],
+        vega: vec![0.0; cells],
+        underlying_prices,
+        days,
+    };
+    let width = surface.underlying_prices.len();
+    for (index, &day) in surface.days.iter().enumerate() {
+        let day_ms = at_ms + day as f64 * dates::MS_PER_DAY;
+        let row = index * width..(index + 1) * width;
+        let (delta, gamma, theta, vega) = (
+            &mut surface.delta[row.clone()],
+            &mut surface.gamma[row.clone()],
+            &mut surface.theta[row.clone()],
+            &mut surface.vega[row],
+        );
+        for leg in &params.legs {
+            let inputs = leg_inputs(leg, day_ms)?;
+            let quantity = leg.side.sign() * leg.lots;
+            for (i, &spot) in surface.underlying_prices.iter().enumerate() {
+                let greeks = leg_greeks(leg, spot, inputs, pricing);
+                delta[i] += greeks.delta * quantity;
+                gamma[i] += greeks.gamma * quantity;
+                theta[i] += greeks.theta * quantity;
+                vega[i] += greeks.vega * quantity;
+            }
+        }
+        surface.dates.push(dates::to_iso(dates::ist_day(day_ms)));
+    }
+    Ok(surface)
+}
+
+pub fn surface(params: &SurfaceParams) -> Result<GreeksSurface, String> {
+    let buffers = surface_buffers(params)?;
+    let width = buffers.underlying_prices.len();
+    let rows = |values: &[f64]| values.chunks(width).map(<[f64]>::to_vec).collect();
+    Ok(GreeksSurface {
+        delta: rows(&buffers.delta),
+        gamma: rows(&buffers.gamma),
+        theta: rows(&buffers.theta),
+        vega: rows(&buffers.vega),
+        underlying_prices: buffers.underlying_prices,
+        days: buffers.days,
+        dates: buffers.dates,
+    })
+}
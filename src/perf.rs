@@ -0,0 +1,157 @@
+//! Phase timings and counts for the most recent spread scan.
+//!
+//! A scan export opens a record with `start`, the scan code wraps each
+//! phase in `time` and bumps counts as it goes, and `finish` keeps the
+//! record for `last_scan_stats`. Phase times add up across slices, so a
+//! chain with several expiries reports its total pairing time. Outside a
+//! started scan, as in `rescan`, the hooks only run the work. A scan that
+//! fails drops its record unfinished, which discards it.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Phase {
+    /// Reading the chain JSON, or copying a loaded chain.
+    Parse,
+    /// Expiry filters, filling in greeks and the per-strike checks.
+    Filter,
+    /// Pairing strikes and building spreads.
+    Pair,
+    /// Sorting, the result filters, ranking and paging.
+    Sort,
+    /// Encoding the results for JS.
+    Serialize,
+}
+
+/// Timings in microseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ScanStats {
+    /// Export that ran the scan, e.g. `bear_call_spread`.
+    scan: String,
+    parse_us: f64,
+    filter_us: f64,
+    pair_us: f64,
+    sort_us: f64,
+    serialize_us: f64,
+    total_us: f64,
+    /// Chain rows scanned.
+    instruments: usize,
+    /// Underlying and expiry combinations.
+    slices: usize,
+    /// Strikes left after the per-strike checks.
+    strikes: usize,
+    /// Spreads built before sorting and the result filters.
+    candidates: usize,
+    results: usize,
+    #[serde(skip)]
+    started_us: f64,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<ScanStats>> = const { RefCell::new(None) };
+    static LAST: RefCell<Option<ScanStats>> = const { RefCell::new(None) };
+}
+
+/// Microseconds on a monotonic clock.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+fn now_us() -> f64 {
+    use wasm_bindgen::{JsCast, JsValue};
+    // performance is on the global object in windows and workers alike
+    let performance = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"));
+    let now = performance.as_ref().ok().and_then(|performance| {
+        js_sys::Reflect::get(performance, &JsValue::from_str("now"))
+            .ok()?
+            .dyn_into::<js_sys::Function>()
+            .ok()?
+            .call0(performance)
+            .ok()?
+            .as_f64()
+    });
+    now.unwrap_or_else(js_sys::Date::now) * 1000.0
+}
+
+/// Microseconds on a monotonic clock.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn now_us() -> f64 {
+    static EPOCH: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    EPOCH
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_secs_f64()
+        * 1e6
+}
+
+fn with_current(f: impl FnOnce(&mut ScanStats)) {
+    CURRENT.with_borrow_mut(|current| {
+        if let Some(stats) = current.as_mut() {
+            f(stats);
+        }
+    });
+}
+
+/// The open record; dropped before `finish`, as on an export's early error
+/// return, it discards the record so later calls aren't counted into it.
+#[must_use]
+pub struct Record(());
+
+impl Drop for Record {
+    fn drop(&mut self) {
+        CURRENT.set(None);
+    }
+}
+
+/// Opens a record for the scan run by `scan`.
+pub fn start(scan: &str) -> Record {
+    CURRENT.set(Some(ScanStats {
+        scan: String::from(scan),
+        started_us: now_us(),
+        ..ScanStats::default()
+    }));
+    Record(())
+}
+
+/// Runs `f`, adding its duration to `phase`.
+pub fn time<R>(phase: Phase, f: impl FnOnce() -> R) -> R {
+    let started = now_us();
+    let result = f();
+    let elapsed = now_us() - started;
+    with_current(|stats| {
+        let total = match phase {
+            Phase::Parse => &mut stats.parse_us,
+            Phase::Filter => &mut stats.filter_us,
+            Phase::Pair => &mut stats.pair_us,
+            Phase::Sort => &mut stats.sort_us,
+            Phase::Serialize => &mut stats.serialize_us,
+        };
+        *total += elapsed;
+    });
+    result
+}
+
+/// Records one slice of `instruments` rows, `strikes` of which passed the
+/// per-strike checks, pairing into `candidates` spreads.
+pub fn count_slice(instruments: usize, strikes: usize, candidates: usize) {
+    with_current(|stats| {
+        stats.slices += 1;
+        stats.instruments += instruments;
+        stats.strikes += strikes;
+        stats.candidates += candidates;
+    });
+}
+
+/// Closes the open record with the number of spreads returned.
+pub fn finish(results: usize) {
+    if let Some(mut stats) = CURRENT.take() {
+        stats.results = results;
+        stats.total_us = now_us() - stats.started_us;
+        LAST.set(Some(stats));
+    }
+}
+
+pub fn last() -> Option<ScanStats> {
+    LAST.with_borrow(Clone::clone)
+}
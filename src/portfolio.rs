@@ -0,0 +1,159 @@
+//! Aggregate view of several open positions against the current chain.
+//!
+//! Greeks and P&L are in rupees, summed across positions. Margin is the
+//! broker-reported figure where a position carries one and an estimate from
+//! the margin module otherwise. Payoff curves are combined per underlying,
+//! since positions on different indices don't share a price axis.
+
+use crate::margin::{self, MarginModel};
+use crate::payoff::{self, PayoffPoint};
+use crate::positions::{self, Leg, Position, PositionGreeks, PriceMode};
+use crate::{chain, dates, greeks};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_RANGE_PERCENTAGE: f64 = 10.0;
+const DEFAULT_STEPS: u32 = 100;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PortfolioParams {
+    positions: Vec<Position>,
+    #[serde(default)]
+    now: Option<f64>,
+    #[serde(default)]
+    price_mode: Option<PriceMode>,
+    #[serde(default)]
+    margin_model: Option<MarginModel>,
+    /// Payoff range as a percent either side of spot; defaults to 10.
+    #[serde(default)]
+    range_percentage: Option<f64>,
+    #[serde(default)]
+    steps: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PositionSummary {
+    id: String,
+    label: Option<String>,
+    underlying_key: Option<String>,
+    pnl: f64,
+    greeks: Option<PositionGreeks>,
+    margin: f64,
+    /// True when `margin` is estimated rather than broker-reported.
+    margin_estimated: bool,
+    missing_legs: Vec<Leg>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct UnderlyingPayoff {
+    spot: f64,
+    /// Combined expiry P&L in rupees.
+    curve: Vec<PayoffPoint>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PortfolioSummary {
+    as_of: String,
+    pnl: f64,
+    /// Sum over positions whose greeks are all available.
+    greeks: PositionGreeks,
+    total_margin: f64,
+    payoff: BTreeMap<String, UnderlyingPayoff>,
+    positions: Vec<PositionSummary>,
+}
+
+pub fn summarize(params: &PortfolioParams, optionchain: &str) -> Result<PortfolioSummary, String> {
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let mut instruments = chain::parse(optionchain)?;
+    greeks::fill_missing(&mut instruments, now_ms);
+    let margin_model = params.margin_model.unwrap_or_default();
+    let price_mode = params.price_mode.unwrap_or_default();
+
+    // Legs per underlying, each scaled to units so different lot sizes add up
+    let mut underlying_legs: BTreeMap<String, (f64, Vec<Leg>)> = BTreeMap::new();
+    let summaries: Vec<PositionSummary> = params
+        .positions
+        .iter()
+        .map(|position| {
+            let (marks, missing) = position.mark(&instruments, price_mode)?;
+            let lot_size = position.lot_size(&instruments)?;
+            let row = position
+                .legs
+                .iter()
+                .find_map(|leg| {
+                    positions::find_leg(leg, position.underlying_key.as_deref(), &instruments)
+                })
+                .map(|(instrument, _)| instrument);
+
+            let estimated = row.map(|row| {
+                margin::for_legs(
+                    &position.legs,
+                    row.underlying_spot_price,
+                    lot_size,
+                    &margin_model,
+                )
+            });
+            if let Some(row) = row {
+                let entry = underlying_legs
+                    .entry(row.underlying_key.clone())
+                    .or_insert_with(|| (row.underlying_spot_price, Vec::new()));
+                entry.1.extend(position.legs.iter().map(|leg| Leg {
+                    lots: leg.lots * lot_size,
+                    ..leg.clone()
+                }));
+            }
+
+            Ok(PositionSummary {
+                id: position.id.clone(),
+                label: position.label.clone(),
+                underlying_key: position
+                    .underlying_key
+                    .clone()
+                    .or_else(|| row.map(|row| row.underlying_key.clone())),
+                pnl: marks.iter().map(|mark| mark.pnl()).sum(),
+                greeks: positions::position_greeks(&marks),
+                margin: position.margin.or(estimated).unwrap_or(0.0),
+                margin_estimated: position.margin.is_none() && estimated.is_some(),
+                missing_legs: missing.into_iter().cloned().collect(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let range = params.range_percentage.unwrap_or(DEFAULT_RANGE_PERCENTAGE) / 100.0;
+    let steps = params.steps.unwrap_or(DEFAULT_STEPS);
+    let payoff = underlying_legs
+        .into_iter()
+        .map(|(underlying_key, (spot, legs))| {
+            let curve = payoff::price_grid(spot * (1.0 - range), spot * (1.0 + range), steps)?
+                .into_iter()
+                .map(|price| PayoffPoint::new(price, payoff::expiry_pnl(&legs, price)))
+                .collect();
+            Ok((underlying_key, UnderlyingPayoff { spot, curve }))
+        })
+        .collect::<Result<BTreeMap<String, UnderlyingPayoff>, String>>()?;
+
+    let greeks = summaries.iter().filter_map(|summary| summary.greeks).fold(
+        PositionGreeks::default(),
+        |total, greeks| PositionGreeks {
+            delta: total.delta + greeks.delta,
+            gamma: total.gamma + greeks.gamma,
+            theta: total.theta + greeks.theta,
+            vega: total.vega + greeks.vega,
+        },
+    );
+
+    Ok(PortfolioSummary {
+        as_of: dates::to_iso(dates::ist_day(now_ms)),
+        pnl: summaries.iter().map(|summary| summary.pnl).sum(),
+        greeks,
+        total_margin: summaries.iter().map(|summary| summary.margin).sum(),
+        payoff,
+        positions: summaries,
+    })
+}
@@ -0,0 +1,331 @@
+//! Open positions and their valuation against a chain.
+//!
+//! A position is a set of option legs entered at known prices. Valuation
+//! looks each leg up in the chain by `instrument_key`, falling back to
+//! strike, option type and expiry, and scales per-unit figures by lots and
+//! lot size.
+
+use crate::greeks::OptionKind;
+use crate::notifications::AlertKind;
+use crate::{chain, dates, lots, Instrument, MarketData, OptionData};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn sign(self) -> f64 {
+        match self {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        }
+    }
+
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+/// Which quote a leg is assumed to fill at.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum PriceMode {
+    #[default]
+    Ltp,
+    /// Midpoint of bid and ask.
+    Mid,
+    /// Sell at the bid and buy at the ask, the price a market order fills at.
+    BidAskConservative,
+}
+
+/// Per-unit price for an order on `side`; `None` if the quote needed by
+/// `mode` is missing.
+pub fn fill_price(market_data: &MarketData, side: Side, mode: PriceMode) -> Option<f64> {
+    let bid = market_data.bid_price.filter(|bid| *bid > 0.0);
+    let ask = market_data.ask_price.filter(|ask| *ask > 0.0);
+    match mode {
+        PriceMode::Ltp => market_data.ltp,
+        PriceMode::Mid => bid.zip(ask).map(|(bid, ask)| 0.5 * (bid + ask)),
+        PriceMode::BidAskConservative => match side {
+            Side::Sell => bid,
+            Side::Buy => ask,
+        },
+    }
+}
+
+fn one_lot() -> f64 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Leg {
+    #[serde(default)]
+    pub instrument_key: Option<String>,
+    pub option_type: OptionKind,
+    pub strike: f64,
+    #[serde(default)]
+    pub expiry: Option<String>,
+    pub side: Side,
+    #[serde(default = "one_lot")]
+    pub lots: f64,
+    /// Entry price per unit.
+    pub price: f64,
+    /// Implied volatility in percent, used to value the leg before expiry.
+    #[serde(default)]
+    pub iv: Option<f64>,
+}
+
+/// A P&L level to watch: targets fire once P&L rises to `level`, stops once
+/// it falls to `level` (normally negative).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct AlertLevel {
+    pub kind: AlertKind,
+    pub level: f64,
+}
+
+impl AlertLevel {
+    pub fn crossed(&self, pnl: f64) -> bool {
+        match self.kind {
+            AlertKind::Target => pnl >= self.level,
+            AlertKind::Stop => pnl <= self.level,
+        }
+    }
+}
+
+/// Figures recorded at the previous close, used to report day-over-day change.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Snapshot {
+    #[serde(default)]
+    pub as_of: Option<f64>,
+    pub pnl: f64,
+    #[serde(default)]
+    pub delta: Option<f64>,
+    #[serde(default)]
+    pub gamma: Option<f64>,
+    #[serde(default)]
+    pub theta: Option<f64>,
+    #[serde(default)]
+    pub vega: Option<f64>,
+    #[serde(default)]
+    pub margin: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Position {
+    pub id: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub underlying_key: Option<String>,
+    #[serde(default)]
+    pub lot_size: Option<f64>,
+    pub legs: Vec<Leg>,
+    /// Broker-reported margin currently blocked for the position.
+    #[serde(default)]
+    pub margin: Option<f64>,
+    #[serde(default)]
+    pub alerts: Vec<AlertLevel>,
+    #[serde(default)]
+    pub previous: Option<Snapshot>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Portfolio {
+    pub positions: Vec<Position>,
+    /// Epoch milliseconds of the valuation; defaults to the current time.
+    #[serde(default)]
+    pub now: Option<f64>,
+}
+
+/// Position greeks: per-unit greeks scaled by signed quantity, so theta is in
+/// rupees per day and vega in rupees per vol point.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PositionGreeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub theta: f64,
+    pub vega: f64,
+}
+
+#[derive(Debug)]
+pub struct LegMark<'a> {
+    pub leg: &'a Leg,
+    pub option: &'a OptionData,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+impl LegMark<'_> {
+    pub fn pnl(&self) -> f64 {
+        (self.price - self.leg.price) * self.quantity
+    }
+}
+
+/// Finds the chain row a leg refers to.
+pub fn find_leg<'a>(
+    leg: &Leg,
+    underlying_key: Option<&str>,
+    instruments: &'a [Instrument],
+) -> Option<(&'a Instrument, &'a OptionData)> {
+    instruments.iter().find_map(|instrument| {
+        let option = chain::option(instrument, leg.option_type)?;
+        let matches = match &leg.instrument_key {
+            Some(key) if !option.instrument_key.is_empty() => option.instrument_key == *key,
+            _ => {
+                instrument.strike_price == leg.strike
+                    && underlying_key.is_none_or(|key| instrument.underlying_key == key)
+                    && leg
+                        .expiry
+                        .as_deref()
+                        .is_none_or(|expiry| dates::same_expiry(&instrument.expiry, expiry))
+            }
+        };
+        matches.then_some((instrument, option))
+    })
+}
+
+impl Position {
+    /// `lot_size`, or the table's size for the underlying, taken from
+    /// `underlying_key` or the chain row of a leg.
+    pub fn lot_size(&self, instruments: &[Instrument]) -> Result<f64, String> {
+        let underlying_key = self.underlying_key.clone().or_else(|| {
+            self.legs
+                .iter()
+                .find_map(|leg| find_leg(leg, None, instruments))
+                .map(|(instrument, _)| instrument.underlying_key.clone())
+        });
+        match underlying_key {
+            Some(underlying_key) => lots::resolve(self.lot_size, &underlying_key),
+            None => self
+                .lot_size
+                .filter(|lot_size| *lot_size > 0.0)
+                .ok_or_else(|| String::from("lot_size or underlying_key is required")),
+        }
+    }
+
+    /// Marks every leg at the price it would close at under `mode`. Legs
+    /// missing from the chain, or without that quote, are returned separately.
+    pub fn mark<'a>(
+        &'a self,
+        instruments: &'a [Instrument],
+        mode: PriceMode,
+    ) -> Result<(Vec<LegMark<'a>>, Vec<&'a Leg>), String> {
+        let lot_size = self.lot_size(instruments)?;
+        let mut marks = Vec::new();
+        let mut missing = Vec::new();
+        for leg in &self.legs {
+            let found = find_leg(leg, self.underlying_key.as_deref(), instruments);
+            let price = found
+                .and_then(|(_, option)| option.market_data.as_ref())
+                .and_then(|market_data| fill_price(market_data, leg.side.opposite(), mode));
+            match (found, price) {
+                (Some((_, option)), Some(price)) => marks.push(LegMark {
+                    leg,
+                    option,
+                    quantity: leg.side.sign() * leg.lots * lot_size,
+                    price,
+                }),
+                _ => missing.push(leg),
+            }
+        }
+        Ok((marks, missing))
+    }
+}
+
+/// Sums quantity-weighted greeks; `None` if any marked leg lacks greeks.
+pub fn position_greeks(marks: &[LegMark]) -> Option<PositionGreeks> {
+    marks
+        .iter()
+        .try_fold(PositionGreeks::default(), |total, mark| {
+            let greeks = mark.option.option_greeks.as_ref()?;
+            Some(PositionGreeks {
+                delta: total.delta + greeks.delta? * mark.quantity,
+                gamma: total.gamma + greeks.gamma? * mark.quantity,
+                theta: total.theta + greeks.theta? * mark.quantity,
+                vega: total.vega + greeks.vega? * mark.quantity,
+            })
+        })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct MarkedLeg {
+    #[serde(flatten)]
+    leg: Leg,
+    mark_price: f64,
+    /// Signed units: lots times lot size, negative for shorts.
+    quantity: f64,
+    pnl: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct MarkedPosition {
+    id: String,
+    label: Option<String>,
+    /// Unrealized P&L of the priced legs.
+    pnl: f64,
+    legs: Vec<MarkedLeg>,
+    missing_legs: Vec<Leg>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Marks {
+    pnl: f64,
+    positions: Vec<MarkedPosition>,
+}
+
+/// Unrealized P&L per leg and per position at the prices the legs would
+/// close at under `mode`.
+pub fn mark_all(
+    positions: &[Position],
+    optionchain: &str,
+    mode: PriceMode,
+) -> Result<Marks, String> {
+    let instruments = chain::parse(optionchain)?;
+    let positions: Vec<MarkedPosition> = positions
+        .iter()
+        .map(|position| {
+            let (marks, missing) = position.mark(&instruments, mode)?;
+            let legs: Vec<MarkedLeg> = marks
+                .iter()
+                .map(|mark| MarkedLeg {
+                    leg: mark.leg.clone(),
+                    mark_price: mark.price,
+                    quantity: mark.quantity,
+                    pnl: mark.pnl(),
+                })
+                .collect();
+            Ok(MarkedPosition {
+                id: position.id.clone(),
+                label: position.label.clone(),
+                pnl: legs.iter().map(|leg| leg.pnl).sum(),
+                legs,
+                missing_legs: missing.into_iter().cloned().collect(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Marks {
+        pnl: positions.iter().map(|position| position.pnl).sum(),
+        positions,
+    })
+}
@@ -0,0 +1,144 @@
+//! Tick updates applied to a stored chain.
+//!
+//! Each update names an option by `instrument_key` and carries only the
+//! fields that changed. An update whose key is an `underlying_key`, such as
+//! `NSE_INDEX|Nifty 50`, moves spot on every row of that underlying instead,
+//! using its `ltp`.
+//!
+//! The rows reported as changed include every row a scan result could
+//! depend on: an OI update marks the whole expiry, since OI walls are drawn
+//! across it, and a spot move marks the whole underlying.
+
+use crate::{Instrument, MarketData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct QuoteUpdate {
+    instrument_key: String,
+    #[serde(default)]
+    ltp: Option<f64>,
+    #[serde(default, alias = "bid")]
+    bid_price: Option<f64>,
+    #[serde(default, alias = "ask")]
+    ask_price: Option<f64>,
+    #[serde(default)]
+    bid_qty: Option<u64>,
+    #[serde(default)]
+    ask_qty: Option<u64>,
+    #[serde(default)]
+    oi: Option<u64>,
+    #[serde(default)]
+    volume: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct UpdateReport {
+    updated: usize,
+    /// Keys that matched neither an option nor an underlying.
+    unknown: Vec<String>,
+}
+
+fn apply_quote(market_data: &mut MarketData, update: &QuoteUpdate) {
+    let fields = [
+        (&mut market_data.ltp, update.ltp),
+        (&mut market_data.bid_price, update.bid_price),
+        (&mut market_data.ask_price, update.ask_price),
+    ];
+    for (field, value) in fields {
+        if value.is_some() {
+            *field = value;
+        }
+    }
+    let counts = [
+        (&mut market_data.bid_qty, update.bid_qty),
+        (&mut market_data.ask_qty, update.ask_qty),
+        (&mut market_data.oi, update.oi),
+        (&mut market_data.volume, update.volume),
+    ];
+    for (field, value) in counts {
+        if value.is_some() {
+            *field = value;
+        }
+    }
+}
+
+/// Applies `updates` in order and returns which keys matched, along with
+/// the indices of the rows that changed.
+pub fn apply(
+    instruments: &mut [Instrument],
+    updates: &[QuoteUpdate],
+) -> (UpdateReport, Vec<usize>) {
+    let mut options: HashMap<String, (usize, bool)> = HashMap::new();
+    for (row, instrument) in instruments.iter().enumerate() {
+        for (option, is_call) in [
+            (&instrument.call_options, true),
+            (&instrument.put_options, false),
+        ] {
+            if let Some(option) = option.as_ref().filter(|o| !o.instrument_key.is_empty()) {
+                options.insert(option.instrument_key.clone(), (row, is_call));
+            }
+        }
+    }
+
+    let mut report = UpdateReport::default();
+    let mut changed = vec![false; instruments.len()];
+    for update in updates {
+        if let Some(&(row, is_call)) = options.get(&update.instrument_key) {
+            changed[row] = true;
+            if update.oi.is_some() {
+                let (underlying, expiry) = (
+                    instruments[row].underlying_key.clone(),
+                    instruments[row].expiry.clone(),
+                );
+                for (flag, instrument) in changed.iter_mut().zip(instruments.iter()) {
+                    *flag |= instrument.underlying_key == underlying && instrument.expiry == expiry;
+                }
+            }
+            let instrument = &mut instruments[row];
+            let option = if is_call {
+                instrument.call_options.as_mut()
+            } else {
+                instrument.put_options.as_mut()
+            };
+            if let Some(option) = option {
+                apply_quote(
+                    option.market_data.get_or_insert(MarketData::default()),
+                    update,
+                );
+            }
+            report.updated += 1;
+            continue;
+        }
+
+        let mut moved = false;
+        if let Some(spot) = update.ltp {
+            for (flag, instrument) in changed
+                .iter_mut()
+                .zip(instruments.iter_mut())
+                .filter(|(_, i)| i.underlying_key == update.instrument_key)
+            {
+                instrument.underlying_spot_price = spot;
+                *flag = true;
+                moved = true;
+            }
+        }
+        if moved {
+            report.updated += 1;
+        } else {
+            report.unknown.push(update.instrument_key.clone());
+        }
+    }
+
+    let rows = changed
+        .iter()
+        .enumerate()
+        .filter(|(_, changed)| **changed)
+        .map(|(row, _)| row)
+        .collect();
+    (report, rows)
+}
@@ -0,0 +1,82 @@
+//! Self-contained scan recordings, for sharing a scenario or attaching one
+//! to a bug report.
+//!
+//! A recording holds the chain as it was scanned, the params after any
+//! `set_defaults` defaults were applied and with the clock pinned, and the
+//! results. Replaying scans the recorded chain with the recorded params and
+//! says whether the results still come out the same.
+
+use crate::spreads::{self, Strategy};
+use crate::{dates, store, BearCallSpreadParams, CreditSpread, Instrument};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const RECORDING_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ScanRecording {
+    /// Layout of the recording; newer ones are refused.
+    version: u32,
+    /// Crate version that made the recording.
+    crate_version: String,
+    /// `bear_call` or `bull_put`.
+    strategy: String,
+    chain: Vec<Instrument>,
+    params: BearCallSpreadParams,
+    results: Vec<CreditSpread>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct Replay {
+    /// Whether the replayed results equal the recorded ones exactly.
+    matches: bool,
+    recorded_count: usize,
+    replayed_count: usize,
+    results: Vec<CreditSpread>,
+}
+
+/// Scans the stored chain `chain_id` and records the chain, `params` and the
+/// results.
+pub fn record(
+    chain_id: u32,
+    strategy: Strategy,
+    mut params: BearCallSpreadParams,
+) -> Result<ScanRecording, String> {
+    let chain = store::get(chain_id)?;
+    params.optionchain = String::new();
+    params.chain_id = None;
+    // Days to expiry must not move between recording and replay
+    params.now = Some(params.now.unwrap_or_else(dates::now_ms));
+    let results = spreads::scan_chain(strategy, &params, chain.clone())?;
+    Ok(ScanRecording {
+        version: RECORDING_VERSION,
+        crate_version: String::from(env!("CARGO_PKG_VERSION")),
+        strategy: String::from(strategy.name()),
+        chain,
+        params,
+        results,
+    })
+}
+
+/// Scans a recording's chain again and compares the results.
+pub fn replay(recording: ScanRecording) -> Result<Replay, String> {
+    if recording.version > RECORDING_VERSION {
+        return Err(format!(
+            "Recording version {} is newer than this build supports",
+            recording.version
+        ));
+    }
+    let strategy =
+        Strategy::from_name(&recording.strategy).ok_or_else(|| String::from("Unknown strategy"))?;
+    let results = spreads::scan_chain(strategy, &recording.params, recording.chain)?;
+    let json = |spreads: &[CreditSpread]| serde_json::to_string(spreads).ok();
+    Ok(Replay {
+        matches: json(&results).is_some() && json(&results) == json(&recording.results),
+        recorded_count: recording.results.len(),
+        replayed_count: results.len(),
+        results,
+    })
+}
@@ -0,0 +1,138 @@
+//! End-of-day summary for tracked positions.
+//!
+//! Each position is marked against the closing chain and compared with the
+//! `previous` snapshot the caller stored from the last report. The report
+//! carries a fresh `snapshot` per position to store for tomorrow.
+
+use crate::notifications::AlertEvent;
+use crate::positions::{self, AlertLevel, Leg, Portfolio, PositionGreeks, PriceMode, Snapshot};
+use crate::{chain, dates, greeks};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct GreeksDrift {
+    delta: Option<f64>,
+    gamma: Option<f64>,
+    theta: Option<f64>,
+    vega: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PositionReport {
+    id: String,
+    label: Option<String>,
+    /// Unrealized P&L against entry prices.
+    pnl: f64,
+    pnl_change: Option<f64>,
+    greeks: Option<PositionGreeks>,
+    greeks_drift: Option<GreeksDrift>,
+    margin: Option<f64>,
+    margin_change: Option<f64>,
+    /// Previous close theta times the days elapsed since it was recorded.
+    decay_captured: Option<f64>,
+    pending_alerts: Vec<AlertLevel>,
+    /// Ready to pass to `notification_payloads`.
+    triggered_alerts: Vec<AlertEvent>,
+    /// Legs that couldn't be found or priced in the closing chain.
+    missing_legs: Vec<Leg>,
+    snapshot: Snapshot,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct DailyReport {
+    as_of: String,
+    total_pnl: f64,
+    total_pnl_change: f64,
+    total_decay_captured: f64,
+    total_margin: f64,
+    pending_alerts: usize,
+    triggered_alerts: usize,
+    positions: Vec<PositionReport>,
+}
+
+fn change(now: Option<f64>, before: Option<f64>) -> Option<f64> {
+    Some(now? - before?)
+}
+
+pub fn build(portfolio: &Portfolio, closing_chain: &str) -> Result<DailyReport, String> {
+    let now_ms = portfolio.now.unwrap_or_else(dates::now_ms);
+    let mut instruments = chain::parse(closing_chain)?;
+    greeks::fill_missing(&mut instruments, now_ms);
+
+    let reports: Vec<PositionReport> = portfolio
+        .positions
+        .iter()
+        .map(|position| {
+            let (marks, missing) = position.mark(&instruments, PriceMode::Ltp)?;
+            let pnl: f64 = marks.iter().map(|mark| mark.pnl()).sum();
+            let greeks = positions::position_greeks(&marks);
+            let previous = position.previous.as_ref();
+
+            let greeks_drift = greeks.zip(previous).map(|(greeks, previous)| GreeksDrift {
+                delta: change(Some(greeks.delta), previous.delta),
+                gamma: change(Some(greeks.gamma), previous.gamma),
+                theta: change(Some(greeks.theta), previous.theta),
+                vega: change(Some(greeks.vega), previous.vega),
+            });
+            let decay_captured = previous.and_then(|previous| {
+                let days = (now_ms - previous.as_of?) / dates::MS_PER_DAY;
+                Some(previous.theta? * days)
+            });
+
+            let (triggered, pending): (Vec<&AlertLevel>, Vec<&AlertLevel>) =
+                position.alerts.iter().partition(|alert| alert.crossed(pnl));
+
+            Ok(PositionReport {
+                id: position.id.clone(),
+                label: position.label.clone(),
+                pnl,
+                pnl_change: previous.map(|previous| pnl - previous.pnl),
+                greeks,
+                greeks_drift,
+                margin: position.margin,
+                margin_change: change(position.margin, previous.and_then(|p| p.margin)),
+                decay_captured,
+                pending_alerts: pending.into_iter().cloned().collect(),
+                triggered_alerts: triggered
+                    .into_iter()
+                    .map(|alert| {
+                        AlertEvent::new(
+                            position.id.clone(),
+                            alert.kind,
+                            alert.level,
+                            pnl,
+                            position.label.clone(),
+                            Some(now_ms),
+                        )
+                    })
+                    .collect(),
+                missing_legs: missing.into_iter().cloned().collect(),
+                snapshot: Snapshot {
+                    as_of: Some(now_ms),
+                    pnl,
+                    delta: greeks.map(|g| g.delta),
+                    gamma: greeks.map(|g| g.gamma),
+                    theta: greeks.map(|g| g.theta),
+                    vega: greeks.map(|g| g.vega),
+                    margin: position.margin,
+                },
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(DailyReport {
+        as_of: dates::to_iso(dates::ist_day(now_ms)),
+        total_pnl: reports.iter().map(|r| r.pnl).sum(),
+        total_pnl_change: reports.iter().filter_map(|r| r.pnl_change).sum(),
+        total_decay_captured: reports.iter().filter_map(|r| r.decay_captured).sum(),
+        total_margin: reports.iter().filter_map(|r| r.margin).sum(),
+        pending_alerts: reports.iter().map(|r| r.pending_alerts.len()).sum(),
+        triggered_alerts: reports.iter().map(|r| r.triggered_alerts.len()).sum(),
+        positions: reports,
+    })
+}
@@ -0,0 +1,139 @@
+//! Resampling of a chain onto a uniform strike grid.
+//!
+//! Grid strikes that already exist are passed through untouched. Missing grid
+//! strikes are linearly interpolated from the nearest listed strikes on either
+//! side; they carry an empty `instrument_key` since there is no tradable
+//! contract behind them. The grid never extrapolates past the listed strikes.
+
+use crate::{chain, Instrument, MarketData, OptionData, OptionGreeks};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_STEP: f64 = 50.0;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ResampleParams {
+    optionchain: String,
+    #[serde(default)]
+    step: Option<f64>,
+}
+
+fn lerp(low: Option<f64>, high: Option<f64>, weight: f64) -> Option<f64> {
+    match (low, high) {
+        (Some(low), Some(high)) => Some(low + (high - low) * weight),
+        _ => None,
+    }
+}
+
+fn lerp_u64(low: Option<u64>, high: Option<u64>, weight: f64) -> Option<u64> {
+    lerp(low.map(|v| v as f64), high.map(|v| v as f64), weight).map(|v| v.round() as u64)
+}
+
+fn interpolate_market_data(low: &MarketData, high: &MarketData, weight: f64) -> MarketData {
+    MarketData {
+        ltp: lerp(low.ltp, high.ltp, weight),
+        volume: lerp_u64(low.volume, high.volume, weight),
+        oi: lerp_u64(low.oi, high.oi, weight),
+        close_price: lerp(low.close_price, high.close_price, weight),
+        bid_price: lerp(low.bid_price, high.bid_price, weight),
+        bid_qty: lerp_u64(low.bid_qty, high.bid_qty, weight),
+        ask_price: lerp(low.ask_price, high.ask_price, weight),
+        ask_qty: lerp_u64(low.ask_qty, high.ask_qty, weight),
+        prev_oi: lerp_u64(low.prev_oi, high.prev_oi, weight),
+    }
+}
+
+fn interpolate_greeks(low: &OptionGreeks, high: &OptionGreeks, weight: f64) -> OptionGreeks {
+    OptionGreeks {
+        vega: lerp(low.vega, high.vega, weight),
+        theta: lerp(low.theta, high.theta, weight),
+        gamma: lerp(low.gamma, high.gamma, weight),
+        delta: lerp(low.delta, high.delta, weight),
+        iv: lerp(low.iv, high.iv, weight),
+        computed: low.computed || high.computed,
+        mismatch: low.mismatch || high.mismatch,
+    }
+}
+
+fn interpolate_option(
+    low: Option<&OptionData>,
+    high: Option<&OptionData>,
+    weight: f64,
+) -> Option<OptionData> {
+    let (low, high) = (low?, high?);
+    Some(OptionData {
+        instrument_key: String::new(),
+        market_data: match (&low.market_data, &high.market_data) {
+            (Some(l), Some(h)) => Some(interpolate_market_data(l, h, weight)),
+            _ => None,
+        },
+        option_greeks: match (&low.option_greeks, &high.option_greeks) {
+            (Some(l), Some(h)) => Some(interpolate_greeks(l, h, weight)),
+            _ => None,
+        },
+    })
+}
+
+fn resample_group(mut rows: Vec<Instrument>, step: f64) -> Vec<Instrument> {
+    rows.sort_by(|a, b| {
+        a.strike_price
+            .partial_cmp(&b.strike_price)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let (Some(first), Some(last)) = (rows.first(), rows.last()) else {
+        return Vec::new();
+    };
+
+    let first_grid = (first.strike_price / step).ceil() as i64;
+    let last_grid = (last.strike_price / step).floor() as i64;
+
+    (first_grid..=last_grid)
+        .filter_map(|index| {
+            let strike = index as f64 * step;
+            let upper = rows.partition_point(|row| row.strike_price < strike);
+            let high = rows.get(upper)?;
+            if high.strike_price == strike {
+                return Some(high.clone());
+            }
+            let low = &rows[upper.checked_sub(1)?];
+            let weight = (strike - low.strike_price) / (high.strike_price - low.strike_price);
+
+            Some(Instrument {
+                expiry: low.expiry.clone(),
+                strike_price: strike,
+                underlying_key: low.underlying_key.clone(),
+                underlying_spot_price: low.underlying_spot_price,
+                call_options: interpolate_option(
+                    low.call_options.as_ref(),
+                    high.call_options.as_ref(),
+                    weight,
+                ),
+                put_options: interpolate_option(
+                    low.put_options.as_ref(),
+                    high.put_options.as_ref(),
+                    weight,
+                ),
+                exercise_style: low.exercise_style,
+            })
+        })
+        .collect()
+}
+
+/// Resamples every (underlying, expiry) slice of the chain onto multiples of `step`.
+pub fn resample(params: &ResampleParams) -> Result<Vec<Instrument>, String> {
+    let step = params.step.unwrap_or(DEFAULT_STEP);
+    if step.is_nan() || step <= 0.0 {
+        return Err(String::from("Strike step must be positive"));
+    }
+
+    let groups = chain::group_by(chain::parse(&params.optionchain)?, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    });
+
+    Ok(groups
+        .into_values()
+        .flat_map(|rows| resample_group(rows, step))
+        .collect())
+}
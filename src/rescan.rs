@@ -0,0 +1,166 @@
+//! Incremental rescans of a stored chain.
+//!
+//! The first rescan of a chain for a strategy, or one with different
+//! parameters, scans everything. Later ones rebuild only the spreads with a
+//! leg on a strike changed by `update_quotes` since the previous rescan,
+//! keep the rest from the cache, and rerun sorting, the result filters,
+//! ranking and paging over the merged set. The caller gets what changed in
+//! the final results, keyed by underlying, expiry and strikes. Untouched
+//! spreads keep the figures from when they were built, including anything
+//! that depends on the clock, like `dte` and theta.
+
+use crate::chain::{self, StrikeKey};
+use crate::spreads::{self, Strategy};
+use crate::{store, BearCallSpreadParams, CreditSpread};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+pub struct CachedScan {
+    /// Parameters as JSON, to spot a changed scan.
+    params: String,
+    version: u64,
+    /// Spreads before sorting and the result filters.
+    candidates: Vec<CreditSpread>,
+    results: Vec<CreditSpread>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SpreadKey {
+    underlying_key: String,
+    expiry: String,
+    sell_strike: u64,
+    buy_strike: u64,
+}
+
+impl SpreadKey {
+    fn of(spread: &CreditSpread) -> SpreadKey {
+        SpreadKey {
+            underlying_key: spread.underlying_key.clone(),
+            expiry: spread.expiry.clone(),
+            sell_strike: spread.sell_strike.to_bits(),
+            buy_strike: spread.buy_strike.to_bits(),
+        }
+    }
+
+    fn touches(&self, touched: &HashSet<StrikeKey>) -> bool {
+        [self.sell_strike, self.buy_strike]
+            .into_iter()
+            .any(|strike| {
+                touched.contains(&(self.underlying_key.clone(), self.expiry.clone(), strike))
+            })
+    }
+}
+
+/// Serialized with plain strikes rather than the bit patterns used for hashing.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RemovedSpread {
+    underlying_key: String,
+    expiry: String,
+    sell_strike: f64,
+    buy_strike: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ScanDelta {
+    /// Set when everything was rescanned; `added` then holds every result.
+    full: bool,
+    added: Vec<CreditSpread>,
+    changed: Vec<CreditSpread>,
+    removed: Vec<RemovedSpread>,
+}
+
+fn delta(previous: &[CreditSpread], results: &[CreditSpread], full: bool) -> ScanDelta {
+    let before: HashMap<SpreadKey, &CreditSpread> = previous
+        .iter()
+        .map(|spread| (SpreadKey::of(spread), spread))
+        .collect();
+    let after: HashSet<SpreadKey> = results.iter().map(SpreadKey::of).collect();
+    // Spreads have no equality of their own; compare what the caller would see
+    let as_json = |spread: &CreditSpread| serde_json::to_value(spread).ok();
+
+    let mut delta = ScanDelta {
+        full,
+        ..ScanDelta::default()
+    };
+    for spread in results {
+        match before.get(&SpreadKey::of(spread)) {
+            None => delta.added.push(spread.clone()),
+            Some(old) if as_json(old) != as_json(spread) => delta.changed.push(spread.clone()),
+            Some(_) => {}
+        }
+    }
+    delta.removed = previous
+        .iter()
+        .filter(|spread| !after.contains(&SpreadKey::of(spread)))
+        .map(|spread| RemovedSpread {
+            underlying_key: spread.underlying_key.clone(),
+            expiry: spread.expiry.clone(),
+            sell_strike: spread.sell_strike,
+            buy_strike: spread.buy_strike,
+        })
+        .collect();
+    delta
+}
+
+pub fn rescan(
+    chain_id: u32,
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+) -> Result<ScanDelta, String> {
+    let params_json = serde_json::to_string(params)
+        .map_err(|_| String::from("Failed to serialize parameters"))?;
+
+    store::with_chain_mut(chain_id, |stored| {
+        let version = stored.version();
+        let cached = stored
+            .scans
+            .remove(&strategy)
+            .filter(|cached| cached.params == params_json);
+
+        let (candidates, previous, full) = match cached {
+            Some(cached) => {
+                let touched: HashSet<StrikeKey> = stored
+                    .changed_since(cached.version)
+                    .map(chain::strike_key)
+                    .collect();
+                let mut candidates: Vec<CreditSpread> = cached
+                    .candidates
+                    .into_iter()
+                    .filter(|spread| !SpreadKey::of(spread).touches(&touched))
+                    .collect();
+                if !touched.is_empty() {
+                    candidates.extend(spreads::candidates(
+                        strategy,
+                        params,
+                        stored.sorted(strategy),
+                        Some(&touched),
+                    )?);
+                }
+                (candidates, cached.results, false)
+            }
+            None => {
+                let candidates =
+                    spreads::candidates(strategy, params, stored.sorted(strategy), None)?;
+                (candidates, Vec::new(), true)
+            }
+        };
+
+        let results = spreads::finish(params, candidates.clone());
+        let delta = delta(&previous, &results, full);
+        stored.scans.insert(
+            strategy,
+            CachedScan {
+                params: params_json,
+                version,
+                candidates,
+                results,
+            },
+        );
+        Ok(delta)
+    })?
+}
@@ -0,0 +1,211 @@
+//! Roll candidates for a threatened short leg.
+//!
+//! A roll buys back the short leg and sells the same option type further out
+//! of the money, further out in time, or both. Candidates are every strike
+//! from the current one up to `max_strike_steps` listed strikes away from
+//! spot, in the current expiry and the next `max_expiries` ones. Breakevens
+//! treat every leg as settling at the same underlying price, which is exact
+//! when all legs share an expiry and an approximation for calendars.
+
+use crate::greeks::OptionKind;
+use crate::positions::{self, Leg, Position, PriceMode, Side};
+use crate::{chain, dates, greeks, payoff, Instrument};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_MAX_STRIKE_STEPS: usize = 3;
+const DEFAULT_MAX_EXPIRIES: usize = 2;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RollParams {
+    /// Index into `position.legs` of the short leg to roll. Defaults to the
+    /// short leg closest to, or deepest, in the money.
+    #[serde(default)]
+    leg_index: Option<usize>,
+    #[serde(default)]
+    price_mode: Option<PriceMode>,
+    #[serde(default)]
+    max_strike_steps: Option<usize>,
+    /// Later expiries to consider besides the leg's own.
+    #[serde(default)]
+    max_expiries: Option<usize>,
+    #[serde(default)]
+    now: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RollCandidate {
+    expiry: String,
+    strike: f64,
+    dte: Option<i64>,
+    /// Per-unit price paid to close the current short.
+    buyback_price: f64,
+    /// Per-unit price received for the new short.
+    new_price: f64,
+    /// Rupees received for the roll; negative for a debit.
+    roll_credit: f64,
+    new_short_delta: Option<f64>,
+    /// Expiry breakevens of the rolled position, including P&L realized on
+    /// the leg that was closed.
+    breakevens: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RollSuggestions {
+    leg_index: usize,
+    leg: Leg,
+    spot: f64,
+    /// Best credit first.
+    candidates: Vec<RollCandidate>,
+}
+
+// How far in the money a short leg is; negative when out of the money
+fn threat(leg: &Leg, spot: f64) -> f64 {
+    match leg.option_type {
+        OptionKind::Call => spot - leg.strike,
+        OptionKind::Put => leg.strike - spot,
+    }
+}
+
+fn further_otm(kind: OptionKind, strike: f64, from: f64) -> bool {
+    match kind {
+        OptionKind::Call => strike >= from,
+        OptionKind::Put => strike <= from,
+    }
+}
+
+pub fn suggest(
+    position: &Position,
+    optionchain: &str,
+    params: &RollParams,
+) -> Result<RollSuggestions, String> {
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    let mode = params.price_mode.unwrap_or_default();
+    let mut instruments = chain::parse(optionchain)?;
+    greeks::fill_missing(&mut instruments, now_ms);
+
+    let underlying_key = position.underlying_key.as_deref();
+    let leg_row = |leg: &Leg| positions::find_leg(leg, underlying_key, &instruments);
+    let spot = position
+        .legs
+        .iter()
+        .find_map(&leg_row)
+        .map(|(instrument, _)| instrument.underlying_spot_price)
+        .ok_or_else(|| String::from("No position leg found in the chain"))?;
+
+    let leg_index = match params.leg_index {
+        Some(index) => index,
+        None => position
+            .legs
+            .iter()
+            .enumerate()
+            .filter(|(_, leg)| leg.side == Side::Sell)
+            .max_by(|(_, a), (_, b)| threat(a, spot).total_cmp(&threat(b, spot)))
+            .map(|(index, _)| index)
+            .ok_or_else(|| String::from("Position has no short leg"))?,
+    };
+    let short = position
+        .legs
+        .get(leg_index)
+        .filter(|leg| leg.side == Side::Sell)
+        .ok_or_else(|| String::from("Leg to roll must be a short leg"))?;
+    let (current_row, current) =
+        leg_row(short).ok_or_else(|| String::from("Short leg not found in the chain"))?;
+    let buyback_price = current
+        .market_data
+        .as_ref()
+        .and_then(|market_data| positions::fill_price(market_data, Side::Buy, mode))
+        .ok_or_else(|| String::from("Short leg has no price to buy back at"))?;
+
+    let lot_size = position.lot_size(&instruments)?;
+    let realized = (short.price - buyback_price) * short.lots;
+
+    let mut expiries: Vec<&str> = instruments
+        .iter()
+        .filter(|i| i.underlying_key == current_row.underlying_key)
+        .map(|i| i.expiry.as_str())
+        .filter(|expiry| *expiry >= current_row.expiry.as_str())
+        .collect();
+    expiries.sort();
+    expiries.dedup();
+    expiries.truncate(1 + params.max_expiries.unwrap_or(DEFAULT_MAX_EXPIRIES));
+
+    let max_steps = params.max_strike_steps.unwrap_or(DEFAULT_MAX_STRIKE_STEPS);
+    let mut candidates: Vec<RollCandidate> = Vec::new();
+    for expiry in expiries {
+        let mut rows: Vec<&Instrument> = instruments
+            .iter()
+            .filter(|i| {
+                i.underlying_key == current_row.underlying_key
+                    && i.expiry == expiry
+                    && further_otm(short.option_type, i.strike_price, short.strike)
+            })
+            .collect();
+        // Nearest to the current strike first
+        rows.sort_by(|a, b| {
+            (a.strike_price - short.strike)
+                .abs()
+                .total_cmp(&(b.strike_price - short.strike).abs())
+        });
+
+        for row in rows.into_iter().take(max_steps + 1) {
+            if expiry == current_row.expiry && row.strike_price == short.strike {
+                continue;
+            }
+            let Some(option) = chain::option(row, short.option_type) else {
+                continue;
+            };
+            let Some(new_price) = option
+                .market_data
+                .as_ref()
+                .and_then(|market_data| positions::fill_price(market_data, Side::Sell, mode))
+            else {
+                continue;
+            };
+
+            let new_short = Leg {
+                instrument_key: Some(option.instrument_key.clone()).filter(|k| !k.is_empty()),
+                strike: row.strike_price,
+                expiry: Some(row.expiry.clone()),
+                price: new_price,
+                iv: option.option_greeks.as_ref().and_then(|g| g.iv),
+                ..short.clone()
+            };
+            let rolled: Vec<Leg> = position
+                .legs
+                .iter()
+                .enumerate()
+                .map(|(index, leg)| {
+                    if index == leg_index {
+                        new_short.clone()
+                    } else {
+                        leg.clone()
+                    }
+                })
+                .collect();
+
+            candidates.push(RollCandidate {
+                expiry: row.expiry.clone(),
+                strike: row.strike_price,
+                dte: dates::days_to_expiry(&row.expiry, now_ms),
+                buyback_price,
+                new_price,
+                roll_credit: (new_price - buyback_price) * short.lots * lot_size,
+                new_short_delta: option.option_greeks.as_ref().and_then(|g| g.delta),
+                breakevens: payoff::breakevens(&rolled, realized),
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.roll_credit.total_cmp(&a.roll_credit));
+    Ok(RollSuggestions {
+        leg_index,
+        leg: short.clone(),
+        spot,
+        candidates,
+    })
+}
@@ -0,0 +1,521 @@
+//! `ScanParams`, a JS class for building scan parameters one setter at a time.
+//!
+//! Each setter checks its value and returns an updated copy, so calls chain:
+//! `new ScanParams(chain).bidAskSpread(true).lotSize(65).limit(50)`. A bad
+//! value throws with the reason instead of being ignored by the scanner, and
+//! the object the setter was called on is left as it was, so a caller that
+//! catches the error can keep using it. The finished object scans directly,
+//! or converts with `toObject` into the plain object the other exports take.
+
+use crate::bindings::scan_params_json;
+use crate::spreads::Strategy;
+use crate::{defaults, BearCallSpreadParams};
+use serde::de::DeserializeOwned;
+use serde_wasm_bindgen::from_value;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct ScanParams {
+    params: BearCallSpreadParams,
+}
+
+fn check(ok: bool, message: &str) -> Result<(), JsValue> {
+    if ok {
+        Ok(())
+    } else {
+        Err(JsValue::from_str(message))
+    }
+}
+
+fn non_negative(value: f64, name: &str) -> Result<f64, JsValue> {
+    check(
+        value.is_finite() && value >= 0.0,
+        &format!("{} must be zero or more", name),
+    )?;
+    Ok(value)
+}
+
+fn ordered(min: Option<f64>, max: Option<f64>, name: &str) -> Result<(), JsValue> {
+    check(
+        min.zip(max).is_none_or(|(min, max)| min <= max),
+        &format!("min_{} is above max_{}", name, name),
+    )
+}
+
+// Enums are named as in the plain-object params, e.g. "breakeven_pct"
+fn variant<T: DeserializeOwned>(value: &str, name: &str) -> Result<T, JsValue> {
+    serde_json::from_value(serde_json::Value::from(value))
+        .map_err(|_| JsValue::from_str(&format!("Unknown {}: {}", name, value)))
+}
+
+fn object<T: DeserializeOwned>(value: JsValue, name: &str) -> Result<T, JsValue> {
+    from_value(value).map_err(|_| JsValue::from_str(&format!("Failed to parse {}", name)))
+}
+
+impl ScanParams {
+    fn with(&self, update: impl FnOnce(&mut BearCallSpreadParams)) -> ScanParams {
+        let mut params = self.params.clone();
+        update(&mut params);
+        ScanParams { params }
+    }
+
+    /// Applies `update` to a copy, so a rejected value leaves `self` as it was.
+    fn try_with(
+        &self,
+        update: impl FnOnce(&mut BearCallSpreadParams) -> Result<(), JsValue>,
+    ) -> Result<ScanParams, JsValue> {
+        let mut params = self.params.clone();
+        update(&mut params)?;
+        Ok(ScanParams { params })
+    }
+}
+
+#[wasm_bindgen]
+impl ScanParams {
+    /// Starts from the defaults of the plain-object params, with no filters
+    /// on, and any `set_defaults` defaults on top. `optionchain` is the JSON
+    /// chain; leave it out with `chainId`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(optionchain: Option<String>) -> ScanParams {
+        ScanParams {
+            params: BearCallSpreadParams {
+                optionchain: optionchain.unwrap_or_default(),
+                ..defaults::params()
+            },
+        }
+    }
+
+    #[wasm_bindgen(js_name = chainId)]
+    pub fn chain_id(&self, chain_id: u32) -> ScanParams {
+        self.with(|params| params.chain_id = Some(chain_id))
+    }
+
+    #[wasm_bindgen(js_name = bidAskSpread)]
+    pub fn bid_ask_spread(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.bid_ask_spread = enabled)
+    }
+
+    /// Keeps spreads whose max loss is at most `ratio` times their max profit.
+    #[wasm_bindgen(js_name = maxRiskReward)]
+    pub fn max_risk_reward(&self, ratio: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                ratio.is_finite() && ratio > 0.0,
+                "max_risk_reward must be above zero",
+            )?;
+            params.max_risk_reward = Some(ratio);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = lotSize)]
+    pub fn lot_size(&self, lot_size: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                lot_size.is_finite() && lot_size > 0.0,
+                "lot_size must be above zero",
+            )?;
+            params.lot_size = Some(lot_size);
+            Ok(())
+        })
+    }
+
+    pub fn expiry(&self, expiry: String) -> ScanParams {
+        self.with(|params| params.expiry = Some(expiry))
+    }
+
+    /// `"weekly"` or `"monthly"`.
+    #[wasm_bindgen(js_name = expiryCycle)]
+    pub fn expiry_cycle(&self, cycle: &str) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.expiry_cycle = Some(variant(cycle, "expiry_cycle")?);
+            Ok(())
+        })
+    }
+
+    pub fn holidays(&self, holidays: Vec<String>) -> ScanParams {
+        self.with(|params| params.holidays = Some(holidays))
+    }
+
+    /// `"calendar"` or `"trading"`.
+    #[wasm_bindgen(js_name = dayCount)]
+    pub fn day_count(&self, day_count: &str) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.day_count = Some(variant(day_count, "day_count")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = groupByUnderlying)]
+    pub fn group_by_underlying(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.group_by_underlying = enabled)
+    }
+
+    #[wasm_bindgen(js_name = groupByExpiry)]
+    pub fn group_by_expiry(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.group_by_expiry = enabled)
+    }
+
+    #[wasm_bindgen(js_name = futuresMarginPercentage)]
+    pub fn futures_margin_percentage(&self, pct: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.futures_margin_percentage =
+                Some(non_negative(pct, "futures_margin_percentage")?);
+            Ok(())
+        })
+    }
+
+    pub fn futures(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "FuturesQuote[]")] quotes: JsValue,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.futures = Some(object(quotes, "futures")?);
+            Ok(())
+        })
+    }
+
+    /// Epoch milliseconds to measure days to expiry from.
+    pub fn now(&self, now: f64) -> ScanParams {
+        self.with(|params| params.now = Some(now))
+    }
+
+    /// Hours left to expiry, for expiry-day scans.
+    #[wasm_bindgen(js_name = timeToExpiryHours)]
+    pub fn time_to_expiry_hours(&self, hours: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                hours.is_finite() && hours > 0.0,
+                "time_to_expiry_hours must be above zero",
+            )?;
+            params.time_to_expiry_hours = Some(hours);
+            Ok(())
+        })
+    }
+
+    /// Annualized, as a decimal: 0.065 for 6.5%.
+    #[wasm_bindgen(js_name = riskFreeRate)]
+    pub fn risk_free_rate(&self, rate: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(rate.is_finite(), "risk_free_rate must be a number")?;
+            params.risk_free_rate = Some(rate);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = dividendYield)]
+    pub fn dividend_yield(&self, yield_: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(yield_.is_finite(), "dividend_yield must be a number")?;
+            params.dividend_yield = Some(yield_);
+            Ok(())
+        })
+    }
+
+    /// `"european"` or `"american"`.
+    #[wasm_bindgen(js_name = exerciseStyle)]
+    pub fn exercise_style(&self, style: &str) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.exercise_style = Some(variant(style, "exercise_style")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = rejectInvalidExpiry)]
+    pub fn reject_invalid_expiry(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.reject_invalid_expiry = enabled)
+    }
+
+    pub fn lenient(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.lenient = enabled)
+    }
+
+    #[wasm_bindgen(js_name = excludeStale)]
+    pub fn exclude_stale(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.exclude_stale = enabled)
+    }
+
+    /// Pass `{}` for the default tolerances.
+    #[wasm_bindgen(js_name = greeksCheck)]
+    pub fn greeks_check(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "GreeksCheck")] check: JsValue,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.greeks_check = Some(object(check, "greeks_check")?);
+            Ok(())
+        })
+    }
+
+    /// `"delta"` or `"lognormal"`.
+    #[wasm_bindgen(js_name = popModel)]
+    pub fn pop_model(&self, model: &str) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.pop_model = Some(variant(model, "pop_model")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minPop)]
+    pub fn min_pop(&self, min_pop: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                (0.0..=1.0).contains(&min_pop),
+                "min_pop must be between 0 and 1",
+            )?;
+            params.min_pop = Some(min_pop);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minShortDelta)]
+    pub fn min_short_delta(&self, delta: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                (0.0..=1.0).contains(&delta),
+                "min_short_delta must be between 0 and 1",
+            )?;
+            ordered(Some(delta), params.max_short_delta, "short_delta")?;
+            params.min_short_delta = Some(delta);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = maxShortDelta)]
+    pub fn max_short_delta(&self, delta: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                (0.0..=1.0).contains(&delta),
+                "max_short_delta must be between 0 and 1",
+            )?;
+            ordered(params.min_short_delta, Some(delta), "short_delta")?;
+            params.max_short_delta = Some(delta);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minOtmPct)]
+    pub fn min_otm_pct(&self, pct: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let pct = non_negative(pct, "min_otm_pct")?;
+            ordered(Some(pct), params.max_otm_pct, "otm_pct")?;
+            params.min_otm_pct = Some(pct);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = maxOtmPct)]
+    pub fn max_otm_pct(&self, pct: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let pct = non_negative(pct, "max_otm_pct")?;
+            ordered(params.min_otm_pct, Some(pct), "otm_pct")?;
+            params.max_otm_pct = Some(pct);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = strikeStepOnly)]
+    pub fn strike_step_only(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.strike_step_only = enabled)
+    }
+
+    /// `"otm"`, `"atm"` or `"itm"`, with `itm_strikes` (default 1) for
+    /// `"itm"`.
+    pub fn moneyness(
+        &self,
+        moneyness: &str,
+        itm_strikes: Option<u32>,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.moneyness = Some(variant(moneyness, "moneyness")?);
+            params.itm_strikes = itm_strikes.map(|count| count as usize);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minWidth)]
+    pub fn min_width(&self, width: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let width = non_negative(width, "min_width")?;
+            ordered(Some(width), params.max_width, "width")?;
+            params.min_width = Some(width);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = maxWidth)]
+    pub fn max_width(&self, width: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let width = non_negative(width, "max_width")?;
+            ordered(params.min_width, Some(width), "width")?;
+            params.max_width = Some(width);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minShortStrikeOi)]
+    pub fn min_short_strike_oi(&self, oi: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.min_short_strike_oi = Some(non_negative(oi, "min_short_strike_oi")? as u64);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minOiChange)]
+    pub fn min_oi_change(&self, change: f64) -> ScanParams {
+        self.with(|params| params.min_oi_change = Some(change))
+    }
+
+    /// `"ltp"`, `"mid"` or `"bid_ask_conservative"`.
+    #[wasm_bindgen(js_name = priceMode)]
+    pub fn price_mode(&self, mode: &str) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.price_mode = Some(variant(mode, "price_mode")?);
+            Ok(())
+        })
+    }
+
+    /// Per-unit slippage on each leg, in `"rupees"` (the default) or `"ticks"`.
+    pub fn slippage(&self, slippage: f64, unit: Option<String>) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let slippage = non_negative(slippage, "slippage")?;
+            params.slippage_unit = unit
+                .map(|unit| variant(&unit, "slippage_unit"))
+                .transpose()?;
+            params.slippage = Some(slippage);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = brokerageProfile)]
+    pub fn brokerage_profile(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "BrokerageProfile")] profile: JsValue,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.brokerage_profile = Some(object(profile, "brokerage_profile")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = marginModel)]
+    pub fn margin_model(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "MarginModel")] model: JsValue,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.margin_model = Some(object(model, "margin_model")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = stopLossMultiple)]
+    pub fn stop_loss_multiple(&self, multiple: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.stop_loss_multiple = Some(non_negative(multiple, "stop_loss_multiple")?);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = profitTargetPct)]
+    pub fn profit_target_pct(&self, pct: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                (0.0..=100.0).contains(&pct),
+                "profit_target_pct must be between 0 and 100",
+            )?;
+            params.profit_target_pct = Some(pct);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = scoreWeights)]
+    pub fn score_weights(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "ScoreWeights")] weights: JsValue,
+    ) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            params.score_weights = Some(object(weights, "score_weights")?);
+            Ok(())
+        })
+    }
+
+    /// A `sort_by` name such as `"roi"`, and optionally `"ascending"` or `"descending"`.
+    #[wasm_bindgen(js_name = sortBy)]
+    pub fn sort_by(&self, key: &str, order: Option<String>) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            let sort_by = variant(key, "sort_by")?;
+            params.sort_order = order
+                .map(|order| variant(&order, "sort_order"))
+                .transpose()?;
+            params.sort_by = Some(sort_by);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = minLiquidityScore)]
+    pub fn min_liquidity_score(&self, score: f64) -> Result<ScanParams, JsValue> {
+        self.try_with(|params| {
+            check(
+                (0.0..=100.0).contains(&score),
+                "min_liquidity_score must be between 0 and 100",
+            )?;
+            params.min_liquidity_score = Some(score);
+            Ok(())
+        })
+    }
+
+    #[wasm_bindgen(js_name = ivRank)]
+    pub fn iv_rank(&self, iv_rank: f64) -> ScanParams {
+        self.with(|params| params.iv_rank = Some(iv_rank))
+    }
+
+    pub fn offset(&self, offset: u32) -> ScanParams {
+        self.with(|params| params.offset = Some(offset as usize))
+    }
+
+    pub fn limit(&self, limit: u32) -> ScanParams {
+        self.with(|params| params.limit = Some(limit as usize))
+    }
+
+    #[wasm_bindgen(js_name = rawNumbers)]
+    pub fn raw_numbers(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.raw_numbers = enabled)
+    }
+
+    #[wasm_bindgen(js_name = signedBreakeven)]
+    pub fn signed_breakeven(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.signed_breakeven = enabled)
+    }
+
+    #[wasm_bindgen(js_name = withStats)]
+    pub fn with_stats(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.with_stats = enabled)
+    }
+
+    pub fn debug(&self, enabled: bool) -> ScanParams {
+        self.with(|params| params.debug = enabled)
+    }
+
+    /// Same as `bear_call_spread` with these parameters.
+    #[wasm_bindgen(js_name = bearCallSpread, unchecked_return_type = "string")]
+    pub fn bear_call_spread(&self) -> JsValue {
+        scan_params_json(Strategy::BearCall, &self.params)
+    }
+
+    /// Same as `bull_put_spread` with these parameters.
+    #[wasm_bindgen(js_name = bullPutSpread, unchecked_return_type = "string")]
+    pub fn bull_put_spread(&self) -> JsValue {
+        scan_params_json(Strategy::BullPut, &self.params)
+    }
+
+    /// The plain params object, for the packed, MessagePack, streaming and
+    /// rescan exports.
+    #[wasm_bindgen(js_name = toObject, unchecked_return_type = "BearCallSpreadParams")]
+    pub fn to_object(&self) -> Result<JsValue, JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        serde::Serialize::serialize(&self.params, &serializer)
+            .map_err(|_| JsValue::from_str("Failed to serialize parameters"))
+    }
+}
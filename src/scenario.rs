@@ -0,0 +1,229 @@
+//! P&L of a set of legs under spot moves and IV shifts.
+//!
+//! `scenario` fills a matrix of caller-chosen moves; `stress_test` runs a
+//! position through the exchange-style grid of standard spot shocks, each
+//! with and without an IV shock, and reports rupee losses. Each cell revalues
+//! every leg with Black-Scholes, or the binomial tree for `american`
+//! exercise, at the moved spot and the leg's own IV scaled by the shift, so
+//! a -20 shift is a 20% IV crush: 15% IV becomes 12%. P&L is per unit of the
+//! underlying scaled by leg `lots`, as in `payoff`.
+
+use crate::dates;
+use crate::greeks::{ExerciseStyle, Pricing};
+use crate::payoff;
+use crate::positions::{Leg, Position};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+const DEFAULT_SPOT_SHOCKS: [f64; 6] = [-5.0, -2.0, -1.0, 1.0, 2.0, 5.0];
+const DEFAULT_IV_SHOCKS: [f64; 3] = [-20.0, 0.0, 20.0];
+// Lowest IV, as a fraction of the leg's own, a shift can take it to
+const MIN_IV_SCALE: f64 = 0.01;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ScenarioParams {
+    legs: Vec<Leg>,
+    /// Underlying price the moves are taken from.
+    spot: f64,
+    /// Spot moves in percent of `spot`, e.g. `[-2, -1, 0, 1, 2]`.
+    spot_moves: Vec<f64>,
+    /// IV shifts in percent of each leg's IV, e.g. `[-20, 0]`.
+    iv_shifts: Vec<f64>,
+    /// Days after `at` to revalue at, e.g. 1 for the session after an event.
+    #[serde(default)]
+    days_ahead: Option<f64>,
+    /// Epoch milliseconds; defaults to the current time.
+    #[serde(default)]
+    at: Option<f64>,
+    /// Annualized decimals; see `bear_call_spread`.
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    #[serde(default)]
+    exercise_style: Option<ExerciseStyle>,
+}
+
+/// P&L indexed `[iv_shift][spot_move]`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ScenarioMatrix {
+    spot_moves: Vec<f64>,
+    underlying_prices: Vec<f64>,
+    iv_shifts: Vec<f64>,
+    pnl: Vec<Vec<f64>>,
+    /// P&L with spot and IV unchanged at the same date, so the cells can be
+    /// read as changes from it.
+    base_pnl: f64,
+}
+
+/// P&L of `legs` at `at_ms` for every IV shift and underlying price.
+pub fn pnl_grid(
+    legs: &[Leg],
+    underlying_prices: &[f64],
+    iv_shifts: &[f64],
+    at_ms: f64,
+    pricing: Pricing,
+) -> Result<Vec<Vec<f64>>, String> {
+    let inputs = legs
+        .iter()
+        .map(|leg| payoff::leg_inputs(leg, at_ms))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(iv_shifts
+        .iter()
+        .map(|shift| {
+            let scale = (1.0 + shift / 100.0).max(MIN_IV_SCALE);
+            underlying_prices
+                .iter()
+                .map(|&spot| {
+                    legs.iter()
+                        .zip(&inputs)
+                        .map(|(leg, inputs)| {
+                            let inputs = inputs.map(|(t, sigma)| (t, sigma * scale));
+                            payoff::leg_pnl(leg, payoff::leg_value(leg, spot, inputs, pricing))
+                        })
+                        .sum()
+                })
+                .collect()
+        })
+        .collect())
+}
+
+pub fn scenario(params: &ScenarioParams) -> Result<ScenarioMatrix, String> {
+    if !(params.spot.is_finite() && params.spot > 0.0) {
+        return Err(String::from("Spot must be above zero"));
+    }
+    let at_ms = params.at.unwrap_or_else(dates::now_ms)
+        + params.days_ahead.unwrap_or(0.0) * dates::MS_PER_DAY;
+    let pricing = Pricing::new(
+        params.risk_free_rate,
+        params.dividend_yield,
+        params.exercise_style,
+    );
+    let underlying_prices: Vec<f64> = params
+        .spot_moves
+        .iter()
+        .map(|pct| params.spot * (1.0 + pct / 100.0))
+        .collect();
+
+    let pnl = pnl_grid(
+        &params.legs,
+        &underlying_prices,
+        &params.iv_shifts,
+        at_ms,
+        pricing,
+    )?;
+    let base_pnl = pnl_grid(&params.legs, &[params.spot], &[0.0], at_ms, pricing)?[0][0];
+    Ok(ScenarioMatrix {
+        spot_moves: params.spot_moves.clone(),
+        underlying_prices,
+        iv_shifts: params.iv_shifts.clone(),
+        pnl,
+        base_pnl,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StressShocks {
+    /// Underlying price the shocks are taken from.
+    spot: f64,
+    /// Spot shocks in percent; defaults to ±1%, ±2% and ±5%.
+    #[serde(default)]
+    spot_shocks: Option<Vec<f64>>,
+    /// IV shocks in percent of each leg's IV, combined with every spot
+    /// shock; defaults to -20, 0 and +20.
+    #[serde(default)]
+    iv_shocks: Option<Vec<f64>>,
+    /// Epoch milliseconds; defaults to the current time.
+    #[serde(default)]
+    at: Option<f64>,
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    #[serde(default)]
+    exercise_style: Option<ExerciseStyle>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StressCase {
+    spot_shock: f64,
+    iv_shock: f64,
+    underlying_price: f64,
+    /// Rupee change in the position's value from now, all lots included.
+    pnl: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StressTest {
+    position_id: String,
+    lot_size: f64,
+    cases: Vec<StressCase>,
+    /// The case with the lowest `pnl`.
+    worst: Option<StressCase>,
+}
+
+/// The position's rupee P&L change under every spot and IV shock pair,
+/// revalued at the same moment so only the shocks move it. Fails unless the
+/// position's `lot_size` or `underlying_key` gives its lot size.
+pub fn stress_test(position: &Position, shocks: &StressShocks) -> Result<StressTest, String> {
+    if !(shocks.spot.is_finite() && shocks.spot > 0.0) {
+        return Err(String::from("Spot must be above zero"));
+    }
+    let at_ms = shocks.at.unwrap_or_else(dates::now_ms);
+    let pricing = Pricing::new(
+        shocks.risk_free_rate,
+        shocks.dividend_yield,
+        shocks.exercise_style,
+    );
+    let spot_shocks = shocks
+        .spot_shocks
+        .clone()
+        .unwrap_or_else(|| DEFAULT_SPOT_SHOCKS.to_vec());
+    let iv_shocks = shocks
+        .iv_shocks
+        .clone()
+        .unwrap_or_else(|| DEFAULT_IV_SHOCKS.to_vec());
+    let underlying_prices: Vec<f64> = spot_shocks
+        .iter()
+        .map(|pct| shocks.spot * (1.0 + pct / 100.0))
+        .collect();
+
+    // Without a chain, legs can't lead to the underlying
+    let lot_size = position.lot_size(&[])?;
+    let base = pnl_grid(&position.legs, &[shocks.spot], &[0.0], at_ms, pricing)?[0][0];
+    let grid = pnl_grid(
+        &position.legs,
+        &underlying_prices,
+        &iv_shocks,
+        at_ms,
+        pricing,
+    )?;
+
+    let cases: Vec<StressCase> = iv_shocks
+        .iter()
+        .zip(&grid)
+        .flat_map(|(&iv_shock, row)| {
+            spot_shocks.iter().zip(&underlying_prices).zip(row).map(
+                move |((&spot_shock, &underlying_price), &pnl)| StressCase {
+                    spot_shock,
+                    iv_shock,
+                    underlying_price,
+                    pnl: (pnl - base) * lot_size,
+                },
+            )
+        })
+        .collect();
+    let worst = cases.iter().min_by(|a, b| a.pnl.total_cmp(&b.pnl)).cloned();
+    Ok(StressTest {
+        position_id: position.id.clone(),
+        lot_size,
+        cases,
+        worst,
+    })
+}
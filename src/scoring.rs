@@ -0,0 +1,87 @@
+//! Composite ranking of scan results.
+//!
+//! Each metric is min-max normalized across the result set, so the best
+//! spread on a metric scores 1 and the worst 0, then the normalized values
+//! are averaged with the caller's weights. A spread missing a metric scores
+//! 0 on it. Scores are reported from 0 to 100.
+
+use crate::CreditSpread;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(default)]
+pub struct ScoreWeights {
+    /// Max profit over max loss.
+    pub roi: f64,
+    pub pop: f64,
+    /// Liquidity score of the less liquid leg.
+    pub liquidity: f64,
+    /// Breakeven distance from spot, as `breakeven_percentage`.
+    pub breakeven_distance: f64,
+    /// Net theta; credit spreads earn more decay as it rises.
+    pub theta: f64,
+}
+
+fn metrics(spread: &CreditSpread) -> [Option<f64>; 5] {
+    let liquidity = spread
+        .legs
+        .iter()
+        .map(|leg| leg.liquidity())
+        .try_fold(f64::INFINITY, |lowest, score| Some(lowest.min(score?)));
+    [
+        (spread.max_loss > 0.0).then(|| spread.max_profit / spread.max_loss),
+        spread.pop,
+        liquidity,
+        Some(spread.breakeven_percentage),
+        spread.net_theta,
+    ]
+}
+
+/// Sets `score` on every spread and sorts by it, highest first.
+pub fn rank(credit_spreads: &mut [CreditSpread], weights: &ScoreWeights) {
+    let weights = [
+        weights.roi,
+        weights.pop,
+        weights.liquidity,
+        weights.breakeven_distance,
+        weights.theta,
+    ];
+    let total_weight: f64 = weights.iter().sum();
+    if total_weight <= 0.0 {
+        return;
+    }
+
+    let values: Vec<[Option<f64>; 5]> = credit_spreads.iter().map(metrics).collect();
+    let bounds: Vec<(f64, f64)> = (0..weights.len())
+        .map(|i| {
+            values
+                .iter()
+                .filter_map(|metrics| metrics[i])
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), v| {
+                    (low.min(v), high.max(v))
+                })
+        })
+        .collect();
+
+    for (spread, metrics) in credit_spreads.iter_mut().zip(&values) {
+        let weighted: f64 = metrics
+            .iter()
+            .zip(&bounds)
+            .zip(&weights)
+            .map(|((value, (low, high)), weight)| {
+                let normalized = match value {
+                    Some(_) if high <= low => 1.0,
+                    Some(value) => (value - low) / (high - low),
+                    None => 0.0,
+                };
+                normalized * weight
+            })
+            .sum();
+        spread.score = Some(weighted / total_weight * 100.0);
+    }
+
+    credit_spreads.sort_by(|a, b| b.score.unwrap_or(0.0).total_cmp(&a.score.unwrap_or(0.0)));
+}
@@ -0,0 +1,464 @@
+//! Single-option screeners, for naked sellers and directional buyers.
+//!
+//! Both sides run one strike at a time under the same liquidity, staleness,
+//! delta and distance filters as the spread scanners. `short_call` and
+//! `short_put` list out-of-the-money options with the premium, its yield on
+//! the estimated naked margin and the probability of keeping it. A naked
+//! short has no hedge, so every result is flagged `undefined_risk`.
+//!
+//! `long_call` and `long_put` list options at any strike with what a buyer
+//! weighs: the premium at risk, delta bought per rupee, the move needed to
+//! break even and the daily theta burn. Given a `target_price`, each also
+//! carries the return at expiry if the underlying finishes there.
+
+use crate::greeks::{ExerciseStyle, OptionKind, Pricing};
+use crate::positions::{PriceMode, Side};
+use crate::spreads::{self, Direction, LegQuote, PopModel, SpreadWarning, StrategyType};
+use crate::{dates, greeks, lots, margin, store, Instrument};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SingleLegParams {
+    #[serde(default)]
+    optionchain: String,
+    /// Chain loaded with `load_chain`; used instead of `optionchain`.
+    #[serde(default)]
+    chain_id: Option<u32>,
+    #[serde(default)]
+    expiry: Option<String>,
+    /// Epoch milliseconds to measure days to expiry from; defaults to the current time.
+    #[serde(default)]
+    now: Option<f64>,
+    /// Hours left to expiry, used instead of the clock; see `bear_call_spread`.
+    #[serde(default)]
+    time_to_expiry_hours: Option<f64>,
+    /// Pricing inputs; see `bear_call_spread`.
+    #[serde(default)]
+    risk_free_rate: Option<f64>,
+    #[serde(default)]
+    dividend_yield: Option<f64>,
+    #[serde(default)]
+    exercise_style: Option<ExerciseStyle>,
+    #[serde(default)]
+    lot_size: Option<f64>,
+    /// Quote used as the entry price; defaults to LTP.
+    #[serde(default)]
+    price_mode: Option<PriceMode>,
+    /// Drop strikes quoted more than 2 rupees wide.
+    #[serde(default)]
+    bid_ask_spread: bool,
+    /// Drop strikes with no bid/ask or no trades today.
+    #[serde(default)]
+    exclude_stale: bool,
+    /// Bounds on the absolute delta.
+    #[serde(default)]
+    min_delta: Option<f64>,
+    #[serde(default)]
+    max_delta: Option<f64>,
+    /// Bounds on the strike's distance from spot, as a percent of spot.
+    #[serde(default)]
+    min_otm_pct: Option<f64>,
+    #[serde(default)]
+    max_otm_pct: Option<f64>,
+    #[serde(default)]
+    min_oi: Option<u64>,
+    /// Minimum liquidity score, 0 to 100.
+    #[serde(default)]
+    min_liquidity_score: Option<f64>,
+    /// How short screeners estimate `pop`; long screeners always use lognormal.
+    #[serde(default)]
+    pop_model: Option<PopModel>,
+    /// Minimum probability of profit, between 0 and 1.
+    #[serde(default)]
+    min_pop: Option<f64>,
+    /// Short screeners only.
+    #[serde(default)]
+    margin_model: Option<margin::MarginModel>,
+    /// Where the buyer expects the underlying at expiry; long screeners only.
+    #[serde(default)]
+    target_price: Option<f64>,
+    /// Ranking for the long screeners; defaults to delta per rupee.
+    #[serde(default)]
+    sort_by: Option<LongSortBy>,
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum LongSortBy {
+    /// Most delta per rupee first.
+    #[default]
+    DeltaPerRupee,
+    /// Cheapest first.
+    Premium,
+    /// Smallest move to breakeven first.
+    BreakevenMove,
+    /// Slowest theta burn, as a percent of premium, first.
+    ThetaBurn,
+    /// Highest `expected_roi` first.
+    ExpectedRoi,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ShortOption {
+    underlying_key: String,
+    expiry: String,
+    dte: Option<i64>,
+    option_type: OptionKind,
+    strategy_type: StrategyType,
+    direction: Direction,
+    strike: f64,
+    spot: f64,
+    instrument_key: Option<String>,
+    lot_size: f64,
+    /// Entry price per unit.
+    premium: f64,
+    premium_per_lot: f64,
+    ltp: Option<f64>,
+    bid: Option<f64>,
+    ask: Option<f64>,
+    delta: Option<f64>,
+    iv: Option<f64>,
+    oi: Option<u64>,
+    volume: Option<u64>,
+    liquidity: f64,
+    /// Probability of expiring beyond the breakeven, between 0 and 1.
+    pop: Option<f64>,
+    breakeven: f64,
+    breakeven_percentage: f64,
+    /// Naked SPAN plus exposure for one lot.
+    estimated_margin: f64,
+    /// Premium per lot as a percent of `estimated_margin`.
+    premium_yield: Option<f64>,
+    /// `premium_yield` scaled to a 365-day year.
+    annualized_yield: Option<f64>,
+    /// Always set: nothing caps the loss on a naked short.
+    undefined_risk: bool,
+    /// `None` for calls, whose loss has no bound; for puts, the loss per lot
+    /// if the underlying went to zero.
+    max_loss: Option<f64>,
+    warnings: Vec<SpreadWarning>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct LongOption {
+    underlying_key: String,
+    expiry: String,
+    dte: Option<i64>,
+    option_type: OptionKind,
+    strategy_type: StrategyType,
+    direction: Direction,
+    strike: f64,
+    spot: f64,
+    instrument_key: Option<String>,
+    lot_size: f64,
+    /// Entry price per unit.
+    premium: f64,
+    /// Also the most one lot can lose.
+    premium_per_lot: f64,
+    ltp: Option<f64>,
+    bid: Option<f64>,
+    ask: Option<f64>,
+    delta: Option<f64>,
+    iv: Option<f64>,
+    oi: Option<u64>,
+    volume: Option<u64>,
+    liquidity: f64,
+    /// Absolute delta per rupee of premium.
+    delta_per_rupee: Option<f64>,
+    breakeven: f64,
+    /// Move from spot to breakeven at expiry, as a percent of spot; negative
+    /// for puts.
+    breakeven_move_pct: f64,
+    /// Premium lost per day with spot unchanged, per unit.
+    theta_per_day: Option<f64>,
+    /// `theta_per_day` as a percent of premium.
+    theta_burn_pct: Option<f64>,
+    /// Probability of finishing beyond breakeven, between 0 and 1.
+    pop: Option<f64>,
+    /// Percent return at expiry with the underlying at `target_price`.
+    expected_roi: Option<f64>,
+    warnings: Vec<SpreadWarning>,
+}
+
+impl SingleLegParams {
+    fn pricing(&self) -> Pricing {
+        Pricing::new(
+            self.risk_free_rate,
+            self.dividend_yield,
+            self.exercise_style,
+        )
+    }
+}
+
+/// The chain with the expiry filter applied and missing greeks filled in.
+fn prepare(params: &SingleLegParams) -> Result<(Vec<Instrument>, f64), String> {
+    let mut instruments = store::resolve(params.chain_id, &params.optionchain)?;
+    if let Some(expiry) = &params.expiry {
+        instruments.retain(|instrument| dates::same_expiry(&instrument.expiry, expiry));
+    }
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    greeks::fill_missing_at(
+        &mut instruments,
+        now_ms,
+        params.time_to_expiry_hours,
+        params.pricing(),
+    );
+    Ok((instruments, now_ms))
+}
+
+fn passes(params: &SingleLegParams, quote: &LegQuote) -> bool {
+    let delta_ok = (params.min_delta.is_none() && params.max_delta.is_none())
+        || quote.delta.is_some_and(|delta| {
+            params.min_delta.is_none_or(|min| delta.abs() >= min)
+                && params.max_delta.is_none_or(|max| delta.abs() <= max)
+        });
+    let otm_pct = (quote.strike - quote.spot).abs() / quote.spot * 100.0;
+    let otm_ok = params.min_otm_pct.is_none_or(|min| otm_pct >= min)
+        && params.max_otm_pct.is_none_or(|max| otm_pct <= max);
+    let oi_ok = params
+        .min_oi
+        .is_none_or(|min| quote.oi.is_some_and(|oi| oi >= min));
+    let liquid = params
+        .min_liquidity_score
+        .is_none_or(|min| quote.liquidity >= min);
+    let spread_ok = !params.bid_ask_spread || quote.tight_spread();
+    let fresh = !params.exclude_stale || !quote.stale;
+    delta_ok && otm_ok && oi_ok && liquid && spread_ok && fresh
+}
+
+fn short_option(
+    kind: OptionKind,
+    params: &SingleLegParams,
+    instrument: &Instrument,
+    quote: &LegQuote,
+    now_ms: f64,
+) -> Result<ShortOption, String> {
+    let lot_size = lots::resolve(params.lot_size, &instrument.underlying_key)?;
+    let (spot, strike) = (quote.spot, quote.strike);
+    let premium = quote.sell_price;
+    let premium_per_lot = premium * lot_size;
+    let breakeven = match kind {
+        OptionKind::Call => strike + premium,
+        OptionKind::Put => strike - premium,
+    };
+
+    let estimated_margin = margin::naked(
+        &margin::ShortLeg {
+            kind,
+            strike,
+            spot,
+            lot_size,
+            hedge_strike: None,
+        },
+        &params.margin_model.unwrap_or_default(),
+    );
+    let premium_yield =
+        (estimated_margin > 0.0).then(|| premium_per_lot / estimated_margin * 100.0);
+    let dte = dates::days_to_expiry(&instrument.expiry, now_ms);
+    let t = dates::time_to_expiry(&instrument.expiry, now_ms, params.time_to_expiry_hours);
+    let Pricing {
+        risk_free_rate: r,
+        dividend_yield: q,
+        ..
+    } = params.pricing();
+    // Fractional days under an hours override, as on expiry day
+    let days = match params.time_to_expiry_hours {
+        Some(_) => t.map(|t| t * 365.0),
+        None => dte.map(|days| days as f64),
+    };
+    let annualized_yield = premium_yield
+        .zip(days.filter(|days| *days > 0.0))
+        .map(|(pct, days)| pct * 365.0 / days);
+
+    let pop = match params.pop_model.unwrap_or_default() {
+        PopModel::Delta => quote.delta.map(|delta| 1.0 - delta.abs()),
+        PopModel::Lognormal => t.zip(quote.iv.map(|iv| iv / 100.0)).map(|(t, sigma)| {
+            let below = greeks::probability_below(spot, breakeven, t, r, q, sigma);
+            match kind {
+                OptionKind::Call => below,
+                OptionKind::Put => 1.0 - below,
+            }
+        }),
+    };
+
+    Ok(ShortOption {
+        underlying_key: instrument.underlying_key.clone(),
+        expiry: instrument.expiry.clone(),
+        dte,
+        option_type: kind,
+        strategy_type: StrategyType::single(kind),
+        direction: Direction::of(kind, Side::Sell),
+        strike,
+        spot,
+        instrument_key: quote.instrument_key.map(String::from),
+        lot_size,
+        premium,
+        premium_per_lot,
+        ltp: quote.ltp,
+        bid: quote.bid,
+        ask: quote.ask,
+        delta: quote.delta,
+        iv: quote.iv,
+        oi: quote.oi,
+        volume: quote.volume,
+        liquidity: quote.liquidity,
+        pop,
+        breakeven,
+        breakeven_percentage: ((breakeven - spot).abs() / spot * 100.0 * 100.0).floor() / 100.0,
+        estimated_margin,
+        premium_yield,
+        annualized_yield,
+        undefined_risk: true,
+        max_loss: match kind {
+            OptionKind::Call => None,
+            OptionKind::Put => Some(breakeven * lot_size),
+        },
+        warnings: quote.warnings.clone(),
+    })
+}
+
+/// Out-of-the-money options of `kind` to sell naked, highest premium yield first.
+pub fn short(kind: OptionKind, params: &SingleLegParams) -> Result<Vec<ShortOption>, String> {
+    let (instruments, now_ms) = prepare(params)?;
+    let price_mode = params.price_mode.unwrap_or_default();
+
+    let mut options: Vec<ShortOption> = instruments
+        .iter()
+        .filter_map(|instrument| {
+            let quote = spreads::quote(instrument, kind, price_mode)?;
+            (quote.is_otm(kind) && passes(params, &quote))
+                .then(|| short_option(kind, params, instrument, &quote, now_ms))
+        })
+        .collect::<Result<_, String>>()?;
+    options.retain(|option| {
+        params
+            .min_pop
+            .is_none_or(|min| option.pop.is_some_and(|pop| pop >= min))
+    });
+
+    // Options without a yield go last
+    options.sort_by(|a, b| match (a.premium_yield, b.premium_yield) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    if let Some(limit) = params.limit {
+        options.truncate(limit);
+    }
+    Ok(options)
+}
+
+fn long_option(
+    kind: OptionKind,
+    params: &SingleLegParams,
+    instrument: &Instrument,
+    quote: &LegQuote,
+    now_ms: f64,
+) -> Result<LongOption, String> {
+    let lot_size = lots::resolve(params.lot_size, &instrument.underlying_key)?;
+    let (spot, strike) = (quote.spot, quote.strike);
+    let premium = quote.buy_price;
+    let breakeven = match kind {
+        OptionKind::Call => strike + premium,
+        OptionKind::Put => strike - premium,
+    };
+
+    let t = dates::time_to_expiry(&instrument.expiry, now_ms, params.time_to_expiry_hours);
+    let Pricing {
+        risk_free_rate: r,
+        dividend_yield: q,
+        ..
+    } = params.pricing();
+    let pop = t.zip(quote.iv.map(|iv| iv / 100.0)).map(|(t, sigma)| {
+        let below = greeks::probability_below(spot, breakeven, t, r, q, sigma);
+        match kind {
+            OptionKind::Call => 1.0 - below,
+            OptionKind::Put => below,
+        }
+    });
+    let theta_per_day = quote.theta.map(f64::abs);
+    let expected_roi = params.target_price.map(|target| {
+        let intrinsic = match kind {
+            OptionKind::Call => (target - strike).max(0.0),
+            OptionKind::Put => (strike - target).max(0.0),
+        };
+        (intrinsic - premium) / premium * 100.0
+    });
+
+    Ok(LongOption {
+        underlying_key: instrument.underlying_key.clone(),
+        expiry: instrument.expiry.clone(),
+        dte: dates::days_to_expiry(&instrument.expiry, now_ms),
+        option_type: kind,
+        strategy_type: StrategyType::single(kind),
+        direction: Direction::of(kind, Side::Buy),
+        strike,
+        spot,
+        instrument_key: quote.instrument_key.map(String::from),
+        lot_size,
+        premium,
+        premium_per_lot: premium * lot_size,
+        ltp: quote.ltp,
+        bid: quote.bid,
+        ask: quote.ask,
+        delta: quote.delta,
+        iv: quote.iv,
+        oi: quote.oi,
+        volume: quote.volume,
+        liquidity: quote.liquidity,
+        delta_per_rupee: quote.delta.map(|delta| delta.abs() / premium),
+        breakeven,
+        breakeven_move_pct: (breakeven - spot) / spot * 100.0,
+        theta_per_day,
+        theta_burn_pct: theta_per_day.map(|theta| theta / premium * 100.0),
+        pop,
+        expected_roi,
+        warnings: quote.warnings.clone(),
+    })
+}
+
+/// Options of `kind` to buy, ranked by `sort_by`.
+pub fn long(kind: OptionKind, params: &SingleLegParams) -> Result<Vec<LongOption>, String> {
+    let (instruments, now_ms) = prepare(params)?;
+    let price_mode = params.price_mode.unwrap_or_default();
+
+    let mut options: Vec<LongOption> = instruments
+        .iter()
+        .filter_map(|instrument| {
+            let quote = spreads::quote(instrument, kind, price_mode)?;
+            // A free option has no return to rank
+            (quote.buy_price > 0.0 && passes(params, &quote))
+                .then(|| long_option(kind, params, instrument, &quote, now_ms))
+        })
+        .collect::<Result<_, String>>()?;
+    options.retain(|option| {
+        params
+            .min_pop
+            .is_none_or(|min| option.pop.is_some_and(|pop| pop >= min))
+    });
+
+    // Larger is better for every key once negated where smaller wins
+    let key = |option: &LongOption| match params.sort_by.unwrap_or_default() {
+        LongSortBy::DeltaPerRupee => option.delta_per_rupee,
+        LongSortBy::Premium => Some(-option.premium),
+        LongSortBy::BreakevenMove => Some(-option.breakeven_move_pct.abs()),
+        LongSortBy::ThetaBurn => option.theta_burn_pct.map(|pct| -pct),
+        LongSortBy::ExpectedRoi => option.expected_roi,
+    };
+    // Options missing the key go last
+    options.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(a), Some(b)) => b.total_cmp(&a),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    if let Some(limit) = params.limit {
+        options.truncate(limit);
+    }
+    Ok(options)
+}
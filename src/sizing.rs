@@ -0,0 +1,83 @@
+//! Lot counts for a defined-risk trade.
+//!
+//! Fixed-fractional sizing risks at most `max_risk_percentage` of capital on
+//! the trade's max loss. Kelly sizing treats the trade as a binary bet that
+//! wins `max_profit` with probability `pop` and otherwise loses `max_loss`,
+//! and risks the growth-optimal fraction of capital `p - (1 - p) / b`, where
+//! `b` is the profit-to-loss ratio.
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SizingParams {
+    capital: f64,
+    max_risk_percentage: f64,
+    /// Max loss per lot, in rupees.
+    max_loss: f64,
+    /// Max profit per lot; Kelly sizing is skipped without it.
+    #[serde(default)]
+    max_profit: Option<f64>,
+    /// Probability of profit, between 0 and 1.
+    pop: f64,
+    /// Scales the Kelly fraction, e.g. 0.5 for half Kelly. Defaults to 1.
+    #[serde(default)]
+    kelly_multiplier: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct PositionSize {
+    /// Rupees at risk under the fixed-fractional limit.
+    risk_budget: f64,
+    fixed_fractional_lots: u64,
+    /// Fraction of capital Kelly would put at risk, before the multiplier;
+    /// zero or negative means the trade has no edge.
+    kelly_fraction: Option<f64>,
+    kelly_lots: Option<u64>,
+    /// The smaller of the two, so Kelly never exceeds the risk limit.
+    suggested_lots: u64,
+}
+
+fn lots_for(risk: f64, max_loss: f64) -> u64 {
+    (risk / max_loss).floor().max(0.0) as u64
+}
+
+pub fn size(params: &SizingParams) -> Result<PositionSize, String> {
+    if params.capital <= 0.0 {
+        return Err(String::from("Capital must be positive"));
+    }
+    if params.max_loss <= 0.0 {
+        return Err(String::from("Max loss must be positive"));
+    }
+    if !(0.0..=1.0).contains(&params.pop) {
+        return Err(String::from("POP must be between 0 and 1"));
+    }
+
+    let risk_budget = params.capital * params.max_risk_percentage / 100.0;
+    let fixed_fractional_lots = lots_for(risk_budget, params.max_loss);
+
+    let kelly_fraction = params
+        .max_profit
+        .filter(|max_profit| *max_profit > 0.0)
+        .map(|max_profit| {
+            let b = max_profit / params.max_loss;
+            params.pop - (1.0 - params.pop) / b
+        });
+    let kelly_lots = kelly_fraction.map(|fraction| {
+        let risk = params.capital * fraction * params.kelly_multiplier.unwrap_or(1.0);
+        lots_for(risk, params.max_loss)
+    });
+
+    Ok(PositionSize {
+        risk_budget,
+        fixed_fractional_lots,
+        kelly_fraction,
+        kelly_lots,
+        suggested_lots: kelly_lots.map_or(fixed_fractional_lots, |kelly| {
+            kelly.min(fixed_fractional_lots)
+        }),
+    })
+}
@@ -0,0 +1,69 @@
+//! Registry of option chain input formats.
+//!
+//! Each broker or vendor format implements `ChainSource` in its own module
+//! and is listed in `SOURCES`; everything downstream only sees the
+//! normalized `Instrument` rows. Text formats accept the payload either as a
+//! string or as an already-parsed JS object.
+
+use crate::{chain, csv, kite, nse, upstox, Instrument};
+use std::borrow::Cow;
+
+pub trait ChainSource {
+    /// Name the format is selected by in `parse_chain`.
+    fn format(&self) -> &'static str;
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String>;
+}
+
+/// The payload as JSON text, re-encoding it if it was passed as an object.
+pub fn json_text(data: &serde_json::Value) -> Cow<'_, str> {
+    match data {
+        serde_json::Value::String(text) => Cow::Borrowed(text),
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
+/// The crate's own chain format, a JSON array of `Instrument` rows.
+pub struct InternalSource;
+
+impl ChainSource for InternalSource {
+    fn format(&self) -> &'static str {
+        "internal"
+    }
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+        chain::parse(&json_text(data))
+    }
+}
+
+static SOURCES: [&(dyn ChainSource + Sync); 5] = [
+    &InternalSource,
+    &upstox::UpstoxSource,
+    &nse::NseSource,
+    &kite::KiteSource,
+    &csv::CsvSource,
+];
+
+/// Names of the registered formats.
+pub fn formats() -> Vec<&'static str> {
+    SOURCES.iter().map(|source| source.format()).collect()
+}
+
+/// Parses `data` with the source registered as `format`, normalizing
+/// expiries to ISO dates.
+pub fn parse(format: &str, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+    let source = SOURCES
+        .iter()
+        .find(|source| source.format().eq_ignore_ascii_case(format.trim()))
+        .ok_or_else(|| {
+            format!(
+                "Unknown chain format {}; expected one of {}",
+                format,
+                formats().join(", ")
+            )
+        })?;
+
+    let mut instruments = source.parse(data)?;
+    chain::normalize_expiries(&mut instruments);
+    Ok(instruments)
+}
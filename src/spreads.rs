@@ -0,0 +1,1263 @@
+use crate::chain::StrikeKey;
+use crate::greeks::{OptionKind, Pricing};
+use crate::holidays;
+use crate::perf::{self, Phase};
+use crate::positions::{self, Leg, PriceMode, Side};
+use crate::trace::{self, Rejection};
+use crate::{
+    arena, benchmark, chain, costs, dates, futures, greeks, liquidity, lots, margin, oi, scoring,
+    store, validation, BearCallSpreadParams, CreditSpread, Instrument, OptionGreeks,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+// Widest bid/ask gap, in rupees, that the bid_ask_spread filter accepts
+pub const MAX_BID_ASK_DIFF: f64 = 2.0;
+// NSE index option tick size in rupees
+const TICK_SIZE: f64 = 0.05;
+
+/// Soft data-quality problems on a spread's legs. Strict scans drop strikes
+/// that fail the enabled checks; `lenient` scans keep them and report these.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum SpreadWarning {
+    /// No usable bid/ask on the leg.
+    StaleQuote,
+    /// No volume today and the LTP is still the previous close.
+    Untraded,
+    /// IV and greeks were solved from prices rather than supplied by the feed.
+    ComputedIv,
+    /// Bid/ask gap wider than the `bid_ask_spread` threshold.
+    WideSpread,
+    /// The chain skips a strike step between the two legs.
+    MissingStrikes,
+    /// The feed's delta or IV disagrees with values solved from the price.
+    GreeksMismatch,
+}
+
+/// One leg of a spread with its probabilities under the leg's own IV. The
+/// flattened `Leg` fields can be fed straight back into position APIs.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SpreadLeg {
+    #[serde(flatten)]
+    leg: Leg,
+    /// Probability of finishing in the money at expiry.
+    prob_itm: Option<f64>,
+    /// Probability of spot trading through the strike before expiry.
+    prob_touch: Option<f64>,
+    /// Liquidity score from 0 to 100.
+    liquidity: Option<f64>,
+    /// The leg's quote and delta when the spread was built.
+    #[serde(default)]
+    ltp: Option<f64>,
+    #[serde(default)]
+    bid: Option<f64>,
+    #[serde(default)]
+    ask: Option<f64>,
+    #[serde(default)]
+    delta: Option<f64>,
+    #[serde(default)]
+    oi: Option<u64>,
+    #[serde(default)]
+    volume: Option<u64>,
+}
+
+impl SpreadLeg {
+    pub fn liquidity(&self) -> Option<f64> {
+        self.liquidity
+    }
+
+    pub fn leg(&self) -> &Leg {
+        &self.leg
+    }
+}
+
+/// How `pop` is estimated.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum PopModel {
+    /// One minus the absolute delta of the short leg.
+    #[default]
+    Delta,
+    /// Lognormal probability of finishing beyond breakeven, using the short
+    /// leg's IV and the time to expiry.
+    Lognormal,
+}
+
+/// Which strikes legs may sit on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum Moneyness {
+    /// Out-of-the-money strikes only.
+    #[default]
+    Otm,
+    /// Also the strike nearest spot when it is in the money.
+    Atm,
+    /// Also the `itm_strikes` in-the-money strikes nearest spot, one by
+    /// default.
+    Itm,
+}
+
+/// Days that `annualized_return_on_risk` counts to expiry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum DayCount {
+    /// Calendar days over a 365-day year.
+    #[default]
+    Calendar,
+    /// Trading sessions over a 250-session year.
+    Trading,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum SlippageUnit {
+    #[default]
+    Rupees,
+    Ticks,
+}
+
+/// Spread figures after each leg fills `slippage` worse than its entry price.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SlippageAdjusted {
+    /// Per-unit slippage applied to each leg, in rupees.
+    slippage_per_leg: f64,
+    net_credit: f64,
+    max_profit: f64,
+    max_loss: f64,
+    breakeven: f64,
+}
+
+/// Net spread premiums, per unit, at which to close the trade. The spread
+/// is bought back, so the stop sits above the entry credit and the target
+/// below it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ExitTriggers {
+    stop_loss_premium: Option<f64>,
+    /// Rupee loss per lot when the stop fills.
+    stop_loss_amount: Option<f64>,
+    profit_target_premium: Option<f64>,
+    profit_target_amount: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    NetCredit,
+    MaxProfit,
+    /// Max profit over max loss.
+    RiskReward,
+    BreakevenPct,
+    Pop,
+    /// Return on margin.
+    Roi,
+    ThetaPerMargin,
+    ThetaToVega,
+}
+
+impl SortBy {
+    fn key(self, spread: &CreditSpread) -> Option<f64> {
+        match self {
+            SortBy::NetCredit => Some(spread.net_credit),
+            SortBy::MaxProfit => Some(spread.max_profit),
+            SortBy::RiskReward => {
+                (spread.max_loss > 0.0).then(|| spread.max_profit / spread.max_loss)
+            }
+            SortBy::BreakevenPct => Some(spread.breakeven_percentage),
+            SortBy::Pop => spread.pop,
+            SortBy::Roi => spread.return_on_margin,
+            SortBy::ThetaPerMargin => spread.theta_per_day_per_margin,
+            SortBy::ThetaToVega => spread.theta_to_vega_ratio,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Ascending,
+    #[default]
+    Descending,
+}
+
+/// What a result is: one option, or the structure its legs form. Serialized
+/// as `CE`, `PE`, `CE_SPREAD`, `PE_SPREAD`, `IRON_CONDOR` and so on, so new
+/// strategies add variants rather than new strings.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StrategyType {
+    Ce,
+    Pe,
+    /// Two calls of one expiry, one sold and one bought.
+    CeSpread,
+    PeSpread,
+    IronCondor,
+    Straddle,
+    Strangle,
+    /// The same strike sold in a near expiry and bought in a far one.
+    Calendar,
+    /// A call sold against a long future.
+    CoveredCall,
+    /// A put bought against a long future.
+    ProtectivePut,
+}
+
+impl StrategyType {
+    /// A single option of `kind`.
+    pub fn single(kind: OptionKind) -> StrategyType {
+        match kind {
+            OptionKind::Call => StrategyType::Ce,
+            OptionKind::Put => StrategyType::Pe,
+        }
+    }
+}
+
+/// The move in the underlying a result profits from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Bullish,
+    Bearish,
+    /// Profits while the underlying stays in a range.
+    Neutral,
+}
+
+impl Direction {
+    /// Direction of `side` on an option of `kind`: buying calls and selling
+    /// puts is bullish.
+    pub fn of(kind: OptionKind, side: Side) -> Direction {
+        match (kind, side) {
+            (OptionKind::Call, Side::Buy) | (OptionKind::Put, Side::Sell) => Direction::Bullish,
+            (OptionKind::Call, Side::Sell) | (OptionKind::Put, Side::Buy) => Direction::Bearish,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Strategy {
+    BearCall,
+    BullPut,
+}
+
+impl Strategy {
+    /// `bear_call` or `bull_put`.
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        match name.trim() {
+            "bear_call" => Some(Strategy::BearCall),
+            "bull_put" => Some(Strategy::BullPut),
+            _ => None,
+        }
+    }
+
+    /// `bear_call` or `bull_put`, as `from_name` takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::BearCall => "bear_call",
+            Strategy::BullPut => "bull_put",
+        }
+    }
+
+    /// Name of the scanning export, `bear_call_spread` or `bull_put_spread`.
+    pub fn export_name(self) -> &'static str {
+        match self {
+            Strategy::BearCall => "bear_call_spread",
+            Strategy::BullPut => "bull_put_spread",
+        }
+    }
+
+    fn strategy_type(self) -> StrategyType {
+        match self {
+            Strategy::BearCall => StrategyType::CeSpread,
+            Strategy::BullPut => StrategyType::PeSpread,
+        }
+    }
+
+    fn kind(self) -> OptionKind {
+        match self {
+            Strategy::BearCall => OptionKind::Call,
+            Strategy::BullPut => OptionKind::Put,
+        }
+    }
+}
+
+/// Projects one side of a strike into a `LegQuote`; `None` unless it can be
+/// priced as both a sale and a purchase under `mode`.
+pub fn quote(instrument: &Instrument, kind: OptionKind, mode: PriceMode) -> Option<LegQuote<'_>> {
+    let option = chain::option(instrument, kind)?;
+    let market_data = option.market_data.as_ref()?;
+    let greeks = option.option_greeks.as_ref();
+    let greek = |field: fn(&OptionGreeks) -> Option<f64>| greeks.and_then(field);
+
+    let mut warnings = Vec::new();
+    match market_data.bid_price.zip(market_data.ask_price) {
+        Some((bid, ask)) if bid > 0.0 || ask > 0.0 => {
+            if (ask - bid).abs() > MAX_BID_ASK_DIFF {
+                warnings.push(SpreadWarning::WideSpread);
+            }
+        }
+        _ => warnings.push(SpreadWarning::StaleQuote),
+    }
+    if validation::untraded(market_data) {
+        warnings.push(SpreadWarning::Untraded);
+    }
+    if greeks.is_some_and(|greeks| greeks.computed) {
+        warnings.push(SpreadWarning::ComputedIv);
+    }
+    if greeks.is_some_and(|greeks| greeks.mismatch) {
+        warnings.push(SpreadWarning::GreeksMismatch);
+    }
+
+    Some(LegQuote {
+        instrument_key: Some(option.instrument_key.as_str()).filter(|key| !key.is_empty()),
+        strike: instrument.strike_price,
+        spot: instrument.underlying_spot_price,
+        sell_price: positions::fill_price(market_data, Side::Sell, mode)?,
+        buy_price: positions::fill_price(market_data, Side::Buy, mode)?,
+        ltp: market_data.ltp,
+        bid: market_data.bid_price,
+        ask: market_data.ask_price,
+        oi: market_data.oi,
+        oi_change: oi::oi_change(market_data),
+        volume: market_data.volume,
+        stale: validation::is_stale(market_data),
+        liquidity: liquidity::score(market_data),
+        delta: greek(|g| g.delta),
+        gamma: greek(|g| g.gamma),
+        theta: greek(|g| g.theta),
+        vega: greek(|g| g.vega),
+        iv: greek(|g| g.iv),
+        warnings,
+    })
+}
+
+/// The parts of one strike's option that spread building reads, projected
+/// out of the chain once so that pairing runs over a flat vector.
+#[derive(Debug, Clone)]
+pub struct LegQuote<'a> {
+    pub instrument_key: Option<&'a str>,
+    pub strike: f64,
+    pub spot: f64,
+    /// Per-unit entry prices under the scan's price mode.
+    pub sell_price: f64,
+    pub buy_price: f64,
+    pub ltp: Option<f64>,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub oi: Option<u64>,
+    pub oi_change: Option<f64>,
+    pub volume: Option<u64>,
+    /// No quotes or no trades today.
+    pub stale: bool,
+    pub liquidity: f64,
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    pub theta: Option<f64>,
+    pub vega: Option<f64>,
+    /// Percent, as in the chain.
+    pub iv: Option<f64>,
+    pub warnings: Vec<SpreadWarning>,
+}
+
+impl LegQuote<'_> {
+    pub fn is_otm(&self, kind: OptionKind) -> bool {
+        self.is_otm_against(kind, self.spot)
+    }
+
+    /// Out of the money against `price`, e.g. a future's instead of spot.
+    pub fn is_otm_against(&self, kind: OptionKind, price: f64) -> bool {
+        match kind {
+            OptionKind::Call => self.strike > price,
+            OptionKind::Put => self.strike < price,
+        }
+    }
+
+    /// Both sides quoted no more than `MAX_BID_ASK_DIFF` apart.
+    pub fn tight_spread(&self) -> bool {
+        match (self.bid, self.ask) {
+            (Some(bid), Some(ask)) => (ask - bid).abs() <= MAX_BID_ASK_DIFF,
+            _ => false,
+        }
+    }
+}
+
+/// How far from spot, or from `forward` when given, in index points, an
+/// in-the-money strike may be under `params.moneyness`; `None` when none may
+/// be used.
+fn itm_reach(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    instruments: &[Instrument],
+    forward: Option<f64>,
+) -> Option<f64> {
+    let (spot, atm_strike) = match forward {
+        Some(forward) => (
+            forward,
+            instruments
+                .iter()
+                .map(|instrument| instrument.strike_price)
+                .min_by(|a, b| (a - forward).abs().total_cmp(&(b - forward).abs()))?,
+        ),
+        None => {
+            let atm = chain::atm(instruments)?;
+            (atm.underlying_spot_price, atm.strike_price)
+        }
+    };
+    let mut itm: Vec<f64> = instruments
+        .iter()
+        .map(|instrument| instrument.strike_price)
+        .filter(|strike| match strategy.kind() {
+            OptionKind::Call => *strike <= spot,
+            OptionKind::Put => *strike >= spot,
+        })
+        .map(|strike| (strike - spot).abs())
+        .collect();
+    itm.sort_by(f64::total_cmp);
+    itm.dedup();
+    let count = match params.moneyness.unwrap_or_default() {
+        Moneyness::Otm => 0,
+        Moneyness::Atm => usize::from(itm.first() == Some(&(atm_strike - spot).abs())),
+        Moneyness::Itm => params.itm_strikes.unwrap_or(1),
+    };
+    itm.truncate(count);
+    itm.last().copied()
+}
+
+/// What every spread in one underlying and expiry shares.
+struct Slice<'a> {
+    underlying_key: &'a str,
+    expiry: &'a str,
+    lot_size: f64,
+    dte: Option<i64>,
+    trading_dte: Option<i64>,
+    expiry_cycle: Option<dates::ExpiryCycle>,
+    /// Years to expiry.
+    t: Option<f64>,
+    /// Whole days under `day_count`, or fractional calendar ones under
+    /// `time_to_expiry_hours`, and the days in a year.
+    annualization_days: Option<f64>,
+    days_per_year: f64,
+    pricing: Pricing,
+    oi_wall: Option<f64>,
+    missing_strikes: Vec<f64>,
+    brokerage_profile: costs::BrokerageProfile,
+    margin_model: margin::MarginModel,
+    slippage_per_leg: Option<f64>,
+}
+
+/// Builds the spreads for a single underlying and expiry; strikes are never
+/// paired across symbols or expiries. With `touched`, only pairs with a leg
+/// on one of those strikes are built.
+fn slice_spreads(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    instruments: Vec<Instrument>,
+    lot_size: f64,
+    now_ms: f64,
+    touched: Option<&HashSet<StrikeKey>>,
+) -> Vec<CreditSpread> {
+    let Some(first) = instruments.first() else {
+        return Vec::new();
+    };
+    let strike_step = chain::strike_step(&instruments);
+    let dte = dates::days_to_expiry(&first.expiry, now_ms);
+    let holidays = holidays(params);
+    let trading_dte = holidays::trading_days_to_expiry(&first.expiry, now_ms, &holidays);
+    let t = dates::time_to_expiry(&first.expiry, now_ms, params.time_to_expiry_hours);
+    let day_count = match params.time_to_expiry_hours {
+        Some(_) => None,
+        None => Some(params.day_count.unwrap_or_default()),
+    };
+    let slice = Slice {
+        underlying_key: &first.underlying_key,
+        expiry: &first.expiry,
+        lot_size,
+        dte,
+        trading_dte,
+        expiry_cycle: dates::expiry_cycle(&first.expiry, &holidays),
+        t,
+        annualization_days: match day_count {
+            None => t.map(|t| t * 365.0),
+            Some(DayCount::Calendar) => dte.map(|days| days as f64),
+            Some(DayCount::Trading) => trading_dte.map(|days| days as f64),
+        },
+        days_per_year: match day_count {
+            Some(DayCount::Trading) => holidays::TRADING_DAYS_PER_YEAR,
+            _ => 365.0,
+        },
+        pricing: Pricing::new(
+            params.risk_free_rate,
+            params.dividend_yield,
+            params.exercise_style,
+        ),
+        oi_wall: oi::wall(&instruments, strategy.kind()),
+        missing_strikes: strike_step
+            .map(|step| chain::missing_strikes(&instruments, step))
+            .unwrap_or_default(),
+        brokerage_profile: params.brokerage_profile.unwrap_or_default(),
+        margin_model: params.margin_model.unwrap_or_default(),
+        slippage_per_leg: params.slippage.map(|slippage| {
+            match params.slippage_unit.unwrap_or_default() {
+                SlippageUnit::Rupees => slippage,
+                SlippageUnit::Ticks => slippage * TICK_SIZE,
+            }
+        }),
+    };
+    let price_mode = params.price_mode.unwrap_or_default();
+    let forward = params
+        .futures
+        .as_deref()
+        .and_then(|quotes| futures::find(quotes, slice.underlying_key, slice.expiry))
+        .map(|quote| quote.price);
+    let itm_reach = itm_reach(strategy, params, &instruments, forward);
+
+    let trace_strike = |strike: f64, reason: Rejection| {
+        trace::strike(
+            params.debug,
+            slice.underlying_key,
+            slice.expiry,
+            strike,
+            reason,
+        );
+    };
+    // The first check a strike fails
+    let strike_rejection = |quote: &LegQuote| {
+        let reference = forward.unwrap_or(quote.spot);
+        let itm_ok = itm_reach.is_some_and(|reach| (quote.strike - reference).abs() <= reach);
+        if !quote.is_otm_against(strategy.kind(), reference) && !itm_ok {
+            Some(Rejection::NotOtm)
+        } else if params.bid_ask_spread && !quote.tight_spread() && !params.lenient {
+            Some(Rejection::WideBidAsk)
+        } else if !params
+            .min_liquidity_score
+            .is_none_or(|min| quote.liquidity >= min)
+        {
+            Some(Rejection::Illiquid)
+        } else if params.exclude_stale && quote.stale {
+            Some(Rejection::Stale)
+        } else if !params.lenient && quote.warnings.contains(&SpreadWarning::GreeksMismatch) {
+            Some(Rejection::GreeksMismatch)
+        } else if params.strike_step_only
+            && strike_step.is_some_and(|step| !chain::on_step(quote.strike, step))
+        {
+            Some(Rejection::OffStep)
+        } else {
+            None
+        }
+    };
+
+    // Any strike can end up as either leg, so it needs a price on both sides
+    let mut otm_strikes: Vec<LegQuote> = perf::time(Phase::Filter, || {
+        instruments
+            .iter()
+            .filter_map(|instrument| {
+                let Some(quote) = quote(instrument, strategy.kind(), price_mode) else {
+                    trace_strike(instrument.strike_price, Rejection::NoQuote);
+                    return None;
+                };
+                match strike_rejection(&quote) {
+                    Some(reason) => {
+                        trace_strike(quote.strike, reason);
+                        None
+                    }
+                    None => Some(quote),
+                }
+            })
+            .collect()
+    });
+
+    // Order strikes moving away from spot so the first leg of each pair is the short one
+    let order = |a: &LegQuote, b: &LegQuote| {
+        let ordering = a
+            .strike
+            .partial_cmp(&b.strike)
+            .unwrap_or(std::cmp::Ordering::Equal);
+        match strategy {
+            Strategy::BearCall => ordering,
+            Strategy::BullPut => ordering.reverse(),
+        }
+    };
+    // Stored chains arrive in this order already (see `store::resolve_sorted`)
+    if !otm_strikes.is_sorted_by(|a, b| order(a, b).is_le()) {
+        otm_strikes.sort_by(order);
+    }
+
+    // Delta, distance and OI bounds apply to the short leg only
+    let short_leg_rejection = |quote: &LegQuote| {
+        let delta_ok = (params.min_short_delta.is_none() && params.max_short_delta.is_none())
+            || quote.delta.is_some_and(|delta| {
+                params.min_short_delta.is_none_or(|min| delta.abs() >= min)
+                    && params.max_short_delta.is_none_or(|max| delta.abs() <= max)
+            });
+        let otm_pct = (quote.strike - quote.spot).abs() / quote.spot * 100.0;
+        let otm_ok = params.min_otm_pct.is_none_or(|min| otm_pct >= min)
+            && params.max_otm_pct.is_none_or(|max| otm_pct <= max);
+        let oi_ok = params
+            .min_short_strike_oi
+            .is_none_or(|min| quote.oi.is_some_and(|oi| oi >= min));
+        let oi_change_ok = params
+            .min_oi_change
+            .is_none_or(|min| quote.oi_change.is_some_and(|change| change >= min));
+        [
+            (delta_ok, Rejection::ShortDelta),
+            (otm_ok, Rejection::ShortOtmPct),
+            (oi_ok, Rejection::ShortOi),
+            (oi_change_ok, Rejection::ShortOiChange),
+        ]
+        .into_iter()
+        .find_map(|(ok, reason)| (!ok).then_some(reason))
+    };
+    let short_leg_ok = |quote: &LegQuote| match short_leg_rejection(quote) {
+        Some(reason) => {
+            trace_strike(quote.strike, reason);
+            false
+        }
+        None => true,
+    };
+
+    let dirty: Vec<bool> = otm_strikes
+        .iter()
+        .map(|quote| {
+            touched.is_none_or(|t| {
+                t.contains(&(
+                    slice.underlying_key.to_string(),
+                    slice.expiry.to_string(),
+                    quote.strike.to_bits(),
+                ))
+            })
+        })
+        .collect();
+
+    // Long legs for the short leg at `i`, moving out from it
+    let (quotes, dirty) = (&otm_strikes, &dirty);
+    let trace_pair = |sell: &LegQuote, buy: &LegQuote, reason: Rejection| {
+        trace::pair(
+            params.debug,
+            slice.underlying_key,
+            slice.expiry,
+            sell.strike,
+            buy.strike,
+            reason,
+        );
+    };
+    let pairs = |i: usize| {
+        let sell = &quotes[i];
+        // Strikes only move further from the short leg, so stop at max_width
+        quotes[i + 1..]
+            .iter()
+            .enumerate()
+            .map(move |(offset, buy)| (i + 1 + offset, buy, (sell.strike - buy.strike).abs()))
+            .take_while(move |(_, buy, width)| {
+                let ok = params.max_width.is_none_or(|max| *width <= max);
+                if !ok {
+                    trace_pair(sell, buy, Rejection::MaxWidth);
+                }
+                ok
+            })
+            .filter(move |(_, buy, width)| {
+                let ok = params.min_width.is_none_or(|min| *width >= min);
+                if !ok {
+                    trace_pair(sell, buy, Rejection::MinWidth);
+                }
+                ok
+            })
+            .filter(move |(j, _, _)| dirty[i] || dirty[*j])
+            .map(move |(_, buy, _)| (sell, buy))
+    };
+
+    // n(n-1)/2 pairs at most, or n per allowed step of width
+    let n = otm_strikes.len();
+    let most_pairs = match params.max_width.zip(strike_step) {
+        Some((max, step)) if step > 0.0 => n * ((max / step) as usize).min(n.saturating_sub(1)),
+        _ => n * n.saturating_sub(1) / 2,
+    };
+    let credit_spreads = perf::time(Phase::Pair, || {
+        let mut credit_spreads = arena::take(most_pairs);
+        #[cfg(feature = "threads")]
+        {
+            use rayon::prelude::*;
+            credit_spreads.par_extend(
+                (0..n)
+                    .into_par_iter()
+                    .filter(|&i| short_leg_ok(&otm_strikes[i]))
+                    .flat_map_iter(pairs)
+                    .map(|(sell, buy)| build_spread(strategy, params, &slice, sell, buy)),
+            );
+        }
+        #[cfg(not(feature = "threads"))]
+        credit_spreads.extend(
+            (0..n)
+                .filter(|&i| short_leg_ok(&otm_strikes[i]))
+                .flat_map(pairs)
+                .map(|(sell, buy)| build_spread(strategy, params, &slice, sell, buy)),
+        );
+        credit_spreads
+    });
+    perf::count_slice(instruments.len(), n, credit_spreads.len());
+    trace::count_slice(instruments.len(), credit_spreads.len());
+    credit_spreads
+}
+
+/// One lot of `sell` against one lot of `buy`.
+fn build_spread(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    slice: &Slice,
+    sell: &LegQuote,
+    buy: &LegQuote,
+) -> CreditSpread {
+    let lot_size = slice.lot_size;
+    let spot = sell.spot;
+    let width = (sell.strike - buy.strike).abs();
+    let spread = width * lot_size;
+    // Rupees and breakevens round up to whole numbers unless `raw_numbers`
+    let round_up = |value: f64| {
+        if params.raw_numbers {
+            value
+        } else {
+            value.ceil()
+        }
+    };
+    let breakeven_for = |net_credit: f64| {
+        round_up(match strategy {
+            Strategy::BearCall => sell.strike + (net_credit / lot_size),
+            Strategy::BullPut => sell.strike - (net_credit / lot_size),
+        })
+    };
+    let net_credit = (sell.sell_price - buy.buy_price) * lot_size;
+    let max_profit = round_up(net_credit);
+    let max_loss = round_up(spread - net_credit);
+    let breakeven = breakeven_for(net_credit);
+
+    let short_leg = margin::ShortLeg {
+        kind: strategy.kind(),
+        strike: sell.strike,
+        spot,
+        lot_size,
+        hedge_strike: Some(buy.strike),
+    };
+    let estimated_margin = margin::estimate(&short_leg, &slice.margin_model);
+    let naked_margin = margin::naked(&short_leg, &slice.margin_model);
+
+    let credit = net_credit / lot_size;
+    // The spread can't be worth more than its width
+    let stop_loss_premium = params
+        .stop_loss_multiple
+        .map(|multiple| (credit * (1.0 + multiple)).min(width));
+    let profit_target_premium = params
+        .profit_target_pct
+        .map(|pct| credit * (1.0 - pct / 100.0));
+    let exits =
+        (stop_loss_premium.is_some() || profit_target_premium.is_some()).then(|| ExitTriggers {
+            stop_loss_premium,
+            stop_loss_amount: stop_loss_premium.map(|premium| (premium - credit) * lot_size),
+            profit_target_premium,
+            profit_target_amount: profit_target_premium
+                .map(|premium| (credit - premium) * lot_size),
+        });
+
+    // Opening orders only; at max profit both legs expire worthless
+    let total_charges = costs::total_charges(
+        &[
+            (Side::Sell, sell.sell_price * lot_size),
+            (Side::Buy, buy.buy_price * lot_size),
+        ],
+        &slice.brokerage_profile,
+    );
+
+    // Both legs fill worse: the sale lower, the purchase higher
+    let slippage_adjusted = slice.slippage_per_leg.map(|slippage_per_leg| {
+        let net_credit = net_credit - 2.0 * slippage_per_leg * lot_size;
+        SlippageAdjusted {
+            slippage_per_leg,
+            net_credit,
+            max_profit: round_up(net_credit),
+            max_loss: round_up(spread - net_credit),
+            breakeven: breakeven_for(net_credit),
+        }
+    });
+
+    // Calculate breakeven_percentage and trim it to 2 decimal places without rounding up
+    let breakeven_percentage = ((breakeven - spot).abs() / spot) * 100.0;
+    let breakeven_percentage_trimmed = if params.raw_numbers {
+        breakeven_percentage
+    } else {
+        (breakeven_percentage * 100.0).floor() / 100.0
+    };
+    let breakeven_distance_pct = params.signed_breakeven.then(|| {
+        if breakeven < spot {
+            -breakeven_percentage_trimmed
+        } else {
+            breakeven_percentage_trimmed
+        }
+    });
+
+    // Short one lot of the sell leg, long one lot of the buy leg
+    let net_greek = |greek: fn(&LegQuote) -> Option<f64>| Some(greek(buy)? - greek(sell)?);
+    let net_delta = net_greek(|q| q.delta);
+    let net_theta = net_greek(|q| q.theta);
+    let net_vega = net_greek(|q| q.vega);
+
+    let futures_benchmark = net_delta.map(|net_delta| {
+        benchmark::against_futures(
+            net_delta,
+            spot,
+            buy.strike,
+            lot_size,
+            max_loss,
+            params.futures_margin_percentage,
+        )
+    });
+
+    let dte = slice.dte;
+    // Return on capital at risk (max profit / max loss), scaled to a year
+    let annualized_return_on_risk = slice
+        .annualization_days
+        .filter(|days| *days > 0.0 && max_loss > 0.0)
+        .map(|days| (max_profit / max_loss) * (slice.days_per_year / days) * 100.0);
+
+    let t = slice.t;
+    let Pricing {
+        risk_free_rate: r,
+        dividend_yield: q,
+        ..
+    } = slice.pricing;
+    let leg = |quote: &LegQuote, side: Side| {
+        let sigma = quote.iv.map(|iv| iv / 100.0);
+        let (spot, strike) = (quote.spot, quote.strike);
+        let probabilities = t.zip(sigma);
+        SpreadLeg {
+            leg: Leg {
+                instrument_key: quote.instrument_key.map(String::from),
+                option_type: strategy.kind(),
+                strike,
+                expiry: Some(slice.expiry.to_string()),
+                side,
+                lots: 1.0,
+                price: match side {
+                    Side::Sell => quote.sell_price,
+                    Side::Buy => quote.buy_price,
+                },
+                iv: quote.iv,
+            },
+            prob_itm: probabilities.map(|(t, sigma)| {
+                greeks::probability_itm(strategy.kind(), spot, strike, t, r, q, sigma)
+            }),
+            prob_touch: probabilities
+                .map(|(t, sigma)| greeks::probability_touch(spot, strike, t, r, q, sigma)),
+            liquidity: Some(quote.liquidity),
+            ltp: quote.ltp,
+            bid: quote.bid,
+            ask: quote.ask,
+            delta: quote.delta,
+            oi: quote.oi,
+            volume: quote.volume,
+        }
+    };
+
+    let pop = match params.pop_model.unwrap_or_default() {
+        PopModel::Delta => sell.delta.map(|delta| 1.0 - delta.abs()),
+        PopModel::Lognormal => {
+            let sigma = sell.iv.map(|iv| iv / 100.0);
+            t.zip(sigma).map(|(t, sigma)| {
+                let below = greeks::probability_below(spot, breakeven, t, r, q, sigma);
+                match strategy {
+                    Strategy::BearCall => below,
+                    Strategy::BullPut => 1.0 - below,
+                }
+            })
+        }
+    };
+
+    let mut warnings = sell.warnings.clone();
+    warnings.extend(&buy.warnings);
+    let (low, high) = if sell.strike < buy.strike {
+        (sell.strike, buy.strike)
+    } else {
+        (buy.strike, sell.strike)
+    };
+    if slice
+        .missing_strikes
+        .iter()
+        .any(|strike| *strike > low && *strike < high)
+    {
+        warnings.push(SpreadWarning::MissingStrikes);
+    }
+    warnings.sort();
+    warnings.dedup();
+
+    CreditSpread {
+        sell_strike: sell.strike,
+        buy_strike: buy.strike,
+        spread,
+        lot_size,
+        net_credit,
+        max_profit,
+        max_loss,
+        breakeven,
+        breakeven_percentage: breakeven_percentage_trimmed,
+        breakeven_distance_pct,
+        slippage_adjusted,
+        score: None,
+        exits,
+        total_charges,
+        net_profit_after_costs: max_profit - total_charges,
+        estimated_margin,
+        return_on_margin: (estimated_margin > 0.0).then(|| max_profit / estimated_margin * 100.0),
+        naked_margin,
+        margin_benefit: naked_margin - estimated_margin,
+        type_: strategy.kind(),
+        strategy_type: strategy.strategy_type(),
+        // The short leg is the one closer to spot, so it sets the direction
+        direction: Direction::of(strategy.kind(), Side::Sell),
+        underlying_key: slice.underlying_key.to_string(),
+        expiry: slice.expiry.to_string(),
+        expiry_valid: dte.is_some(),
+        expiry_cycle: slice.expiry_cycle,
+        trading_dte: slice.trading_dte,
+        dte,
+        hours_to_expiry: t.map(|t| t * dates::HOURS_PER_YEAR),
+        annualized_return_on_risk,
+        pop,
+        expected_value: pop.map(|pop| pop * max_profit - (1.0 - pop) * max_loss),
+        iv_rank: params.iv_rank,
+        net_delta,
+        net_gamma: net_greek(|q| q.gamma),
+        net_theta,
+        // Weekend and holiday decay lands on the sessions either side of it
+        net_theta_per_trading_day: net_theta
+            .zip(dte.zip(slice.trading_dte))
+            .filter(|(_, (_, trading))| *trading > 0)
+            .map(|(theta, (days, trading))| theta * days as f64 / trading as f64),
+        net_vega,
+        theta_per_day_per_margin: net_theta
+            .filter(|_| estimated_margin > 0.0)
+            .map(|theta| theta * lot_size / estimated_margin * 100.0),
+        theta_to_vega_ratio: net_theta
+            .zip(net_vega)
+            .filter(|(_, vega)| *vega != 0.0)
+            .map(|(theta, vega)| theta / vega.abs()),
+        futures_benchmark,
+        oi_wall: slice.oi_wall,
+        oi_wall_protected: slice.oi_wall.is_some_and(|wall| match strategy {
+            Strategy::BearCall => wall > spot && wall <= sell.strike,
+            Strategy::BullPut => wall < spot && wall >= sell.strike,
+        }),
+        warnings,
+        legs: vec![leg(sell, Side::Sell), leg(buy, Side::Buy)],
+    }
+}
+
+/// Parses the option chain and returns every credit spread for `strategy`.
+///
+/// The error string is the message handed back to JS callers.
+pub fn scan(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+) -> Result<Vec<CreditSpread>, String> {
+    let instruments = perf::time(Phase::Parse, || {
+        store::resolve_sorted(strategy, params.chain_id, &params.optionchain)
+    })?;
+    scan_chain(strategy, params, instruments)
+}
+
+/// Same as `scan`, on a chain that is already parsed; `params.optionchain`
+/// is ignored.
+pub fn scan_chain(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    instruments: Vec<Instrument>,
+) -> Result<Vec<CreditSpread>, String> {
+    let candidates = candidates(strategy, params, instruments, None)?;
+    Ok(perf::time(Phase::Sort, || finish(params, candidates)))
+}
+
+/// Every spread for `strategy` before sorting and the result filters. With
+/// `touched`, only spreads with a leg on one of those strikes are built.
+pub fn candidates(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    instruments: Vec<Instrument>,
+    touched: Option<&HashSet<StrikeKey>>,
+) -> Result<Vec<CreditSpread>, String> {
+    let mut credit_spreads = Vec::new();
+    for slice in slice_candidates(strategy, params, instruments, touched)? {
+        gather(&mut credit_spreads, slice);
+    }
+    Ok(credit_spreads)
+}
+
+/// Moves a slice's spreads onto `credit_spreads` and recycles its buffer for
+/// the next slice.
+pub fn gather(credit_spreads: &mut Vec<CreditSpread>, mut slice: Vec<CreditSpread>) {
+    credit_spreads.append(&mut slice);
+    arena::recycle(slice);
+}
+
+fn holidays(params: &BearCallSpreadParams) -> Vec<i64> {
+    holidays::trading_holidays(params.holidays.as_deref())
+}
+
+/// Applies the chain-wide filters and fills in missing greeks.
+fn prepare(
+    params: &BearCallSpreadParams,
+    instruments: &mut Vec<Instrument>,
+    touched: Option<&HashSet<StrikeKey>>,
+    now_ms: f64,
+) {
+    let trace_row = |instrument: &Instrument, reason: Rejection| {
+        trace::row(
+            params.debug,
+            &instrument.underlying_key,
+            &instrument.expiry,
+            instrument.strike_price,
+            reason,
+        );
+    };
+    if let Some(expiry) = &params.expiry {
+        instruments.retain(|instrument| {
+            let keep = dates::same_expiry(&instrument.expiry, expiry);
+            if !keep {
+                trace_row(instrument, Rejection::Expiry);
+            }
+            keep
+        });
+    }
+    if let Some(cycle) = params.expiry_cycle {
+        let holidays = holidays(params);
+        instruments.retain(|instrument| {
+            let keep = dates::expiry_cycle(&instrument.expiry, &holidays) == Some(cycle);
+            if !keep {
+                trace_row(instrument, Rejection::ExpiryCycle);
+            }
+            keep
+        });
+    }
+    if params.reject_invalid_expiry {
+        instruments.retain(|instrument| {
+            let keep = chain::has_valid_expiry(instrument);
+            if !keep {
+                trace_row(instrument, Rejection::InvalidExpiry);
+            }
+            keep
+        });
+    }
+    if let Some(touched) = touched {
+        // Slices without a touched strike would build nothing
+        let slices: HashSet<(&str, &str)> = touched
+            .iter()
+            .map(|(underlying, expiry, _)| (underlying.as_str(), expiry.as_str()))
+            .collect();
+        instruments.retain(|instrument| {
+            slices.contains(&(
+                instrument.underlying_key.as_str(),
+                instrument.expiry.as_str(),
+            ))
+        });
+    }
+
+    let pricing = Pricing::new(
+        params.risk_free_rate,
+        params.dividend_yield,
+        params.exercise_style,
+    );
+    if let Some(check) = &params.greeks_check {
+        greeks::cross_check(
+            instruments,
+            now_ms,
+            params.time_to_expiry_hours,
+            pricing,
+            check,
+        );
+    }
+    greeks::fill_missing_at(instruments, now_ms, params.time_to_expiry_hours, pricing);
+}
+
+/// Same as `candidates`, one underlying and expiry at a time; each slice's
+/// spreads are built only when the iterator reaches it.
+pub fn slice_candidates<'a>(
+    strategy: Strategy,
+    params: &'a BearCallSpreadParams,
+    instruments: Vec<Instrument>,
+    touched: Option<&'a HashSet<StrikeKey>>,
+) -> Result<impl Iterator<Item = Vec<CreditSpread>> + 'a, String> {
+    let (_, slices) = weighted_slice_candidates(strategy, params, instruments, touched)?;
+    Ok(slices.map(|(_, slice)| slice))
+}
+
+/// Most pairs `strikes` strikes can make.
+fn pair_work(strikes: usize) -> usize {
+    strikes * strikes.saturating_sub(1) / 2
+}
+
+/// `slice_candidates` with each slice's share of the scan for progress
+/// reporting: the most pairs its strikes can make, out of the returned total.
+/// Lot sizes are resolved for every slice up front, so an underlying without
+/// one fails the scan before any slice is built.
+pub fn weighted_slice_candidates<'a>(
+    strategy: Strategy,
+    params: &'a BearCallSpreadParams,
+    mut instruments: Vec<Instrument>,
+    touched: Option<&'a HashSet<StrikeKey>>,
+) -> Result<(usize, impl Iterator<Item = (usize, Vec<CreditSpread>)> + 'a), String> {
+    let now_ms = params.now.unwrap_or_else(dates::now_ms);
+    perf::time(Phase::Filter, || {
+        prepare(params, &mut instruments, touched, now_ms)
+    });
+
+    let slices: Vec<(f64, Vec<Instrument>)> = chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    })
+    .into_iter()
+    .map(|((underlying_key, _), instruments)| {
+        Ok((
+            lots::resolve(params.lot_size, &underlying_key)?,
+            instruments,
+        ))
+    })
+    .collect::<Result<_, String>>()?;
+    let total = slices.iter().map(|(_, slice)| pair_work(slice.len())).sum();
+    let slices = slices.into_iter().map(move |(lot_size, instruments)| {
+        let work = pair_work(instruments.len());
+        (
+            work,
+            slice_spreads(strategy, params, instruments, lot_size, now_ms, touched),
+        )
+    });
+    Ok((total, slices))
+}
+
+/// Percent of `total` that `done` is; 100 for an empty scan.
+pub fn percent_done(done: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        done as f64 / total as f64 * 100.0
+    }
+}
+
+/// Runs the scan like `scan`, handing `emit` each underlying and expiry's
+/// spreads as soon as they are built, in chunks of at most `chunk_size`,
+/// then `progress` the percent of pairs processed so far. Chunks are sorted
+/// and filtered on their own but not ranked or paged; the returned spreads
+/// are the full result. Scanning stops at the first error from `emit` or
+/// `progress`.
+pub fn scan_streaming(
+    strategy: Strategy,
+    params: &BearCallSpreadParams,
+    chunk_size: usize,
+    mut emit: impl FnMut(&[CreditSpread]) -> Result<(), String>,
+    mut progress: impl FnMut(f64) -> Result<(), String>,
+) -> Result<Vec<CreditSpread>, String> {
+    let instruments = store::resolve_sorted(strategy, params.chain_id, &params.optionchain)?;
+    let mut credit_spreads = Vec::new();
+    let (total, slices) = weighted_slice_candidates(strategy, params, instruments, None)?;
+    let mut done = 0;
+    for (work, slice) in slices {
+        let chunk = sort_and_filter(params, slice.clone(), false);
+        for part in chunk.chunks(chunk_size.max(1)) {
+            emit(part)?;
+        }
+        gather(&mut credit_spreads, slice);
+        done += work;
+        progress(percent_done(done, total))?;
+    }
+    Ok(finish(params, credit_spreads))
+}
+
+/// Sorting, the result filters, ranking and paging, in that order.
+pub fn finish(
+    params: &BearCallSpreadParams,
+    credit_spreads: Vec<CreditSpread>,
+) -> Vec<CreditSpread> {
+    let mut credit_spreads = sort_and_filter(params, credit_spreads, true);
+
+    // Ranked last so scores are normalized over the spreads actually returned
+    if let Some(weights) = &params.score_weights {
+        scoring::rank(&mut credit_spreads, weights);
+    }
+
+    let offset = params.offset.unwrap_or(0).min(credit_spreads.len());
+    credit_spreads.drain(..offset);
+    if let Some(limit) = params.limit {
+        credit_spreads.truncate(limit);
+    }
+    credit_spreads
+}
+
+/// Drops are traced only when `traced`, so that streamed chunks, filtered
+/// again with the full result, are not counted twice.
+fn sort_and_filter(
+    params: &BearCallSpreadParams,
+    mut credit_spreads: Vec<CreditSpread>,
+    traced: bool,
+) -> Vec<CreditSpread> {
+    // The legacy boolean sorts map onto sort_by, return on margin winning
+    let sort_by = params.sort_by.or(if params.return_on_margin_sort {
+        Some(SortBy::Roi)
+    } else if params.breakeven_percentage_sort {
+        Some(SortBy::BreakevenPct)
+    } else {
+        None
+    });
+    if let Some(sort_by) = sort_by {
+        let order = params.sort_order.unwrap_or_default();
+        // Spreads missing the sort key go last in either order
+        credit_spreads.sort_by(|a, b| match (sort_by.key(a), sort_by.key(b)) {
+            (Some(a), Some(b)) => match order {
+                SortOrder::Ascending => a.total_cmp(&b),
+                SortOrder::Descending => b.total_cmp(&a),
+            },
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+    }
+
+    let max_risk_reward = params
+        .max_risk_reward
+        .or(params.risk_reward_ratio.then_some(3.0));
+    let trace_result = |spread: &CreditSpread, reason: Rejection| {
+        if traced {
+            trace::result(
+                params.debug,
+                &spread.underlying_key,
+                &spread.expiry,
+                spread.sell_strike,
+                spread.buy_strike,
+                reason,
+            );
+        }
+    };
+    if let Some(ratio) = max_risk_reward {
+        credit_spreads.retain(|spread| {
+            let keep = spread.max_loss <= ratio * spread.max_profit;
+            if !keep {
+                trace_result(spread, Rejection::RiskReward);
+            }
+            keep
+        });
+    }
+
+    if let Some(min_pop) = params.min_pop {
+        credit_spreads.retain(|spread| {
+            let keep = spread.pop.is_some_and(|pop| pop >= min_pop);
+            if !keep {
+                trace_result(spread, Rejection::MinPop);
+            }
+            keep
+        });
+    }
+    credit_spreads
+}
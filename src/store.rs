@@ -0,0 +1,195 @@
+//! Parsed chains kept in module memory between calls.
+//!
+//! A multi-megabyte chain costs far more to serialize and parse than to
+//! scan, so callers that run several scans on the same snapshot can load it
+//! once and pass the returned id instead. The module is single-threaded, so
+//! the store lives in a thread local. Chains stay until they are dropped.
+//!
+//! Every update bumps the chain's version and stamps the rows it changed, so
+//! an incremental rescan can find the strikes changed since it last ran.
+//!
+//! Loading also indexes the chain by underlying, expiry and strike, so
+//! builders that need particular strikes of a stored chain look them up
+//! instead of scanning it. It also keeps the rows' strike order for each
+//! strategy, so spread scans of a stored chain skip re-sorting its strikes.
+
+use crate::chain::StrikeIndex;
+use crate::rescan::CachedScan;
+use crate::spreads::Strategy;
+use crate::{chain, Instrument};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+pub struct StoredChain {
+    instruments: Vec<Instrument>,
+    index: StrikeIndex,
+    /// Rows by strike, ascending for calls and descending for puts: the
+    /// order `BearCall` and `BullPut` pair strikes in.
+    ascending: Vec<usize>,
+    descending: Vec<usize>,
+    version: u64,
+    /// Version of the update that last changed each row.
+    row_versions: Vec<u64>,
+    /// Last incremental scan per strategy.
+    pub scans: HashMap<Strategy, CachedScan>,
+}
+
+impl StoredChain {
+    /// A copy of the chain in the order `strategy` pairs its strikes in.
+    pub fn sorted(&self, strategy: Strategy) -> Vec<Instrument> {
+        let rows = match strategy {
+            Strategy::BearCall => &self.ascending,
+            Strategy::BullPut => &self.descending,
+        };
+        rows.iter()
+            .map(|&row| self.instruments[row].clone())
+            .collect()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Rows changed after `version`.
+    pub fn changed_since(&self, version: u64) -> impl Iterator<Item = &Instrument> {
+        self.instruments
+            .iter()
+            .zip(&self.row_versions)
+            .filter(move |(_, row_version)| **row_version > version)
+            .map(|(instrument, _)| instrument)
+    }
+
+    /// Applies `f`, which returns the indices of the rows it changed, as one
+    /// update.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut [Instrument]) -> (R, Vec<usize>)) -> R {
+        let (result, rows) = f(&mut self.instruments);
+        if !rows.is_empty() {
+            self.version += 1;
+            for row in rows {
+                self.row_versions[row] = self.version;
+            }
+        }
+        result
+    }
+}
+
+/// Row indices of `instruments` ordered by strike, ties in row order.
+fn strike_order(instruments: &[Instrument], descending: bool) -> Vec<usize> {
+    let mut rows: Vec<usize> = (0..instruments.len()).collect();
+    rows.sort_by(|&a, &b| {
+        let ordering = instruments[a]
+            .strike_price
+            .partial_cmp(&instruments[b].strike_price)
+            .unwrap_or(std::cmp::Ordering::Equal);
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    rows
+}
+
+#[derive(Default)]
+struct Store {
+    next_id: u32,
+    chains: HashMap<u32, StoredChain>,
+}
+
+thread_local! {
+    static STORE: RefCell<Store> = RefCell::new(Store::default());
+}
+
+fn not_loaded(chain_id: u32) -> String {
+    format!("No chain loaded with id {}", chain_id)
+}
+
+/// Stores a parsed chain and returns its id.
+pub fn insert(instruments: Vec<Instrument>) -> u32 {
+    STORE.with_borrow_mut(|store| {
+        store.next_id += 1;
+        let stored = StoredChain {
+            row_versions: vec![0; instruments.len()],
+            index: StrikeIndex::build(&instruments),
+            ascending: strike_order(&instruments, false),
+            descending: strike_order(&instruments, true),
+            instruments,
+            version: 0,
+            scans: HashMap::new(),
+        };
+        store.chains.insert(store.next_id, stored);
+        store.next_id
+    })
+}
+
+/// Frees a stored chain; `false` if the id wasn't loaded.
+pub fn remove(chain_id: u32) -> bool {
+    STORE.with_borrow_mut(|store| store.chains.remove(&chain_id).is_some())
+}
+
+/// Drops the cached incremental scans of every stored chain.
+pub fn clear_scans() {
+    STORE.with_borrow_mut(|store| {
+        for stored in store.chains.values_mut() {
+            stored.scans = HashMap::new();
+        }
+    });
+}
+
+/// A copy of a stored chain, for scans that filter it in place.
+pub fn get(chain_id: u32) -> Result<Vec<Instrument>, String> {
+    with_chain_mut(chain_id, |stored| stored.instruments.clone())
+}
+
+/// Runs `f` on a stored chain.
+pub fn with_chain_mut<R>(
+    chain_id: u32,
+    f: impl FnOnce(&mut StoredChain) -> R,
+) -> Result<R, String> {
+    STORE.with_borrow_mut(|store| {
+        store
+            .chains
+            .get_mut(&chain_id)
+            .map(f)
+            .ok_or_else(|| not_loaded(chain_id))
+    })
+}
+
+/// The stored chain `chain_id` if given, otherwise `optionchain` parsed.
+pub fn resolve(chain_id: Option<u32>, optionchain: &str) -> Result<Vec<Instrument>, String> {
+    match chain_id {
+        Some(chain_id) => get(chain_id),
+        None => chain::parse(optionchain),
+    }
+}
+
+/// `resolve` for a spread scan: a stored chain comes back in the order
+/// `strategy` pairs strikes in.
+pub fn resolve_sorted(
+    strategy: Strategy,
+    chain_id: Option<u32>,
+    optionchain: &str,
+) -> Result<Vec<Instrument>, String> {
+    match chain_id {
+        Some(chain_id) => with_chain_mut(chain_id, |stored| stored.sorted(strategy)),
+        None => chain::parse(optionchain),
+    }
+}
+
+/// `resolve` along with the chain's strike index, the stored one for a
+/// loaded chain.
+pub fn resolve_indexed(
+    chain_id: Option<u32>,
+    optionchain: &str,
+) -> Result<(Vec<Instrument>, StrikeIndex), String> {
+    match chain_id {
+        Some(chain_id) => with_chain_mut(chain_id, |stored| {
+            (stored.instruments.clone(), stored.index.clone())
+        }),
+        None => {
+            let instruments = chain::parse(optionchain)?;
+            let index = StrikeIndex::build(&instruments);
+            Ok((instruments, index))
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! What a scan leaves out, and why.
+//!
+//! Every row, strike, pair or spread that a filter drops is tallied for the
+//! scan's `with_stats` envelope. Under `debug` it is also logged as a JSON
+//! object naming it and the filter, e.g.
+//! `{"stage":"strike","underlying_key":"NSE_INDEX|Nifty 50","expiry":"2024-12-26","strike":24000.0,"reason":"wide_bid_ask"}`,
+//! so a spread missing from the results can be traced back to its cause.
+//! Events go through `log` at info level under the `trace` target.
+//!
+//! Tallies are atomic so pairs rejected on the `threads` pool count too, and
+//! are only kept between `start` and `finish`, which the scan exports call,
+//! or until a scan that fails drops its `Tally`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+/// The filter that dropped a row, strike, pair or spread.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum Rejection {
+    /// Outside the `expiry` filter.
+    Expiry,
+    /// Not the `expiry_cycle` asked for.
+    ExpiryCycle,
+    /// Expiry can't be parsed, under `reject_invalid_expiry`.
+    InvalidExpiry,
+    /// No option on the strategy's side, or no price to sell and buy it at.
+    NoQuote,
+    /// At or in the money, beyond what `moneyness` allows.
+    NotOtm,
+    /// Bid and ask too far apart, under `bid_ask_spread`.
+    WideBidAsk,
+    /// Under `min_liquidity_score`.
+    Illiquid,
+    /// No quotes or no trades today, under `exclude_stale`.
+    Stale,
+    /// Feed greeks disagree with computed ones, under `greeks_check`.
+    GreeksMismatch,
+    /// Off the chain's strike step, under `strike_step_only`.
+    OffStep,
+    /// Short leg outside `min_short_delta` and `max_short_delta`.
+    ShortDelta,
+    /// Short leg outside `min_otm_pct` and `max_otm_pct`.
+    ShortOtmPct,
+    /// Short leg under `min_short_strike_oi`.
+    ShortOi,
+    /// Short leg under `min_oi_change`.
+    ShortOiChange,
+    /// Legs closer than `min_width`.
+    MinWidth,
+    /// Legs further apart than `max_width`; wider pairs on the same short
+    /// leg are skipped without an event of their own.
+    MaxWidth,
+    /// Over `max_risk_reward` or `risk_reward_ratio`.
+    RiskReward,
+    /// Under `min_pop`.
+    MinPop,
+}
+
+const REJECTIONS: [Rejection; 18] = [
+    Rejection::Expiry,
+    Rejection::ExpiryCycle,
+    Rejection::InvalidExpiry,
+    Rejection::NoQuote,
+    Rejection::NotOtm,
+    Rejection::WideBidAsk,
+    Rejection::Illiquid,
+    Rejection::Stale,
+    Rejection::GreeksMismatch,
+    Rejection::OffStep,
+    Rejection::ShortDelta,
+    Rejection::ShortOtmPct,
+    Rejection::ShortOi,
+    Rejection::ShortOiChange,
+    Rejection::MinWidth,
+    Rejection::MaxWidth,
+    Rejection::RiskReward,
+    Rejection::MinPop,
+];
+
+/// Counts for one scan; `rejections` holds every filter that dropped
+/// anything.
+#[derive(Serialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RejectionStats {
+    /// Strikes looked at, after the expiry filters.
+    total_strikes: usize,
+    /// Strikes with no price to trade at on the strategy's side.
+    filtered_no_ltp: usize,
+    filtered_bid_ask: usize,
+    /// Spreads built, before the result filters.
+    pairs_generated: usize,
+    pairs_filtered_rr: usize,
+    rejections: BTreeMap<Rejection, usize>,
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static STRIKES: AtomicUsize = AtomicUsize::new(0);
+static PAIRS: AtomicUsize = AtomicUsize::new(0);
+static COUNTS: [AtomicUsize; REJECTIONS.len()] = [const { AtomicUsize::new(0) }; REJECTIONS.len()];
+
+/// Tallying for a scan; dropped before `finish`, as on an export's early
+/// error return, it stops the tally so later calls aren't counted into it.
+#[must_use]
+pub struct Tally(());
+
+impl Drop for Tally {
+    fn drop(&mut self) {
+        ACTIVE.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Starts tallying for a new scan.
+pub fn start() -> Tally {
+    STRIKES.store(0, Ordering::Relaxed);
+    PAIRS.store(0, Ordering::Relaxed);
+    for count in &COUNTS {
+        count.store(0, Ordering::Relaxed);
+    }
+    ACTIVE.store(true, Ordering::Relaxed);
+    Tally(())
+}
+
+/// Stops tallying and returns the counts since `start`.
+pub fn finish() -> RejectionStats {
+    ACTIVE.store(false, Ordering::Relaxed);
+    let count = |reason: Rejection| COUNTS[reason as usize].load(Ordering::Relaxed);
+    RejectionStats {
+        total_strikes: STRIKES.load(Ordering::Relaxed),
+        filtered_no_ltp: count(Rejection::NoQuote),
+        filtered_bid_ask: count(Rejection::WideBidAsk),
+        pairs_generated: PAIRS.load(Ordering::Relaxed),
+        pairs_filtered_rr: count(Rejection::RiskReward),
+        rejections: REJECTIONS
+            .into_iter()
+            .map(|reason| (reason, count(reason)))
+            .filter(|(_, count)| *count > 0)
+            .collect(),
+    }
+}
+
+/// Records one underlying and expiry of `strikes` strikes, building `pairs`
+/// spreads.
+pub fn count_slice(strikes: usize, pairs: usize) {
+    if ACTIVE.load(Ordering::Relaxed) {
+        STRIKES.fetch_add(strikes, Ordering::Relaxed);
+        PAIRS.fetch_add(pairs, Ordering::Relaxed);
+    }
+}
+
+fn count(reason: Rejection) {
+    if ACTIVE.load(Ordering::Relaxed) {
+        COUNTS[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum Stage {
+    /// Chain rows, before the chain is split by underlying and expiry.
+    Row,
+    Strike,
+    Pair,
+    /// Built spreads, under the result filters.
+    Result,
+}
+
+#[derive(Serialize, Debug)]
+struct Event<'a> {
+    stage: Stage,
+    underlying_key: &'a str,
+    expiry: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strike: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sell_strike: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    buy_strike: Option<f64>,
+    reason: Rejection,
+}
+
+/// Tallies `event` and, under `debug`, logs it.
+fn emit(debug: bool, event: Event) {
+    count(event.reason);
+    if !debug {
+        return;
+    }
+    if let Ok(json) = serde_json::to_string(&event) {
+        log::info!(target: "trace", "{}", json);
+    }
+}
+
+pub fn row(debug: bool, underlying_key: &str, expiry: &str, strike: f64, reason: Rejection) {
+    emit(
+        debug,
+        Event {
+            stage: Stage::Row,
+            underlying_key,
+            expiry,
+            strike: Some(strike),
+            sell_strike: None,
+            buy_strike: None,
+            reason,
+        },
+    );
+}
+
+pub fn strike(debug: bool, underlying_key: &str, expiry: &str, strike: f64, reason: Rejection) {
+    emit(
+        debug,
+        Event {
+            stage: Stage::Strike,
+            underlying_key,
+            expiry,
+            strike: Some(strike),
+            sell_strike: None,
+            buy_strike: None,
+            reason,
+        },
+    );
+}
+
+pub fn pair(
+    debug: bool,
+    underlying_key: &str,
+    expiry: &str,
+    sell_strike: f64,
+    buy_strike: f64,
+    reason: Rejection,
+) {
+    emit(
+        debug,
+        Event {
+            stage: Stage::Pair,
+            underlying_key,
+            expiry,
+            strike: None,
+            sell_strike: Some(sell_strike),
+            buy_strike: Some(buy_strike),
+            reason,
+        },
+    );
+}
+
+pub fn result(
+    debug: bool,
+    underlying_key: &str,
+    expiry: &str,
+    sell_strike: f64,
+    buy_strike: f64,
+    reason: Rejection,
+) {
+    emit(
+        debug,
+        Event {
+            stage: Stage::Result,
+            underlying_key,
+            expiry,
+            strike: None,
+            sell_strike: Some(sell_strike),
+            buy_strike: Some(buy_strike),
+            reason,
+        },
+    );
+}
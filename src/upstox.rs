@@ -0,0 +1,57 @@
+//! Raw Upstox `/v2/option/chain` responses.
+//!
+//! The rows inside `data` already use the crate's field names, and fields the
+//! crate doesn't read, like `pcr` and the greeks' `pop`, are ignored. This
+//! unwraps the `{ status, data }` envelope and turns an error response into
+//! the broker's message.
+
+use crate::source::{self, ChainSource};
+use crate::{chain, Instrument};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    status: String,
+    #[serde(default)]
+    data: Option<Vec<Instrument>>,
+    #[serde(default)]
+    errors: Vec<ApiError>,
+}
+
+pub fn parse(payload: &str) -> Result<Vec<Instrument>, String> {
+    let response = serde_json::from_str::<Response>(payload).map_err(chain::json_error)?;
+    if response.status != "success" {
+        let messages: Vec<&str> = response
+            .errors
+            .iter()
+            .map(|error| error.message.as_str())
+            .filter(|message| !message.is_empty())
+            .collect();
+        return Err(if messages.is_empty() {
+            String::from("Upstox returned an error response")
+        } else {
+            format!("Upstox returned an error: {}", messages.join("; "))
+        });
+    }
+    response
+        .data
+        .ok_or_else(|| String::from("Upstox response has no data"))
+}
+
+pub struct UpstoxSource;
+
+impl ChainSource for UpstoxSource {
+    fn format(&self) -> &'static str {
+        "upstox"
+    }
+
+    fn parse(&self, data: &serde_json::Value) -> Result<Vec<Instrument>, String> {
+        parse(&source::json_text(data))
+    }
+}
@@ -0,0 +1,203 @@
+//! Row-level checks on an option chain.
+//!
+//! The scanners skip rows they can't use without saying why. Validation
+//! reports every problem instead, with the row index, and returns the chain
+//! with the unusable parts removed: malformed and duplicate rows are dropped,
+//! negative prices are cleared, and zero bid/ask pairs, which the feed sends
+//! for strikes with no quotes, are cleared so they read as missing. Strikes
+//! with no volume today are reported but kept.
+
+use crate::greeks::OptionKind;
+use crate::{chain, dates, Instrument, MarketData, OptionData};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum ChainIssue {
+    /// The row doesn't match the chain format; it is dropped.
+    MalformedRow,
+    /// Same underlying, expiry and strike as an earlier row; it is dropped.
+    DuplicateStrike,
+    /// The expiry couldn't be parsed into a date.
+    InvalidExpiry,
+    /// The option side has no `market_data`.
+    MissingMarketData,
+    /// Bid and ask are both zero or absent.
+    StaleQuote,
+    /// No volume today and the LTP is still the previous close.
+    Untraded,
+    /// A price field is negative; it is cleared.
+    NegativePrice,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct RowIssue {
+    /// Index of the row in the input array.
+    row: usize,
+    strike: Option<f64>,
+    /// The option side the issue is on, if it is specific to one.
+    option_type: Option<OptionKind>,
+    issue: ChainIssue,
+    /// Parser error for malformed rows, or the offending field.
+    detail: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ChainValidation {
+    rows: usize,
+    /// Rows kept in `chain`.
+    valid_rows: usize,
+    issues: Vec<RowIssue>,
+    chain: Vec<Instrument>,
+}
+
+fn issue(
+    row: usize,
+    strike: Option<f64>,
+    option_type: Option<OptionKind>,
+    issue: ChainIssue,
+) -> RowIssue {
+    RowIssue {
+        row,
+        strike,
+        option_type,
+        issue,
+        detail: None,
+    }
+}
+
+/// Bid and ask are both zero or absent.
+pub fn no_quotes(market_data: &MarketData) -> bool {
+    let empty = |price: Option<f64>| price.is_none_or(|price| price == 0.0);
+    empty(market_data.bid_price) && empty(market_data.ask_price)
+}
+
+/// Nothing has traded this session: no volume, and the LTP is missing or
+/// unchanged from `close_price`.
+pub fn untraded(market_data: &MarketData) -> bool {
+    market_data.volume.is_none_or(|volume| volume == 0)
+        && (market_data.ltp.is_none() || market_data.ltp == market_data.close_price)
+}
+
+/// Either no quotes or no trades today.
+pub fn is_stale(market_data: &MarketData) -> bool {
+    no_quotes(market_data) || untraded(market_data)
+}
+
+// Clears negative prices and empty bid/ask pairs, returning what was found
+fn clean_market_data(market_data: &mut MarketData) -> Vec<(ChainIssue, Option<String>)> {
+    let mut found = Vec::new();
+    for (field, price) in [
+        ("ltp", &mut market_data.ltp),
+        ("close_price", &mut market_data.close_price),
+        ("bid_price", &mut market_data.bid_price),
+        ("ask_price", &mut market_data.ask_price),
+    ] {
+        if price.is_some_and(|price| price < 0.0) {
+            *price = None;
+            found.push((ChainIssue::NegativePrice, Some(String::from(field))));
+        }
+    }
+
+    if no_quotes(market_data) {
+        market_data.bid_price = None;
+        market_data.ask_price = None;
+        found.push((ChainIssue::StaleQuote, None));
+    }
+    if untraded(market_data) {
+        found.push((ChainIssue::Untraded, None));
+    }
+    found
+}
+
+fn check_option(
+    row: usize,
+    strike: f64,
+    kind: OptionKind,
+    option: &mut OptionData,
+    issues: &mut Vec<RowIssue>,
+) {
+    let Some(market_data) = option.market_data.as_mut() else {
+        issues.push(issue(
+            row,
+            Some(strike),
+            Some(kind),
+            ChainIssue::MissingMarketData,
+        ));
+        return;
+    };
+    for (found, detail) in clean_market_data(market_data) {
+        issues.push(RowIssue {
+            detail,
+            ..issue(row, Some(strike), Some(kind), found)
+        });
+    }
+}
+
+pub fn validate(optionchain: &str) -> Result<ChainValidation, String> {
+    let rows = serde_json::from_str::<Vec<serde_json::Value>>(optionchain)
+        .map_err(|_| String::from("Failed to parse JSON"))?;
+
+    let mut issues = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut cleaned = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        let mut instrument = match serde_json::from_value::<Instrument>(row.clone()) {
+            Ok(instrument) => instrument,
+            Err(err) => {
+                let strike = row.get("strike_price").and_then(|s| s.as_f64());
+                issues.push(RowIssue {
+                    detail: Some(err.to_string()),
+                    ..issue(index, strike, None, ChainIssue::MalformedRow)
+                });
+                continue;
+            }
+        };
+        if let Some(iso) = dates::normalize_expiry(&instrument.expiry) {
+            instrument.expiry = iso;
+        }
+        let strike = instrument.strike_price;
+
+        let key = (
+            instrument.underlying_key.clone(),
+            instrument.expiry.clone(),
+            strike.to_bits(),
+        );
+        if !seen.insert(key) {
+            issues.push(issue(
+                index,
+                Some(strike),
+                None,
+                ChainIssue::DuplicateStrike,
+            ));
+            continue;
+        }
+        if !chain::has_valid_expiry(&instrument) {
+            issues.push(issue(index, Some(strike), None, ChainIssue::InvalidExpiry));
+        }
+
+        for kind in [OptionKind::Call, OptionKind::Put] {
+            let option = match kind {
+                OptionKind::Call => instrument.call_options.as_mut(),
+                OptionKind::Put => instrument.put_options.as_mut(),
+            };
+            if let Some(option) = option {
+                check_option(index, strike, kind, option, &mut issues);
+            }
+        }
+        cleaned.push(instrument);
+    }
+
+    Ok(ChainValidation {
+        rows: rows.len(),
+        valid_rows: cleaned.len(),
+        issues,
+        chain: cleaned,
+    })
+}
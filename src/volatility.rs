@@ -0,0 +1,512 @@
+//! Volatility-derived views of the chain.
+
+use crate::futures::{self, FuturesQuote};
+use crate::{chain, dates, greeks, greeks::OptionKind, Instrument};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use tsify_next::Tsify;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ExpectedMove {
+    underlying_key: String,
+    expiry: String,
+    dte: Option<i64>,
+    atm_strike: f64,
+    spot: f64,
+    /// ATM call plus put premium, the market's expected move to expiry.
+    straddle_price: f64,
+    expected_move_percentage: f64,
+    lower: f64,
+    upper: f64,
+    /// Expected move scaled to the requested horizon by the square root of time.
+    horizon_days: Option<f64>,
+    horizon_move: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct AtmStrike {
+    underlying_key: String,
+    expiry: String,
+    spot: f64,
+    /// Listed strike nearest spot.
+    atm_strike: f64,
+    call_ltp: Option<f64>,
+    put_ltp: Option<f64>,
+    /// Call plus put LTP at `atm_strike`; `None` unless both are priced.
+    straddle_price: Option<f64>,
+    /// The expiry's future and its premium over spot, when quoted.
+    futures_price: Option<f64>,
+    basis: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct IvStats {
+    current_iv: f64,
+    low: f64,
+    high: f64,
+    observations: usize,
+    /// Where `current_iv` sits between the period low (0) and high (100);
+    /// `None` when the history is flat.
+    iv_rank: Option<f64>,
+    /// Percent of historical observations below `current_iv`.
+    iv_percentile: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct SkewPoint {
+    strike: f64,
+    /// Percent, as in the feed.
+    iv: f64,
+    delta: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct VolSkew {
+    underlying_key: String,
+    expiry: String,
+    spot: f64,
+    atm_strike: f64,
+    atm_iv: Option<f64>,
+    calls: Vec<SkewPoint>,
+    puts: Vec<SkewPoint>,
+    /// Least-squares slope of out-of-the-money IV against strike distance from
+    /// spot, in vol points per 1% of spot. Negative when downside puts are richer.
+    skew_slope: Option<f64>,
+    /// 25-delta call IV minus 25-delta put IV, interpolated on delta.
+    risk_reversal_25d: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "snake_case")]
+pub enum TermShape {
+    /// Longer expiries carry higher IV.
+    Contango,
+    /// Near expiries carry higher IV, typical around events.
+    Backwardation,
+    Flat,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct TermPoint {
+    expiry: String,
+    dte: i64,
+    atm_strike: f64,
+    atm_iv: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct TermStructure {
+    underlying_key: String,
+    points: Vec<TermPoint>,
+    /// Least-squares slope of ATM IV against days to expiry, in vol points per day.
+    slope: Option<f64>,
+    shape: Option<TermShape>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct ChainSnapshot {
+    /// Epoch milliseconds the chain was captured at.
+    timestamp: f64,
+    optionchain: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StraddleHistoryParams {
+    snapshots: Vec<ChainSnapshot>,
+    /// Only track this expiry; otherwise every expiry found is tracked.
+    #[serde(default)]
+    expiry: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StraddlePoint {
+    timestamp: f64,
+    spot: f64,
+    /// Can move between snapshots as spot does.
+    atm_strike: f64,
+    straddle_price: f64,
+    implied_move_percentage: f64,
+    hours_to_expiry: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StraddleDecay {
+    first_price: f64,
+    last_price: f64,
+    /// Last minus first; negative as premium decays.
+    change: f64,
+    change_percentage: f64,
+    high: f64,
+    low: f64,
+    elapsed_hours: f64,
+    /// Average premium lost per hour; negative if the straddle gained.
+    decay_per_hour: Option<f64>,
+    /// Spot's move over the period as a fraction of the first straddle
+    /// price, i.e. how much of the initially implied move has been realized.
+    realized_move_ratio: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+pub struct StraddleHistory {
+    underlying_key: String,
+    expiry: String,
+    /// In time order.
+    points: Vec<StraddlePoint>,
+    /// `None` with fewer than two points.
+    decay: Option<StraddleDecay>,
+}
+
+fn ltp(instrument: &Instrument, kind: OptionKind) -> Option<f64> {
+    chain::option(instrument, kind)?.market_data.as_ref()?.ltp
+}
+
+/// The ATM strike among those with both sides priced, and its call plus put LTP.
+fn atm_straddle(instruments: Vec<Instrument>) -> Option<(Instrument, f64)> {
+    let priced: Vec<Instrument> = instruments
+        .into_iter()
+        .filter(|i| ltp(i, OptionKind::Call).is_some() && ltp(i, OptionKind::Put).is_some())
+        .collect();
+    let atm = chain::atm(&priced)?;
+    let straddle_price = ltp(atm, OptionKind::Call)? + ltp(atm, OptionKind::Put)?;
+    Some((atm.clone(), straddle_price))
+}
+
+/// The ATM strike and its straddle for every underlying and expiry in the
+/// chain, with the basis of any future in `futures` on the same expiry.
+pub fn atm_strikes(optionchain: &str, futures: &[FuturesQuote]) -> Result<Vec<AtmStrike>, String> {
+    let instruments = chain::parse(optionchain)?;
+
+    Ok(chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    })
+    .into_values()
+    .filter_map(|instruments| {
+        let atm = chain::atm(&instruments)?;
+        let call_ltp = ltp(atm, OptionKind::Call);
+        let put_ltp = ltp(atm, OptionKind::Put);
+        let future = futures::find(futures, &atm.underlying_key, &atm.expiry);
+        Some(AtmStrike {
+            underlying_key: atm.underlying_key.clone(),
+            expiry: atm.expiry.clone(),
+            spot: atm.underlying_spot_price,
+            atm_strike: atm.strike_price,
+            call_ltp,
+            put_ltp,
+            straddle_price: call_ltp.zip(put_ltp).map(|(call, put)| call + put),
+            futures_price: future.map(|future| future.price),
+            basis: future.map(|future| future.basis(atm.underlying_spot_price)),
+        })
+    })
+    .collect())
+}
+
+/// Straddle-implied expected move for every underlying and expiry in the chain.
+pub fn expected_move(
+    optionchain: &str,
+    horizon_days: Option<f64>,
+    now_ms: f64,
+) -> Result<Vec<ExpectedMove>, String> {
+    let instruments = chain::parse(optionchain)?;
+
+    Ok(chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    })
+    .into_values()
+    .filter_map(|instruments| {
+        let (atm, straddle_price) = atm_straddle(instruments)?;
+        let spot = atm.underlying_spot_price;
+        let dte = dates::days_to_expiry(&atm.expiry, now_ms);
+
+        let horizon_move = horizon_days.zip(dte).map(|(horizon, dte)| {
+            if dte > 0 {
+                straddle_price * (horizon / dte as f64).clamp(0.0, 1.0).sqrt()
+            } else {
+                straddle_price
+            }
+        });
+
+        Some(ExpectedMove {
+            underlying_key: atm.underlying_key.clone(),
+            expiry: atm.expiry.clone(),
+            dte,
+            atm_strike: atm.strike_price,
+            spot,
+            straddle_price,
+            expected_move_percentage: straddle_price / spot * 100.0,
+            lower: spot - straddle_price,
+            upper: spot + straddle_price,
+            horizon_days,
+            horizon_move,
+        })
+    })
+    .collect())
+}
+
+/// IV rank and percentile of `current_iv` against a history of IVs, usually a
+/// year of daily closes. Non-finite values in the history are ignored.
+pub fn iv_stats(historical_ivs: &[f64], current_iv: f64) -> Result<IvStats, String> {
+    let history: Vec<f64> = historical_ivs
+        .iter()
+        .copied()
+        .filter(|iv| iv.is_finite())
+        .collect();
+    if history.is_empty() {
+        return Err(String::from("No historical IVs"));
+    }
+
+    let low = history.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = history.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let below = history.iter().filter(|iv| **iv < current_iv).count();
+
+    Ok(IvStats {
+        current_iv,
+        low,
+        high,
+        observations: history.len(),
+        iv_rank: (high > low)
+            .then(|| ((current_iv - low) / (high - low) * 100.0).clamp(0.0, 100.0)),
+        iv_percentile: below as f64 / history.len() as f64 * 100.0,
+    })
+}
+
+fn iv(instrument: &Instrument, kind: OptionKind) -> Option<f64> {
+    chain::option(instrument, kind)?
+        .option_greeks
+        .as_ref()?
+        .iv
+        .filter(|iv| *iv > 0.0)
+}
+
+/// Mean of the call and put IV at a strike, or whichever side is quoted.
+fn atm_iv(atm: &Instrument) -> Option<f64> {
+    match (iv(atm, OptionKind::Call), iv(atm, OptionKind::Put)) {
+        (Some(call), Some(put)) => Some(0.5 * (call + put)),
+        (call, put) => call.or(put),
+    }
+}
+
+fn skew_points(instruments: &[Instrument], kind: OptionKind) -> Vec<SkewPoint> {
+    let mut points: Vec<SkewPoint> = instruments
+        .iter()
+        .filter_map(|instrument| {
+            Some(SkewPoint {
+                strike: instrument.strike_price,
+                iv: iv(instrument, kind)?,
+                delta: chain::option(instrument, kind)?
+                    .option_greeks
+                    .as_ref()?
+                    .delta,
+            })
+        })
+        .collect();
+    points.sort_by(|a, b| {
+        a.strike
+            .partial_cmp(&b.strike)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    points
+}
+
+/// IV at an absolute delta of `target`, interpolated between the two
+/// neighbouring strikes.
+fn iv_at_delta(points: &[SkewPoint], target: f64) -> Option<f64> {
+    let mut by_delta: Vec<(f64, f64)> = points
+        .iter()
+        .filter_map(|p| Some((p.delta?.abs(), p.iv)))
+        .collect();
+    by_delta.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    by_delta.windows(2).find_map(|pair| {
+        let ((d0, iv0), (d1, iv1)) = (pair[0], pair[1]);
+        if target < d0 || target > d1 {
+            return None;
+        }
+        if d1 == d0 {
+            return Some(iv0);
+        }
+        Some(iv0 + (iv1 - iv0) * (target - d0) / (d1 - d0))
+    })
+}
+
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// IV smile per underlying and expiry. Missing IVs and deltas are solved from
+/// prices first.
+pub fn iv_skew(optionchain: &str, now_ms: f64) -> Result<Vec<VolSkew>, String> {
+    let mut instruments = chain::parse(optionchain)?;
+    greeks::fill_missing(&mut instruments, now_ms);
+
+    Ok(chain::group_by(instruments, |instrument| {
+        (instrument.underlying_key.clone(), instrument.expiry.clone())
+    })
+    .into_values()
+    .filter_map(|instruments| {
+        let atm = chain::atm(&instruments)?;
+        let spot = atm.underlying_spot_price;
+        let calls = skew_points(&instruments, OptionKind::Call);
+        let puts = skew_points(&instruments, OptionKind::Put);
+
+        // Each side of the smile is read from its out-of-the-money options
+        let otm: Vec<(f64, f64)> = puts
+            .iter()
+            .filter(|p| p.strike < spot)
+            .chain(calls.iter().filter(|p| p.strike >= spot))
+            .map(|p| ((p.strike / spot - 1.0) * 100.0, p.iv))
+            .collect();
+
+        let risk_reversal_25d = iv_at_delta(&calls, 0.25)
+            .zip(iv_at_delta(&puts, 0.25))
+            .map(|(call, put)| call - put);
+
+        Some(VolSkew {
+            underlying_key: atm.underlying_key.clone(),
+            expiry: atm.expiry.clone(),
+            spot,
+            atm_strike: atm.strike_price,
+            atm_iv: atm_iv(atm),
+            skew_slope: slope(&otm),
+            risk_reversal_25d,
+            calls,
+            puts,
+        })
+    })
+    .collect())
+}
+
+/// ATM IV per unexpired expiry for each underlying, nearest expiry first.
+pub fn term_structure(optionchain: &str, now_ms: f64) -> Result<Vec<TermStructure>, String> {
+    let mut instruments = chain::parse(optionchain)?;
+    greeks::fill_missing(&mut instruments, now_ms);
+
+    Ok(
+        chain::group_by(instruments, |instrument| instrument.underlying_key.clone())
+            .into_iter()
+            .map(|(underlying_key, instruments)| {
+                let points: Vec<TermPoint> =
+                    chain::group_by(instruments, |instrument| instrument.expiry.clone())
+                        .into_values()
+                        .filter_map(|instruments| {
+                            let atm = chain::atm(&instruments)?;
+                            let dte = dates::days_to_expiry(&atm.expiry, now_ms)
+                                .filter(|days| *days >= 0)?;
+                            Some(TermPoint {
+                                expiry: atm.expiry.clone(),
+                                dte,
+                                atm_strike: atm.strike_price,
+                                atm_iv: atm_iv(atm)?,
+                            })
+                        })
+                        .collect();
+
+                let slope = slope(
+                    &points
+                        .iter()
+                        .map(|p| (p.dte as f64, p.atm_iv))
+                        .collect::<Vec<_>>(),
+                );
+                let shape = slope.map(|slope| {
+                    if slope > 0.0 {
+                        TermShape::Contango
+                    } else if slope < 0.0 {
+                        TermShape::Backwardation
+                    } else {
+                        TermShape::Flat
+                    }
+                });
+
+                TermStructure {
+                    underlying_key,
+                    points,
+                    slope,
+                    shape,
+                }
+            })
+            .collect(),
+    )
+}
+
+fn decay(points: &[StraddlePoint]) -> Option<StraddleDecay> {
+    if points.len() < 2 {
+        return None;
+    }
+    let (first, last) = (points.first()?, points.last()?);
+    let change = last.straddle_price - first.straddle_price;
+    let elapsed_hours = (last.timestamp - first.timestamp) / 3_600_000.0;
+    let prices = points.iter().map(|p| p.straddle_price);
+    Some(StraddleDecay {
+        first_price: first.straddle_price,
+        last_price: last.straddle_price,
+        change,
+        change_percentage: change / first.straddle_price * 100.0,
+        high: prices.clone().fold(f64::NEG_INFINITY, f64::max),
+        low: prices.fold(f64::INFINITY, f64::min),
+        elapsed_hours,
+        decay_per_hour: (elapsed_hours > 0.0)
+            .then(|| (first.straddle_price - last.straddle_price) / elapsed_hours),
+        realized_move_ratio: (last.spot - first.spot).abs() / first.straddle_price,
+    })
+}
+
+/// The ATM straddle in each snapshot, tracked per underlying and expiry.
+pub fn straddle_history(params: &StraddleHistoryParams) -> Result<Vec<StraddleHistory>, String> {
+    let mut series: BTreeMap<(String, String), Vec<StraddlePoint>> = BTreeMap::new();
+    for snapshot in &params.snapshots {
+        let mut instruments = chain::parse(&snapshot.optionchain)?;
+        if let Some(expiry) = &params.expiry {
+            instruments.retain(|instrument| dates::same_expiry(&instrument.expiry, expiry));
+        }
+        let slices = chain::group_by(instruments, |instrument| {
+            (instrument.underlying_key.clone(), instrument.expiry.clone())
+        });
+        for (key, instruments) in slices {
+            let Some((atm, straddle_price)) = atm_straddle(instruments) else {
+                continue;
+            };
+            let spot = atm.underlying_spot_price;
+            series.entry(key).or_default().push(StraddlePoint {
+                timestamp: snapshot.timestamp,
+                spot,
+                atm_strike: atm.strike_price,
+                straddle_price,
+                implied_move_percentage: straddle_price / spot * 100.0,
+                hours_to_expiry: dates::hours_to_expiry(&atm.expiry, snapshot.timestamp),
+            });
+        }
+    }
+
+    Ok(series
+        .into_iter()
+        .map(|((underlying_key, expiry), mut points)| {
+            points.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+            StraddleHistory {
+                underlying_key,
+                expiry,
+                decay: decay(&points),
+                points,
+            }
+        })
+        .collect())
+}